@@ -0,0 +1,272 @@
+use crate::cloudflare::{concurrency_limit, CloudflareClient};
+use crate::expression::{self, SetItem};
+use crate::models::*;
+use futures::stream::{self, StreamExt};
+use std::collections::HashSet;
+use std::net::IpAddr;
+
+// IP 访问规则支持的模式
+const MODES: &[&str] = &["block", "challenge", "js_challenge", "managed_challenge", "whitelist"];
+
+// CSV 表头中可能出现的首列名称
+const HEADER_NAMES: &[&str] = &["ip", "value", "address", "cidr", "target"];
+
+// 规则所在范围：zones/{id} 或 accounts/{id}
+pub fn scope(zone_id: Option<&str>, account_id: Option<&str>) -> Result<String, String> {
+    match (zone_id, account_id) {
+        (Some(zone_id), None) => Ok(format!("zones/{}", zone_id)),
+        (None, Some(account_id)) => Ok(format!("accounts/{}", account_id)),
+        _ => Err("Exactly one of zone_id or account_id is required".to_string()),
+    }
+}
+
+pub(crate) fn network(ip: IpAddr, prefix: u8) -> IpAddr {
+    match ip {
+        IpAddr::V4(v4) => {
+            let mask = if prefix == 0 { 0 } else { u32::MAX << (32 - prefix as u32) };
+            IpAddr::from(std::net::Ipv4Addr::from(u32::from(v4) & mask))
+        }
+        IpAddr::V6(v6) => {
+            let mask = if prefix == 0 { 0 } else { u128::MAX << (128 - prefix as u32) };
+            IpAddr::from(std::net::Ipv6Addr::from(u128::from(v6) & mask))
+        }
+    }
+}
+
+// 根据值推断目标类型：IP、CIDR、ASN（AS 前缀）或两位国家代码
+pub fn classify(value: &str) -> Result<AccessRuleConfiguration, String> {
+    let value = value.trim();
+    let config = |target: &str, value: String| Ok(AccessRuleConfiguration { target: target.to_string(), value });
+
+    if let Some(item) = expression::parse_ip(value) {
+        return match item {
+            SetItem::Ip(ip) => config(if ip.is_ipv4() { "ip" } else { "ip6" }, ip.to_string()),
+            SetItem::Cidr(ip, prefix) => match (ip.is_ipv4(), prefix) {
+                (true, 32) => config("ip", ip.to_string()),
+                (false, 128) => config("ip6", ip.to_string()),
+                // Cloudflare 仅支持 IPv4 /16、/24 与 IPv6 /32、/48、/64
+                (true, 16 | 24) | (false, 32 | 48 | 64) => {
+                    config("ip_range", format!("{}/{}", network(ip, prefix), prefix))
+                }
+                (true, _) => Err(format!("{}: IPv4 ranges must be /16 or /24", value)),
+                (false, _) => Err(format!("{}: IPv6 ranges must be /32, /48 or /64", value)),
+            },
+            _ => Err(format!("{}: unsupported address", value)),
+        };
+    }
+
+    if let Some(number) = value.strip_prefix("AS").or_else(|| value.strip_prefix("as")) {
+        if !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) {
+            return config("asn", format!("AS{}", number));
+        }
+    }
+
+    if value.len() == 2 && value.chars().all(|c| c.is_ascii_alphanumeric()) && value.chars().next().is_some_and(|c| c.is_ascii_alphabetic()) {
+        return config("country", value.to_ascii_uppercase());
+    }
+
+    Err(format!("{}: expected an IP address, CIDR range, ASN (AS123) or country code", value))
+}
+
+// 校验模式并规范化目标；显式给出的 target 必须与值一致
+pub fn validate(rule: &AccessRule) -> Result<AccessRule, String> {
+    if !MODES.contains(&rule.mode.as_str()) {
+        return Err(format!(
+            "Unsupported mode '{}' (expected one of: {})",
+            rule.mode,
+            MODES.join(", ")
+        ));
+    }
+
+    let configuration = classify(&rule.configuration.value)?;
+    if !rule.configuration.target.is_empty() && rule.configuration.target != configuration.target {
+        return Err(format!(
+            "{}: target is {}, not {}",
+            rule.configuration.value, configuration.target, rule.configuration.target
+        ));
+    }
+
+    let mut rule = rule.clone();
+    rule.id = None;
+    rule.scope = None;
+    rule.configuration = configuration;
+    Ok(rule)
+}
+
+pub async fn list_rules(client: &CloudflareClient, request: &ListAccessRulesRequest) -> Result<Vec<AccessRule>, String> {
+    let scope = scope(request.zone_id.as_deref(), request.account_id.as_deref())?;
+
+    let mut filters = Vec::new();
+    if let Some(mode) = &request.mode {
+        filters.push(("mode", mode.clone()));
+    }
+    if let Some(target) = &request.target {
+        filters.push(("configuration.target", target.clone()));
+    }
+    if let Some(notes) = &request.notes {
+        filters.push(("notes", notes.clone()));
+    }
+
+    client.list_access_rules(&scope, &filters).await
+}
+
+pub async fn create_rule(client: &CloudflareClient, request: &CreateAccessRuleRequest) -> Result<AccessRule, String> {
+    let scope = scope(request.zone_id.as_deref(), request.account_id.as_deref())?;
+    let rule = validate(&request.rule)?;
+    client.create_access_rule(&scope, &rule).await
+}
+
+pub async fn update_rule(client: &CloudflareClient, request: &UpdateAccessRuleRequest) -> Result<AccessRule, String> {
+    let scope = scope(request.zone_id.as_deref(), request.account_id.as_deref())?;
+    if !MODES.contains(&request.mode.as_str()) {
+        return Err(format!("Unsupported mode '{}'", request.mode));
+    }
+    client.update_access_rule(&scope, &request.rule_id, &request.mode, &request.notes).await
+}
+
+pub async fn delete_rule(client: &CloudflareClient, request: &DeleteAccessRuleRequest) -> Result<String, String> {
+    let scope = scope(request.zone_id.as_deref(), request.account_id.as_deref())?;
+    client.delete_access_rule(&scope, &request.rule_id).await
+}
+
+fn unquote(field: &str) -> String {
+    let field = field.trim();
+    field.strip_prefix('"')
+        .and_then(|f| f.strip_suffix('"'))
+        .map(|f| f.replace("\"\"", "\""))
+        .unwrap_or_else(|| field.to_string())
+}
+
+// 解析导入内容：纯文本每行一个值（允许 # 注释），CSV 首列为值、其余列合并为备注
+fn parse_import(content: &str, mode: &str, default_notes: Option<&str>) -> (Vec<AccessRule>, Vec<String>) {
+    let mut rules = Vec::new();
+    let mut errors = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let (value, notes) = match line.split_once([',', ';', '\t']) {
+            Some((value, notes)) => (unquote(value), Some(unquote(notes)).filter(|n| !n.is_empty())),
+            None => (unquote(line), None),
+        };
+
+        if rules.is_empty() && errors.is_empty() && HEADER_NAMES.contains(&value.to_ascii_lowercase().as_str()) {
+            continue;
+        }
+
+        match classify(&value) {
+            Ok(configuration) => rules.push(AccessRule {
+                id: None,
+                mode: mode.to_string(),
+                configuration,
+                notes: notes.or_else(|| default_notes.map(str::to_string)),
+                scope: None,
+                created_on: None,
+                modified_on: None,
+            }),
+            Err(e) => errors.push(format!("Line {}: {}", index + 1, e)),
+        }
+    }
+
+    (rules, errors)
+}
+
+// 批量导入；已存在相同目标的规则与重复行会被跳过
+pub async fn import(client: &CloudflareClient, request: &ImportAccessRulesRequest) -> Result<AccessRuleImportReport, String> {
+    let scope = scope(request.zone_id.as_deref(), request.account_id.as_deref())?;
+    if !MODES.contains(&request.mode.as_str()) {
+        return Err(format!("Unsupported mode '{}'", request.mode));
+    }
+
+    let (parsed, errors) = parse_import(&request.content, &request.mode, request.notes.as_deref());
+    let mut report = AccessRuleImportReport { parsed: Vec::new(), created: Vec::new(), skipped: Vec::new(), errors };
+
+    let existing = client.list_access_rules(&scope, &[]).await?;
+    let mut seen: HashSet<(String, String)> = existing.into_iter()
+        .map(|r| (r.configuration.target, r.configuration.value))
+        .collect();
+
+    for rule in parsed {
+        let key = (rule.configuration.target.clone(), rule.configuration.value.clone());
+        if seen.insert(key) {
+            report.parsed.push(rule);
+        } else {
+            report.skipped.push(rule.configuration.value);
+        }
+    }
+
+    if request.dry_run {
+        return Ok(report);
+    }
+
+    let results: Vec<_> = stream::iter(report.parsed.iter())
+        .map(|rule| {
+            let scope = &scope;
+            async move { (rule, client.create_access_rule(scope, rule).await) }
+        })
+        .buffered(concurrency_limit(None))
+        .collect()
+        .await;
+
+    for (rule, result) in results {
+        match result {
+            Ok(created) => report.created.push(created),
+            Err(e) => report.errors.push(format!("{}: {}", rule.configuration.value, e)),
+        }
+    }
+
+    log::info!("Imported {} access rule(s) into {}", report.created.len(), scope);
+
+    Ok(report)
+}
+
+// 按 IP（精确匹配或 CIDR 包含）或文本查找规则
+pub async fn search(client: &CloudflareClient, request: &SearchAccessRulesRequest) -> Result<Vec<AccessRuleMatch>, String> {
+    let scope = scope(request.zone_id.as_deref(), request.account_id.as_deref())?;
+
+    let ip: Option<IpAddr> = match request.ip.as_deref().map(str::trim) {
+        Some(ip) => Some(ip.parse().map_err(|_| format!("Invalid IP address: {}", ip))?),
+        None => None,
+    };
+    let query = request.query.as_deref().map(|q| q.trim().to_lowercase()).filter(|q| !q.is_empty());
+
+    if ip.is_none() && query.is_none() {
+        return Err("Either ip or query is required".to_string());
+    }
+
+    let rules = client.list_access_rules(&scope, &[]).await?;
+    let mut matches = Vec::new();
+
+    for rule in rules {
+        let config = &rule.configuration;
+        let match_type = match (&ip, config.target.as_str()) {
+            (Some(ip), "ip" | "ip6") if config.value.parse::<IpAddr>().ok() == Some(*ip) => Some("exact"),
+            (Some(ip), "ip_range") if expression::parse_ip(&config.value).is_some_and(|item| expression::ip_in(ip, &item)) => {
+                Some("cidr")
+            }
+            _ => None,
+        };
+
+        let match_type = match_type.or_else(|| {
+            let query = query.as_ref()?;
+            let notes = rule.notes.as_deref().unwrap_or_default().to_lowercase();
+            (config.value.to_lowercase().contains(query) || notes.contains(query)).then_some("text")
+        });
+
+        if let Some(match_type) = match_type {
+            matches.push(AccessRuleMatch { rule, match_type: match_type.to_string() });
+        }
+    }
+
+    // 精确匹配优先，其次 CIDR 包含，最后文本匹配
+    matches.sort_by_key(|m| match m.match_type.as_str() {
+        "exact" => 0,
+        "cidr" => 1,
+        _ => 2,
+    });
+
+    Ok(matches)
+}
@@ -0,0 +1,388 @@
+use crate::cloudflare::{concurrency_limit, CloudflareClient};
+use crate::models::*;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
+use serde_json::json;
+
+// 审计规则目录（SARIF 输出中的 rules 也来自这里）
+struct AuditRule {
+    id: &'static str,
+    title: &'static str,
+    help: &'static str,
+}
+
+// 规则以具名常量引用，finding 无法指向目录外的规则 ID
+const SSL_MODE: AuditRule = AuditRule { id: "CFM001", title: "SSL mode is off or flexible", help: "Set SSL/TLS encryption mode to Full (strict) so traffic to the origin is encrypted and the origin certificate is validated." };
+const MIN_TLS_VERSION: AuditRule = AuditRule { id: "CFM002", title: "Minimum TLS version below 1.2", help: "Set min_tls_version to 1.2 or higher; TLS 1.0 and 1.1 are deprecated." };
+const HSTS: AuditRule = AuditRule { id: "CFM003", title: "HSTS not enabled", help: "Enable HTTP Strict Transport Security in the security_header setting once the site is fully served over HTTPS." };
+const ALWAYS_USE_HTTPS: AuditRule = AuditRule { id: "CFM004", title: "Always Use HTTPS disabled", help: "Enable always_use_https to redirect all HTTP requests to HTTPS." };
+const TLS_1_3: AuditRule = AuditRule { id: "CFM005", title: "TLS 1.3 disabled", help: "Enable tls_1_3 for faster and more secure handshakes." };
+const SECURITY_LEVEL: AuditRule = AuditRule { id: "CFM006", title: "Security level too low", help: "Set security_level to medium or higher." };
+const UNPROXIED_RECORD: AuditRule = AuditRule { id: "CFM007", title: "Unproxied record exposes origin", help: "Enable the Cloudflare proxy (orange cloud) on A/AAAA/CNAME records that serve web traffic, and rotate the origin IP if it was exposed." };
+const WILDCARD_RECORD: AuditRule = AuditRule { id: "CFM008", title: "Wildcard DNS record", help: "Replace wildcard records with explicit hostnames unless the wildcard is intentional." };
+const NO_DMARC: AuditRule = AuditRule { id: "CFM009", title: "No DMARC record", help: "Publish a TXT record at _dmarc.<zone> such as \"v=DMARC1; p=quarantine; rua=mailto:...\"." };
+const NO_SPF: AuditRule = AuditRule { id: "CFM010", title: "No SPF record", help: "Publish a TXT record at the zone apex starting with \"v=spf1\", or \"v=spf1 -all\" if the domain does not send mail." };
+const CERTIFICATE_EXPIRY: AuditRule = AuditRule { id: "CFM011", title: "Certificate expired or expiring soon", help: "Renew or replace the certificate before it expires." };
+const NO_FIREWALL_RULES: AuditRule = AuditRule { id: "CFM012", title: "No firewall rules", help: "Add firewall or WAF custom rules to block unwanted traffic." };
+const HTTPS_REWRITES: AuditRule = AuditRule { id: "CFM013", title: "Automatic HTTPS Rewrites disabled", help: "Enable automatic_https_rewrites to fix mixed content." };
+const BROWSER_CHECK: AuditRule = AuditRule { id: "CFM014", title: "Browser Integrity Check disabled", help: "Enable browser_check to challenge requests with suspicious headers." };
+
+const RULES: &[&AuditRule] = &[
+    &SSL_MODE,
+    &MIN_TLS_VERSION,
+    &HSTS,
+    &ALWAYS_USE_HTTPS,
+    &TLS_1_3,
+    &SECURITY_LEVEL,
+    &UNPROXIED_RECORD,
+    &WILDCARD_RECORD,
+    &NO_DMARC,
+    &NO_SPF,
+    &CERTIFICATE_EXPIRY,
+    &NO_FIREWALL_RULES,
+    &HTTPS_REWRITES,
+    &BROWSER_CHECK,
+];
+
+fn finding(r: &AuditRule, severity: Severity, detail: String, resource: Option<String>) -> AuditFinding {
+    AuditFinding {
+        rule_id: r.id.to_string(),
+        severity,
+        title: r.title.to_string(),
+        detail,
+        resource,
+        fix: r.help.to_string(),
+    }
+}
+
+// 审计所需的 Zone 数据，读取失败的部分为 None
+struct ZoneAuditData {
+    zone_name: String,
+    settings: Option<Vec<ZoneSetting>>,
+    dns_records: Option<Vec<DnsRecord>>,
+    certificates: Option<Vec<SslCertificate>>,
+    custom_certificates: Option<Vec<CustomCertificate>>,
+    firewall_rules: Option<Vec<FirewallRule>>,
+    waf_packages: Option<Vec<WafPackage>>,
+}
+
+// 审计单个 Zone
+pub async fn audit_zone(client: &CloudflareClient, zone_id: &str) -> Result<ZoneAuditReport, String> {
+    let zone = client.get_zone(zone_id).await?;
+
+    let (settings, dns, certs, custom, firewall, waf) = tokio::join!(
+        client.get_zone_settings(zone_id),
+        client.get_dns_records(zone_id),
+        client.get_ssl_certificates(zone_id),
+        client.get_custom_certificates(zone_id),
+        client.get_firewall_rules(zone_id),
+        client.get_waf_packages(zone_id),
+    );
+
+    let mut errors = Vec::new();
+    let data = ZoneAuditData {
+        zone_name: zone.name,
+        settings: keep("settings", settings, &mut errors),
+        dns_records: keep("dns_records", dns, &mut errors),
+        certificates: keep("ssl_certificates", certs, &mut errors),
+        custom_certificates: keep("custom_certificates", custom, &mut errors),
+        firewall_rules: keep("firewall_rules", firewall, &mut errors),
+        waf_packages: keep("waf_packages", waf, &mut errors),
+    };
+
+    let mut findings = evaluate(&data, Utc::now());
+    findings.sort_by(|a, b| a.severity.cmp(&b.severity).then(a.rule_id.cmp(&b.rule_id)));
+
+    let score = score(&findings);
+    Ok(ZoneAuditReport {
+        zone_id: zone_id.to_string(),
+        zone_name: data.zone_name,
+        score,
+        grade: grade(score).to_string(),
+        audited_at: Utc::now().to_rfc3339(),
+        findings,
+        errors,
+    })
+}
+
+// 并发审计多个 Zone（为空时审计所有 Zone）
+pub async fn audit_zones(client: &CloudflareClient, request: &AuditZonesRequest) -> Result<Vec<ZoneAuditReport>, String> {
+    let zone_ids = if request.zone_ids.is_empty() {
+        client.get_zones().await?.into_iter().map(|z| z.id).collect()
+    } else {
+        request.zone_ids.clone()
+    };
+
+    let reports: Vec<ZoneAuditReport> = stream::iter(zone_ids)
+        .map(|zone_id| async move {
+            match audit_zone(client, &zone_id).await {
+                Ok(report) => report,
+                Err(e) => ZoneAuditReport {
+                    zone_id,
+                    zone_name: String::new(),
+                    score: 0,
+                    grade: "N/A".to_string(),
+                    audited_at: Utc::now().to_rfc3339(),
+                    findings: Vec::new(),
+                    errors: vec![e],
+                },
+            }
+        })
+        .buffered(concurrency_limit(request.concurrency))
+        .collect()
+        .await;
+
+    Ok(reports)
+}
+
+fn keep<T>(name: &str, result: Result<T, String>, errors: &mut Vec<String>) -> Option<T> {
+    match result {
+        Ok(v) => Some(v),
+        Err(e) => {
+            errors.push(format!("{}: {}", name, e));
+            None
+        }
+    }
+}
+
+fn evaluate(data: &ZoneAuditData, now: DateTime<Utc>) -> Vec<AuditFinding> {
+    let mut findings = Vec::new();
+
+    if let Some(settings) = &data.settings {
+        check_settings(settings, &mut findings);
+    }
+    if let Some(records) = &data.dns_records {
+        check_dns(&data.zone_name, records, &mut findings);
+    }
+    if let Some(certificates) = &data.certificates {
+        for pack in certificates {
+            for cert in pack.certificates.iter().flatten() {
+                let hosts = pack.hosts.as_ref().map(|h| h.join(", ")).unwrap_or_else(|| pack.id.clone());
+                check_expiry(&cert.expires_on, &hosts, now, &mut findings);
+            }
+        }
+    }
+    if let Some(certificates) = &data.custom_certificates {
+        for cert in certificates {
+            check_expiry(&cert.expires_on, &cert.hosts.join(", "), now, &mut findings);
+        }
+    }
+    if let Some(rules) = &data.firewall_rules {
+        // 已部署 WAF 时不提示
+        let has_waf = data.waf_packages.as_ref()
+            .map(|p| p.iter().any(|pkg| pkg.detection_mode != "off"))
+            .unwrap_or(false);
+        if rules.iter().all(|r| r.paused) && !has_waf {
+            findings.push(finding(&NO_FIREWALL_RULES, Severity::Info, "The zone has no active firewall rules.".to_string(), None));
+        }
+    }
+
+    findings
+}
+
+fn setting<'a>(settings: &'a [ZoneSetting], id: &str) -> Option<&'a serde_json::Value> {
+    settings.iter().find(|s| s.id == id).map(|s| &s.value)
+}
+
+fn check_settings(settings: &[ZoneSetting], findings: &mut Vec<AuditFinding>) {
+    match setting(settings, "ssl").and_then(|v| v.as_str()) {
+        Some("off") => findings.push(finding(&SSL_MODE, Severity::Critical, "SSL is off; visitors cannot use HTTPS.".to_string(), Some("ssl".to_string()))),
+        Some("flexible") => findings.push(finding(&SSL_MODE, Severity::High, "SSL mode is flexible; traffic between Cloudflare and the origin is unencrypted.".to_string(), Some("ssl".to_string()))),
+        _ => {}
+    }
+
+    if let Some(version) = setting(settings, "min_tls_version").and_then(|v| v.as_str()) {
+        if version.parse::<f64>().map(|v| v < 1.2).unwrap_or(false) {
+            findings.push(finding(&MIN_TLS_VERSION, Severity::Medium, format!("Minimum TLS version is {}.", version), Some("min_tls_version".to_string())));
+        }
+    }
+
+    if let Some(header) = setting(settings, "security_header") {
+        if !header["strict_transport_security"]["enabled"].as_bool().unwrap_or(false) {
+            findings.push(finding(&HSTS, Severity::Medium, "HSTS is not enabled.".to_string(), Some("security_header".to_string())));
+        }
+    }
+
+    let checks = [
+        ("always_use_https", &ALWAYS_USE_HTTPS, Severity::Medium),
+        ("tls_1_3", &TLS_1_3, Severity::Low),
+        ("automatic_https_rewrites", &HTTPS_REWRITES, Severity::Low),
+        ("browser_check", &BROWSER_CHECK, Severity::Low),
+    ];
+    for (id, rule, severity) in checks {
+        if setting(settings, id).and_then(|v| v.as_str()) == Some("off") {
+            findings.push(finding(rule, severity, format!("{} is off.", id), Some(id.to_string())));
+        }
+    }
+
+    if let Some(level) = setting(settings, "security_level").and_then(|v| v.as_str()) {
+        if matches!(level, "off" | "essentially_off" | "low") {
+            findings.push(finding(&SECURITY_LEVEL, Severity::Medium, format!("Security level is {}.", level), Some("security_level".to_string())));
+        }
+    }
+}
+
+fn check_dns(zone_name: &str, records: &[DnsRecord], findings: &mut Vec<AuditFinding>) {
+    for record in records {
+        let resource = format!("{} {}", record.record_type, record.name);
+
+        if matches!(record.record_type.as_str(), "A" | "AAAA") && !record.proxied && !is_private_address(&record.content) {
+            findings.push(finding(
+                &UNPROXIED_RECORD,
+                Severity::Medium,
+                format!("{} points to {} without the Cloudflare proxy.", record.name, record.content),
+                Some(resource.clone()),
+            ));
+        }
+
+        if record.name.starts_with("*.") {
+            findings.push(finding(&WILDCARD_RECORD, Severity::Low, format!("Wildcard record {} resolves every subdomain.", record.name), Some(resource)));
+        }
+    }
+
+    let txt = |name: &str, prefix: &str| {
+        records.iter().any(|r| {
+            r.record_type == "TXT"
+                && r.name.eq_ignore_ascii_case(name)
+                && r.content.trim_matches('"').to_ascii_lowercase().starts_with(prefix)
+        })
+    };
+
+    if !txt(&format!("_dmarc.{}", zone_name), "v=dmarc1") {
+        findings.push(finding(&NO_DMARC, Severity::Medium, format!("No DMARC policy at _dmarc.{}.", zone_name), None));
+    }
+    if !txt(zone_name, "v=spf1") {
+        findings.push(finding(&NO_SPF, Severity::Low, format!("No SPF record at {}.", zone_name), None));
+    }
+}
+
+fn is_private_address(content: &str) -> bool {
+    match content.parse::<std::net::IpAddr>() {
+        Ok(std::net::IpAddr::V4(ip)) => ip.is_private() || ip.is_loopback() || ip.is_link_local(),
+        Ok(std::net::IpAddr::V6(ip)) => ip.is_loopback() || (ip.segments()[0] & 0xfe00) == 0xfc00,
+        Err(_) => false,
+    }
+}
+
+fn check_expiry(expires_on: &str, hosts: &str, now: DateTime<Utc>, findings: &mut Vec<AuditFinding>) {
+    let Ok(expires) = DateTime::parse_from_rfc3339(expires_on) else {
+        return;
+    };
+    let days = (expires.with_timezone(&Utc) - now).num_days();
+
+    let severity = if days < 0 {
+        Severity::Critical
+    } else if days < 7 {
+        Severity::High
+    } else if days < 30 {
+        Severity::Medium
+    } else {
+        return;
+    };
+
+    let detail = if days < 0 {
+        format!("Certificate for {} expired on {}.", hosts, expires_on)
+    } else {
+        format!("Certificate for {} expires in {} day(s) ({}).", hosts, days, expires_on)
+    };
+    findings.push(finding(&CERTIFICATE_EXPIRY, severity, detail, Some(hosts.to_string())));
+}
+
+fn severity_weight(severity: Severity) -> u32 {
+    match severity {
+        Severity::Critical => 25,
+        Severity::High => 15,
+        Severity::Medium => 8,
+        Severity::Low => 3,
+        Severity::Info => 0,
+    }
+}
+
+fn score(findings: &[AuditFinding]) -> u32 {
+    let penalty: u32 = findings.iter().map(|f| severity_weight(f.severity)).sum();
+    100u32.saturating_sub(penalty)
+}
+
+fn grade(score: u32) -> &'static str {
+    match score {
+        90..=100 => "A",
+        80..=89 => "B",
+        70..=79 => "C",
+        60..=69 => "D",
+        _ => "F",
+    }
+}
+
+// 导出为 SARIF 2.1.0，便于导入代码扫描平台
+pub fn to_sarif(reports: &[ZoneAuditReport]) -> serde_json::Value {
+    let rules: Vec<serde_json::Value> = RULES.iter()
+        .map(|r| json!({
+            "id": r.id,
+            "name": r.title,
+            "shortDescription": { "text": r.title },
+            "help": { "text": r.help }
+        }))
+        .collect();
+
+    let results: Vec<serde_json::Value> = reports.iter()
+        .flat_map(|report| {
+            report.findings.iter().map(move |f| {
+                let location = match &f.resource {
+                    Some(resource) => format!("{}/{}", report.zone_name, resource),
+                    None => report.zone_name.clone(),
+                };
+                json!({
+                    "ruleId": f.rule_id,
+                    "level": sarif_level(f.severity),
+                    "message": { "text": f.detail },
+                    "locations": [{
+                        "logicalLocations": [{
+                            "name": f.resource.clone().unwrap_or_else(|| report.zone_name.clone()),
+                            "fullyQualifiedName": location,
+                            "kind": "resource"
+                        }]
+                    }],
+                    "properties": {
+                        "zoneId": report.zone_id,
+                        "severity": f.severity,
+                        "security-severity": security_severity(f.severity),
+                        "fix": f.fix
+                    }
+                })
+            })
+        })
+        .collect();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "cloudflare-manager-audit",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules
+                }
+            },
+            "results": results
+        }]
+    })
+}
+
+fn sarif_level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical | Severity::High => "error",
+        Severity::Medium => "warning",
+        Severity::Low | Severity::Info => "note",
+    }
+}
+
+fn security_severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical => "9.5",
+        Severity::High => "8.0",
+        Severity::Medium => "5.5",
+        Severity::Low => "3.0",
+        Severity::Info => "0.0",
+    }
+}
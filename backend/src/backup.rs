@@ -0,0 +1,930 @@
+use crate::cloudflare::{concurrency_limit, CloudflareClient};
+use crate::models::*;
+use crate::worker_scripts;
+use futures::stream::{self, StreamExt};
+use std::collections::HashMap;
+
+// 归档格式版本，结构不兼容变更时递增
+pub const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+// 单条 INSERT 语句的大致上限（D1 单条语句限制为 100KB）
+const D1_INSERT_BATCH_BYTES: usize = 50_000;
+
+// ==================== 导出 ====================
+
+pub async fn export(client: &CloudflareClient, request: &BackupExportRequest) -> Result<BackupArchive, String> {
+    if request.zone_ids.is_empty() && request.account_id.is_none() {
+        return Err("Specify zone_ids and/or account_id to export".to_string());
+    }
+
+    let mut errors = Vec::new();
+
+    let zone_ids: Vec<String> = if request.zone_ids.is_empty() {
+        let account_id = request.account_id.as_deref().unwrap_or_default();
+        client.get_zones().await?
+            .into_iter()
+            .filter(|z| z.account.as_ref().map(|a| a.id == account_id).unwrap_or(false))
+            .map(|z| z.id)
+            .collect()
+    } else {
+        request.zone_ids.clone()
+    };
+
+    let mut zones = Vec::new();
+    for zone_id in &zone_ids {
+        match export_zone(client, zone_id, &mut errors).await {
+            Ok(zone) => zones.push(zone),
+            Err(e) => errors.push(format!("zone {}: {}", zone_id, e)),
+        }
+    }
+
+    let account = match &request.account_id {
+        Some(account_id) => Some(export_account(client, account_id, request, &mut errors).await),
+        None => None,
+    };
+
+    log::info!("Exported backup archive with {} zone(s), {} error(s)", zones.len(), errors.len());
+
+    Ok(BackupArchive {
+        format_version: ARCHIVE_FORMAT_VERSION,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        zones,
+        account,
+        errors,
+    })
+}
+
+fn keep<T: Default>(label: String, result: Result<T, String>, errors: &mut Vec<String>) -> T {
+    result.unwrap_or_else(|e| {
+        errors.push(format!("{}: {}", label, e));
+        T::default()
+    })
+}
+
+async fn export_zone(client: &CloudflareClient, zone_id: &str, errors: &mut Vec<String>) -> Result<ZoneBackup, String> {
+    let zone = client.get_zone(zone_id).await?;
+
+    let (settings, dns, page_rules, firewall, waf, rate_limits, routes, certificates) = tokio::join!(
+        client.get_zone_settings(zone_id),
+        client.get_dns_records(zone_id),
+        client.get_page_rules(zone_id),
+        client.get_firewall_rules(zone_id),
+        client.get_waf_packages(zone_id),
+        client.get_rate_limits(zone_id),
+        client.get_worker_routes(zone_id),
+        client.get_custom_certificates(zone_id),
+    );
+
+    let label = |section: &str| format!("{}/{}", zone.name, section);
+
+    Ok(ZoneBackup {
+        zone_id: zone_id.to_string(),
+        settings: keep(label("settings"), settings, errors),
+        dns_records: keep(label("dns_records"), dns, errors),
+        page_rules: keep(label("page_rules"), page_rules, errors),
+        firewall_rules: keep(label("firewall_rules"), firewall, errors),
+        waf_packages: keep(label("waf_packages"), waf, errors),
+        rate_limits: keep(label("rate_limits"), rate_limits, errors),
+        worker_routes: keep(label("worker_routes"), routes, errors),
+        custom_certificates: keep(label("custom_certificates"), certificates, errors),
+        zone_name: zone.name,
+    })
+}
+
+async fn export_account(
+    client: &CloudflareClient,
+    account_id: &str,
+    request: &BackupExportRequest,
+    errors: &mut Vec<String>,
+) -> AccountBackup {
+    let mut worker_scripts = Vec::new();
+    let workers = keep("workers".to_string(), client.list_workers(account_id).await, errors);
+    for worker in workers {
+        let (content, settings) = tokio::join!(
+            client.get_worker(account_id, &worker.id),
+            client.get_worker_settings(account_id, &worker.id),
+        );
+        let settings = match settings {
+            Ok(settings) => Some(settings),
+            Err(e) => {
+                errors.push(format!("workers/{}/settings: {}", worker.id, e));
+                None
+            }
+        };
+        match content {
+            Ok(content) => worker_scripts.push(match content.single_source() {
+                Some(source) => WorkerScriptBackup {
+                    name: worker.id,
+                    content: source.to_string(),
+                    main_module: None,
+                    modules: Vec::new(),
+                    // 没有主模块的是 Service Worker 格式，恢复时需按原格式上传
+                    body_part: content.main_module.is_none().then(|| worker_scripts::MAIN_MODULE.to_string()),
+                    settings,
+                },
+                None => {
+                    let bundle = worker_scripts::bundle(content);
+                    WorkerScriptBackup {
+                        name: worker.id,
+                        content: String::new(),
+                        main_module: bundle.main_module,
+                        modules: bundle.modules,
+                        body_part: None,
+                        settings,
+                    }
+                }
+            }),
+            Err(e) => errors.push(format!("workers/{}: {}", worker.id, e)),
+        }
+    }
+
+    let mut kv_namespaces = Vec::new();
+    let namespaces = keep("kv_namespaces".to_string(), client.list_kv_namespaces(account_id).await, errors);
+    for namespace in namespaces {
+        let id = namespace["id"].as_str().unwrap_or_default().to_string();
+        let title = namespace["title"].as_str().unwrap_or_default().to_string();
+        let entries = export_kv_namespace(client, account_id, &id, request.include_kv_values, errors).await;
+        kv_namespaces.push(KvNamespaceBackup { id, title, entries });
+    }
+
+    let mut d1_databases = Vec::new();
+    let databases = keep("d1_databases".to_string(), client.list_d1_databases(account_id).await, errors);
+    for database in databases {
+        let uuid = database["uuid"].as_str().unwrap_or_default().to_string();
+        let name = database["name"].as_str().unwrap_or_default().to_string();
+        match export_d1_database(client, account_id, &uuid, &name, request.include_d1_data).await {
+            Ok(backup) => d1_databases.push(backup),
+            Err(e) => errors.push(format!("d1/{}: {}", name, e)),
+        }
+    }
+
+    AccountBackup {
+        account_id: account_id.to_string(),
+        worker_scripts,
+        kv_namespaces,
+        d1_databases,
+    }
+}
+
+async fn export_kv_namespace(
+    client: &CloudflareClient,
+    account_id: &str,
+    namespace_id: &str,
+    include_values: bool,
+    errors: &mut Vec<String>,
+) -> Vec<KvEntryBackup> {
+    let keys = match client.list_all_kv_keys(account_id, namespace_id).await {
+        Ok(keys) => keys,
+        Err(e) => {
+            errors.push(format!("kv/{}: {}", namespace_id, e));
+            return Vec::new();
+        }
+    };
+
+    let results: Vec<(KvEntryBackup, Option<String>)> = stream::iter(keys)
+        .map(|key| async move {
+            let name = key["name"].as_str().unwrap_or_default().to_string();
+            let mut error = None;
+            let value = if include_values {
+                match client.read_kv_value(account_id, namespace_id, &name).await {
+                    Ok(v) => Some(v),
+                    Err(e) => {
+                        error = Some(format!("kv/{}/{}: {}", namespace_id, name, e));
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+
+            let entry = KvEntryBackup {
+                key: name,
+                value,
+                expiration: key["expiration"].as_u64(),
+                metadata: key.get("metadata").cloned(),
+            };
+            (entry, error)
+        })
+        .buffered(concurrency_limit(None))
+        .collect()
+        .await;
+
+    results.into_iter()
+        .map(|(entry, error)| {
+            errors.extend(error);
+            entry
+        })
+        .collect()
+}
+
+async fn export_d1_database(
+    client: &CloudflareClient,
+    account_id: &str,
+    database_id: &str,
+    name: &str,
+    include_data: bool,
+) -> Result<D1DatabaseBackup, String> {
+    // 表优先，其次索引、视图、触发器，保证恢复时的依赖顺序
+    let schema_sql = "SELECT type, name, sql FROM sqlite_master \
+        WHERE sql IS NOT NULL AND name NOT LIKE 'sqlite_%' AND name NOT LIKE '_cf_%' \
+        ORDER BY CASE type WHEN 'table' THEN 0 WHEN 'index' THEN 1 WHEN 'view' THEN 2 ELSE 3 END, rowid";
+    let schema_result = client.execute_d1_query(account_id, database_id, schema_sql).await?;
+
+    let schema: Vec<D1SchemaObject> = schema_result["results"].as_array()
+        .map(|rows| rows.iter()
+            .map(|row| D1SchemaObject {
+                object_type: row["type"].as_str().unwrap_or_default().to_string(),
+                name: row["name"].as_str().unwrap_or_default().to_string(),
+                sql: row["sql"].as_str().unwrap_or_default().to_string(),
+            })
+            .collect())
+        .unwrap_or_default();
+
+    let mut tables = Vec::new();
+    if include_data {
+        for object in schema.iter().filter(|o| o.object_type == "table") {
+            let query = format!("SELECT * FROM {}", quote_identifier(&object.name));
+            let result = client.execute_d1_query(account_id, database_id, &query).await?;
+            let rows = result["results"].as_array()
+                .map(|rows| rows.iter().filter_map(|r| r.as_object().cloned()).collect())
+                .unwrap_or_default();
+            tables.push(D1TableBackup { name: object.name.clone(), rows });
+        }
+    }
+
+    Ok(D1DatabaseBackup {
+        uuid: database_id.to_string(),
+        name: name.to_string(),
+        schema,
+        tables,
+    })
+}
+
+// ==================== 恢复 ====================
+
+// 按依赖顺序回放归档：先账户资源（KV、D1、Workers），再 Zone（设置、DNS、防火墙、页面规则、速率限制、Worker 路由）
+pub async fn restore(client: &CloudflareClient, request: &BackupRestoreRequest) -> Result<RestoreReport, String> {
+    let archive = &request.archive;
+    if archive.format_version > ARCHIVE_FORMAT_VERSION {
+        return Err(format!(
+            "Unsupported archive format version {} (supported: {})",
+            archive.format_version, ARCHIVE_FORMAT_VERSION
+        ));
+    }
+    if request.target_zone_id.is_some() && archive.zones.len() > 1 {
+        return Err("target_zone_id can only be used with single-zone archives; use zone_map instead".to_string());
+    }
+
+    let mut report = RestoreReport::default();
+
+    if let Some(account) = &archive.account {
+        let account_id = request.target_account_id.as_deref().unwrap_or(&account.account_id);
+        restore_account(client, account, account_id, &mut report).await;
+    }
+
+    for zone in &archive.zones {
+        let target_zone_id = request.target_zone_id.as_ref()
+            .or_else(|| request.zone_map.get(&zone.zone_id))
+            .unwrap_or(&zone.zone_id);
+
+        match client.get_zone(target_zone_id).await {
+            Ok(target) => restore_zone(client, zone, target_zone_id, &target.name, &mut report).await,
+            Err(e) => skip(&mut report, format!("zone:{}", zone.zone_name), format!("Target zone {} unavailable: {}", target_zone_id, e)),
+        }
+    }
+
+    log::info!("Restore finished: {} applied, {} skipped", report.applied.len(), report.skipped.len());
+    Ok(report)
+}
+
+fn apply(report: &mut RestoreReport, resource: String, detail: impl Into<String>) {
+    report.applied.push(RestoreItem { resource, detail: detail.into() });
+}
+
+fn skip(report: &mut RestoreReport, resource: String, detail: impl Into<String>) {
+    report.skipped.push(RestoreItem { resource, detail: detail.into() });
+}
+
+async fn restore_account(client: &CloudflareClient, account: &AccountBackup, account_id: &str, report: &mut RestoreReport) {
+    // 归档中的 KV Namespace / D1 数据库 ID -> 目标账户中的 ID，用于改写 Worker 绑定
+    let mut resource_ids: HashMap<String, String> = HashMap::new();
+
+    // KV：同名 Namespace 直接复用
+    let existing_namespaces = client.list_kv_namespaces(account_id).await.unwrap_or_default();
+    for namespace in &account.kv_namespaces {
+        let resource = format!("kv:{}", namespace.title);
+        let existing = existing_namespaces.iter()
+            .find(|n| n["title"].as_str() == Some(namespace.title.as_str()))
+            .and_then(|n| n["id"].as_str())
+            .map(|s| s.to_string());
+
+        let namespace_id = match existing {
+            Some(id) => id,
+            None => match client.create_kv_namespace(account_id, &namespace.title).await {
+                Ok(created) => {
+                    apply(report, resource.clone(), "Namespace created");
+                    created["id"].as_str().unwrap_or_default().to_string()
+                }
+                Err(e) => {
+                    skip(report, resource, format!("Failed to create namespace: {}", e));
+                    continue;
+                }
+            },
+        };
+
+        resource_ids.insert(namespace.id.clone(), namespace_id.clone());
+        restore_kv_entries(client, account_id, &namespace_id, namespace, report).await;
+    }
+
+    // D1：已存在同名数据库时跳过，避免覆盖数据
+    let existing_databases = client.list_d1_databases(account_id).await.unwrap_or_default();
+    for database in &account.d1_databases {
+        let resource = format!("d1:{}", database.name);
+        let existing = existing_databases.iter()
+            .find(|d| d["name"].as_str() == Some(database.name.as_str()))
+            .and_then(|d| d["uuid"].as_str());
+        if let Some(uuid) = existing {
+            resource_ids.insert(database.uuid.clone(), uuid.to_string());
+            skip(report, resource, "A database with this name already exists");
+            continue;
+        }
+
+        match client.create_d1_database(account_id, &database.name).await {
+            Ok(created) => {
+                let database_id = created["uuid"].as_str().unwrap_or_default().to_string();
+                resource_ids.insert(database.uuid.clone(), database_id.clone());
+                restore_d1_database(client, account_id, &database_id, database, report).await;
+            }
+            Err(e) => skip(report, resource, format!("Failed to create database: {}", e)),
+        }
+    }
+
+    // Workers：在 KV/D1 之后上传，以便绑定指向恢复后的资源；已存在的同名脚本不覆盖
+    let existing_workers: Vec<String> = match client.list_workers(account_id).await {
+        Ok(workers) => workers.into_iter().map(|w| w.id).collect(),
+        Err(e) => {
+            for script in &account.worker_scripts {
+                skip(report, format!("worker:{}", script.name), format!("Failed to list existing scripts: {}", e));
+            }
+            return;
+        }
+    };
+    for script in &account.worker_scripts {
+        let resource = format!("worker:{}", script.name);
+        if existing_workers.contains(&script.name) {
+            skip(report, resource, "A script with this name already exists");
+            continue;
+        }
+
+        let (modules, main_module) = if let Some(body_part) = &script.body_part {
+            (worker_scripts::service_worker(body_part, &script.content).0, body_part.clone())
+        } else if script.modules.is_empty() {
+            (worker_scripts::single_module(&script.content), worker_scripts::MAIN_MODULE.to_string())
+        } else {
+            let Some(main_module) = &script.main_module else {
+                skip(report, resource, "Archive has modules but no main module; redeploy it from source");
+                continue;
+            };
+            match worker_scripts::decode_modules(&script.modules) {
+                Ok(modules) => (modules, main_module.clone()),
+                Err(e) => {
+                    skip(report, resource, e);
+                    continue;
+                }
+            }
+        };
+
+        let (request, missing) = upload_request(account_id, script, &resource_ids);
+        let mut metadata = match worker_scripts::build_metadata(&request, &main_module) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                skip(report, resource, e);
+                continue;
+            }
+        };
+        // Service Worker 格式以 body_part 指定脚本字段
+        if script.body_part.is_some() {
+            if let Some(fields) = metadata.as_object_mut() {
+                fields.remove("main_module");
+                fields.insert("body_part".to_string(), main_module.into());
+            }
+        }
+
+        let detail = match (&script.settings, missing.is_empty()) {
+            (None, _) => "Script uploaded (archive has no settings; bindings must be reconfigured)".to_string(),
+            (Some(_), true) => format!("Script uploaded with {} binding(s)", request.vars.len() + request.bindings.len()),
+            (Some(_), false) => format!(
+                "Script uploaded with {} binding(s); reconfigure: {}",
+                request.vars.len() + request.bindings.len(),
+                missing.join(", ")
+            ),
+        };
+        match client.upload_worker(account_id, &script.name, &modules, &metadata).await {
+            Ok(_) => apply(report, resource, detail),
+            Err(e) => skip(report, resource, e),
+        }
+    }
+}
+
+// 由归档中的脚本设置生成上传请求：KV/D1 绑定改写为恢复后的 ID，secret 无法导出，
+// 返回需要手动重新配置的绑定
+fn upload_request(
+    account_id: &str,
+    script: &WorkerScriptBackup,
+    resource_ids: &HashMap<String, String>,
+) -> (UploadWorkerRequest, Vec<String>) {
+    let mut request = UploadWorkerRequest {
+        account_id: account_id.to_string(),
+        script_name: script.name.clone(),
+        script_content: String::new(),
+        main_module: None,
+        modules: Vec::new(),
+        archive: None,
+        vars: Default::default(),
+        secrets: Default::default(),
+        bindings: Vec::new(),
+        compatibility_date: None,
+        compatibility_flags: Vec::new(),
+        usage_model: None,
+        placement: None,
+        keep_secrets: true,
+    };
+    let mut missing = Vec::new();
+    let Some(settings) = &script.settings else {
+        return (request, missing);
+    };
+
+    request.compatibility_date = settings.compatibility_date.clone();
+    request.compatibility_flags = settings.compatibility_flags.clone();
+    request.usage_model = settings.usage_model.clone();
+    request.placement = settings.placement.as_ref()
+        .and_then(|p| p["mode"].as_str())
+        .map(|mode| mode.to_string());
+
+    for binding in &settings.bindings {
+        let name = binding["name"].as_str().unwrap_or_default();
+        let binding_type = binding["type"].as_str().unwrap_or_default();
+        if matches!(binding_type, "secret_text" | "secret_key") {
+            missing.push(format!("{} ({})", name, binding_type));
+            continue;
+        }
+        match serde_json::from_value(binding.clone()) {
+            Ok(WorkerBinding::PlainText { name, text }) => {
+                request.vars.insert(name, text);
+            }
+            Ok(WorkerBinding::KvNamespace { name, namespace_id }) => {
+                let namespace_id = resource_ids.get(&namespace_id).cloned().unwrap_or(namespace_id);
+                request.bindings.push(WorkerBinding::KvNamespace { name, namespace_id });
+            }
+            Ok(WorkerBinding::D1 { name, id }) => {
+                let id = resource_ids.get(&id).cloned().unwrap_or(id);
+                request.bindings.push(WorkerBinding::D1 { name, id });
+            }
+            Ok(other) => request.bindings.push(other),
+            Err(_) => missing.push(format!("{} ({})", name, binding_type)),
+        }
+    }
+    (request, missing)
+}
+
+async fn restore_kv_entries(
+    client: &CloudflareClient,
+    account_id: &str,
+    namespace_id: &str,
+    namespace: &KvNamespaceBackup,
+    report: &mut RestoreReport,
+) {
+    let now = chrono::Utc::now().timestamp() as u64;
+
+    let results: Vec<(String, Result<(), String>)> = stream::iter(&namespace.entries)
+        .map(|entry| async move {
+            let Some(value) = &entry.value else {
+                return (entry.key.clone(), Err("Value not included in archive".to_string()));
+            };
+            // 过期时间已过的键不再恢复
+            let ttl = match entry.expiration {
+                Some(expiration) if expiration <= now + 60 => {
+                    return (entry.key.clone(), Err("Key has expired".to_string()));
+                }
+                Some(expiration) => Some(expiration - now),
+                None => None,
+            };
+            let result = client
+                .write_kv_value(account_id, namespace_id, &entry.key, value, ttl, entry.metadata.clone())
+                .await
+                .map(|_| ());
+            (entry.key.clone(), result)
+        })
+        .buffered(concurrency_limit(None))
+        .collect()
+        .await;
+
+    let mut written = 0;
+    for (key, result) in results {
+        match result {
+            Ok(()) => written += 1,
+            Err(e) => skip(report, format!("kv:{}/{}", namespace.title, key), e),
+        }
+    }
+    if written > 0 {
+        apply(report, format!("kv:{}", namespace.title), format!("{} key(s) written", written));
+    }
+}
+
+async fn restore_d1_database(
+    client: &CloudflareClient,
+    account_id: &str,
+    database_id: &str,
+    database: &D1DatabaseBackup,
+    report: &mut RestoreReport,
+) {
+    let resource = format!("d1:{}", database.name);
+
+    // 先建表，再导入数据，最后创建索引、视图和触发器
+    for object in database.schema.iter().filter(|o| o.object_type == "table") {
+        if let Err(e) = client.execute_d1_query(account_id, database_id, &object.sql).await {
+            skip(report, format!("{}/{}", resource, object.name), format!("Failed to create table: {}", e));
+        }
+    }
+
+    let mut rows_written = 0;
+    for table in &database.tables {
+        for statement in insert_statements(&table.name, &table.rows) {
+            match client.execute_d1_query(account_id, database_id, &statement.sql).await {
+                Ok(_) => rows_written += statement.rows,
+                Err(e) => skip(report, format!("{}/{}", resource, table.name), format!("Failed to insert {} row(s): {}", statement.rows, e)),
+            }
+        }
+    }
+
+    for object in database.schema.iter().filter(|o| o.object_type != "table") {
+        if let Err(e) = client.execute_d1_query(account_id, database_id, &object.sql).await {
+            skip(report, format!("{}/{}", resource, object.name), format!("Failed to create {}: {}", object.object_type, e));
+        }
+    }
+
+    apply(report, resource, format!("Database created, {} row(s) restored", rows_written));
+}
+
+struct InsertStatement {
+    sql: String,
+    rows: usize,
+}
+
+// 按大小分批生成多行 INSERT 语句
+fn insert_statements(table: &str, rows: &[serde_json::Map<String, serde_json::Value>]) -> Vec<InsertStatement> {
+    let mut statements = Vec::new();
+    let mut current: Option<(Vec<String>, String, usize)> = None;
+
+    for row in rows {
+        let columns: Vec<String> = row.keys().cloned().collect();
+        let values = format!(
+            "({})",
+            row.values().map(sql_literal).collect::<Vec<_>>().join(", ")
+        );
+
+        let fits = current.as_ref()
+            .map(|(cols, sql, _)| *cols == columns && sql.len() + values.len() < D1_INSERT_BATCH_BYTES)
+            .unwrap_or(false);
+
+        if fits {
+            if let Some((_, sql, count)) = current.as_mut() {
+                sql.push_str(", ");
+                sql.push_str(&values);
+                *count += 1;
+            }
+        } else {
+            if let Some((_, sql, count)) = current.take() {
+                statements.push(InsertStatement { sql, rows: count });
+            }
+            let sql = format!(
+                "INSERT INTO {} ({}) VALUES {}",
+                quote_identifier(table),
+                columns.iter().map(|c| quote_identifier(c)).collect::<Vec<_>>().join(", "),
+                values
+            );
+            current = Some((columns, sql, 1));
+        }
+    }
+
+    if let Some((_, sql, count)) = current {
+        statements.push(InsertStatement { sql, rows: count });
+    }
+    statements
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn sql_literal(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => "NULL".to_string(),
+        serde_json::Value::Bool(b) => if *b { "1" } else { "0" }.to_string(),
+        serde_json::Value::Number(n) => n.to_string(),
+        serde_json::Value::String(s) => format!("'{}'", s.replace('\'', "''")),
+        // D1 以字节数组返回 BLOB
+        serde_json::Value::Array(bytes) if bytes.iter().all(|b| b.as_u64().map(|v| v <= 255).unwrap_or(false)) => {
+            let hex: String = bytes.iter().map(|b| format!("{:02x}", b.as_u64().unwrap_or(0))).collect();
+            format!("X'{}'", hex)
+        }
+        other => format!("'{}'", other.to_string().replace('\'', "''")),
+    }
+}
+
+fn is_label_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_'
+}
+
+// 恢复到其他 Zone 时，将名称中的源 Zone 域名替换为目标 Zone 域名。
+// 只替换作为完整标签后缀出现的域名（www.example.com），不替换 notexample.com 或 example.com.cn
+fn rename(value: &str, source: &str, target: &str) -> String {
+    if source == target || source.is_empty() {
+        return value.to_string();
+    }
+    let mut out = String::with_capacity(value.len());
+    let mut last = 0;
+    for (index, _) in value.match_indices(source) {
+        let end = index + source.len();
+        let before = value[..index].chars().next_back();
+        let after = value[end..].chars().next();
+        let whole = !before.is_some_and(is_label_char) && !after.is_some_and(|c| is_label_char(c) || c == '.');
+        if whole && index >= last {
+            out.push_str(&value[last..index]);
+            out.push_str(target);
+            last = end;
+        }
+    }
+    out.push_str(&value[last..]);
+    out
+}
+
+async fn restore_zone(
+    client: &CloudflareClient,
+    zone: &ZoneBackup,
+    zone_id: &str,
+    zone_name: &str,
+    report: &mut RestoreReport,
+) {
+    let prefix = format!("zone:{}", zone_name);
+    let source = zone.zone_name.as_str();
+
+    // 1. 设置：只更新与目标不同的项，逐项提交以便单独记录失败
+    let current: HashMap<String, serde_json::Value> = client.get_zone_settings(zone_id).await
+        .map(|settings| settings.into_iter().map(|s| (s.id, s.value)).collect())
+        .unwrap_or_default();
+    for setting in &zone.settings {
+        let resource = format!("{}/settings/{}", prefix, setting.id);
+        if current.get(&setting.id) == Some(&setting.value) {
+            continue;
+        }
+        let update = [UpdateSetting { id: setting.id.clone(), value: setting.value.clone() }];
+        match client.update_zone_settings(zone_id, &update).await {
+            Ok(_) => apply(report, resource, "Setting updated"),
+            Err(e) => skip(report, resource, e),
+        }
+    }
+
+    // 2. DNS 记录：已存在的相同记录跳过
+    let existing_records = client.get_dns_records(zone_id).await.unwrap_or_default();
+    for record in &zone.dns_records {
+        let mut record = record.clone();
+        record.id = None;
+        record.zone_id = Some(zone_id.to_string());
+        record.name = rename(&record.name, source, zone_name);
+        record.content = rename(&record.content, source, zone_name);
+
+        let resource = format!("{}/dns_records/{} {}", prefix, record.record_type, record.name);
+        let exists = existing_records.iter().any(|r| {
+            r.record_type == record.record_type && r.name == record.name && r.content == record.content
+        });
+        if exists {
+            skip(report, resource, "Identical record already exists");
+            continue;
+        }
+        match client.create_dns_record(&record).await {
+            Ok(_) => apply(report, resource, "Record created"),
+            Err(e) => skip(report, resource, e),
+        }
+    }
+
+    // 3. 防火墙规则：表达式与动作相同的规则跳过
+    let mut existing_rules: Vec<(String, String)> = client.get_firewall_rules(zone_id).await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|r| (r.filter.expression.trim().to_string(), r.action))
+        .collect();
+    for rule in &zone.firewall_rules {
+        let mut rule = rule.clone();
+        rule.id = None;
+        rule.filter.id = None;
+        rule.filter.expression = rename(&rule.filter.expression, source, zone_name);
+
+        let resource = format!("{}/firewall_rules/{}", prefix, rule.description.as_deref().unwrap_or(&rule.filter.expression));
+        let key = (rule.filter.expression.trim().to_string(), rule.action.clone());
+        if existing_rules.contains(&key) {
+            skip(report, resource, "Rule with the same expression and action already exists");
+            continue;
+        }
+        match client.create_firewall_rule(zone_id, &rule).await {
+            Ok(_) => {
+                existing_rules.push(key);
+                apply(report, resource, "Firewall rule created")
+            }
+            Err(e) => skip(report, resource, e),
+        }
+    }
+
+    // 4. WAF：只恢复同名包的敏感度与动作模式
+    if !zone.waf_packages.is_empty() {
+        let target_packages = client.get_waf_packages(zone_id).await.unwrap_or_default();
+        for package in &zone.waf_packages {
+            let resource = format!("{}/waf_packages/{}", prefix, package.name);
+            let Some(target) = target_packages.iter().find(|p| p.name == package.name) else {
+                skip(report, resource, "WAF package not available on target zone");
+                continue;
+            };
+            match client.update_waf_package(zone_id, &target.id, package.sensitivity.as_deref(), package.action_mode.as_deref()).await {
+                Ok(_) => apply(report, resource, "WAF package settings updated"),
+                Err(e) => skip(report, resource, e),
+            }
+        }
+    }
+
+    // 5. 页面规则：目标与动作都相同的规则跳过
+    let page_rule_key = |rule: &PageRule| (
+        serde_json::to_value(&rule.targets).unwrap_or_default(),
+        serde_json::to_value(&rule.actions).unwrap_or_default(),
+    );
+    let mut existing_page_rules: Vec<_> = client.get_page_rules(zone_id).await
+        .unwrap_or_default()
+        .iter()
+        .map(page_rule_key)
+        .collect();
+    for rule in &zone.page_rules {
+        let mut rule = rule.clone();
+        rule.id = None;
+        for target in rule.targets.iter_mut() {
+            target.constraint.value = rename(&target.constraint.value, source, zone_name);
+        }
+
+        let resource = format!(
+            "{}/page_rules/{}",
+            prefix,
+            rule.targets.first().map(|t| t.constraint.value.as_str()).unwrap_or("")
+        );
+        let key = page_rule_key(&rule);
+        if existing_page_rules.contains(&key) {
+            skip(report, resource, "Page rule with the same targets and actions already exists");
+            continue;
+        }
+        match client.create_page_rule(zone_id, &rule).await {
+            Ok(_) => {
+                existing_page_rules.push(key);
+                apply(report, resource, "Page rule created")
+            }
+            Err(e) => skip(report, resource, e),
+        }
+    }
+
+    // 6. 速率限制：URL 与阈值相同的规则跳过
+    let mut existing_rate_limits: Vec<(String, u32)> = client.get_rate_limits(zone_id).await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|r| (r.match_request.url, r.threshold))
+        .collect();
+    for rate_limit in &zone.rate_limits {
+        let resource = format!("{}/rate_limits/{}", prefix, rate_limit.description);
+        let key = (rename(&rate_limit.match_request.url, source, zone_name), rate_limit.threshold);
+        if existing_rate_limits.contains(&key) {
+            skip(report, resource, "Rate limit with the same URL and threshold already exists");
+            continue;
+        }
+        let request = CreateRateLimitRequest {
+            zone_id: zone_id.to_string(),
+            disabled: rate_limit.disabled,
+            description: rate_limit.description.clone(),
+            match_request: MatchRequest {
+                url: rename(&rate_limit.match_request.url, source, zone_name),
+                methods: rate_limit.match_request.methods.clone(),
+                schemes: rate_limit.match_request.schemes.clone(),
+            },
+            threshold: rate_limit.threshold,
+            period: rate_limit.period,
+            action: rate_limit.action.clone(),
+        };
+        match client.create_rate_limit(zone_id, &request).await {
+            Ok(_) => {
+                existing_rate_limits.push(key);
+                apply(report, resource, "Rate limit created")
+            }
+            Err(e) => skip(report, resource, e),
+        }
+    }
+
+    // 7. Worker 路由（依赖 Worker 脚本已存在）：同一 pattern 只能有一条路由
+    let mut existing_patterns: Vec<String> = client.get_worker_routes(zone_id).await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|r| r.pattern)
+        .collect();
+    for route in &zone.worker_routes {
+        let pattern = rename(&route.pattern, source, zone_name);
+        let resource = format!("{}/worker_routes/{}", prefix, pattern);
+        let Some(script) = &route.script else {
+            skip(report, resource, "Route has no script");
+            continue;
+        };
+        if existing_patterns.contains(&pattern) {
+            skip(report, resource, "Route with the same pattern already exists");
+            continue;
+        }
+        match client.create_worker_route(zone_id, &pattern, script).await {
+            Ok(_) => {
+                existing_patterns.push(pattern);
+                apply(report, resource, "Worker route created")
+            }
+            Err(e) => skip(report, resource, e),
+        }
+    }
+
+    // 8. 自定义证书：私钥不可导出，只能跳过
+    for certificate in &zone.custom_certificates {
+        skip(
+            report,
+            format!("{}/custom_certificates/{}", prefix, certificate.hosts.join(",")),
+            "Private keys are not exported; upload the certificate manually",
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn row(value: Value) -> serde_json::Map<String, Value> {
+        value.as_object().cloned().unwrap()
+    }
+
+    #[test]
+    fn escapes_quotes_in_strings_and_identifiers() {
+        assert_eq!(sql_literal(&json!("it's")), "'it''s'");
+        assert_eq!(quote_identifier("my \"table\""), "\"my \"\"table\"\"\"");
+
+        let statements = insert_statements("t\"1", &[row(json!({ "na\"me": "O'Brien" }))]);
+        assert_eq!(statements.len(), 1);
+        assert_eq!(statements[0].sql, "INSERT INTO \"t\"\"1\" (\"na\"\"me\") VALUES ('O''Brien')");
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(sql_literal(&Value::Null), "NULL");
+        assert_eq!(sql_literal(&json!(true)), "1");
+        assert_eq!(sql_literal(&json!(-2.5)), "-2.5");
+        assert_eq!(sql_literal(&json!([0, 15, 255])), "X'000fff'");
+        // 超出字节范围的数组不是 BLOB，按 JSON 文本保存
+        assert_eq!(sql_literal(&json!([1, 256])), "'[1,256]'");
+        assert_eq!(sql_literal(&json!({ "a": "b'c" })), "'{\"a\":\"b''c\"}'");
+    }
+
+    #[test]
+    fn batches_split_on_column_change() {
+        let rows = [
+            row(json!({ "a": 1 })),
+            row(json!({ "a": 2 })),
+            row(json!({ "a": 3, "b": 4 })),
+            row(json!({ "a": 5 })),
+        ];
+        let statements = insert_statements("t", &rows);
+        let counts: Vec<usize> = statements.iter().map(|s| s.rows).collect();
+        assert_eq!(counts, vec![2, 1, 1]);
+        assert_eq!(statements[0].sql, "INSERT INTO \"t\" (\"a\") VALUES (1), (2)");
+        assert_eq!(statements[1].sql, "INSERT INTO \"t\" (\"a\", \"b\") VALUES (3, 4)");
+    }
+
+    #[test]
+    fn batches_split_on_size() {
+        let value = "x".repeat(1_000);
+        let rows: Vec<_> = (0..120).map(|_| row(json!({ "v": value }))).collect();
+        let statements = insert_statements("t", &rows);
+
+        assert!(statements.len() > 1);
+        assert_eq!(statements.iter().map(|s| s.rows).sum::<usize>(), rows.len());
+        assert!(statements.iter().all(|s| s.sql.len() < D1_INSERT_BATCH_BYTES));
+    }
+
+    #[test]
+    fn rename_replaces_whole_labels_only() {
+        assert_eq!(rename("www.example.com", "example.com", "example.org"), "www.example.org");
+        assert_eq!(rename("example.com", "example.com", "example.org"), "example.org");
+        assert_eq!(rename("notexample.com", "example.com", "example.org"), "notexample.com");
+        assert_eq!(rename("my-example.com", "example.com", "example.org"), "my-example.com");
+        assert_eq!(rename("example.com.cn", "example.com", "example.org"), "example.com.cn");
+        assert_eq!(
+            rename("v=spf1 include:_spf.example.com ~all", "example.com", "example.org"),
+            "v=spf1 include:_spf.example.org ~all"
+        );
+        assert_eq!(rename("https://example.com/*", "example.com", "example.org"), "https://example.org/*");
+    }
+}
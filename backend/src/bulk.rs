@@ -0,0 +1,344 @@
+use crate::access_rules;
+use crate::cloudflare::{concurrency_limit, CloudflareClient};
+use crate::expression;
+use crate::models::*;
+use actix_web::web;
+use futures::stream::{self, StreamExt};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+
+// 保留的最大任务数量，超出后移除最早完成的任务
+const MAX_JOBS: usize = 200;
+
+// 扇出任务的单个目标 Zone
+pub struct FanOutTarget {
+    pub client: Arc<CloudflareClient>,
+    pub zone_id: String,
+    pub zone_name: Option<String>,
+    pub label: Option<String>,
+}
+
+struct JobEntry {
+    owner: String,
+    job: BulkJob,
+}
+
+// 批量任务存储（内存），任务在后台执行，可随时查询进度
+#[derive(Default)]
+pub struct BulkJobStore {
+    jobs: RwLock<HashMap<String, JobEntry>>,
+}
+
+impl BulkJobStore {
+    // 启动扇出任务：对每个目标 Zone 以有限并发执行 op，立即返回任务快照
+    pub fn spawn_job<F, Fut>(
+        store: &web::Data<BulkJobStore>,
+        owner: &CloudflareCredentials,
+        description: String,
+        targets: Vec<FanOutTarget>,
+        failed: Vec<BulkZoneResult>,
+        concurrency: usize,
+        op: F,
+    ) -> BulkJob
+    where
+        F: Fn(Arc<CloudflareClient>, String, Option<String>) -> Fut + 'static,
+        Fut: Future<Output = Result<String, String>> + 'static,
+    {
+        let job = BulkJob {
+            id: uuid::Uuid::new_v4().to_string(),
+            description,
+            status: BulkJobStatus::Running,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            finished_at: None,
+            total: targets.len() + failed.len(),
+            completed: 0,
+            succeeded: 0,
+            failed: 0,
+            results: Vec::new(),
+        };
+        let job_id = job.id.clone();
+
+        {
+            let mut jobs = store.jobs.write().unwrap();
+            prune(&mut jobs);
+            jobs.insert(job_id.clone(), JobEntry {
+                owner: owner.api_token.trim().to_string(),
+                job: job.clone(),
+            });
+        }
+
+        // 目标解析阶段已失败的 Zone 直接计入结果
+        for result in failed {
+            store.record(&job_id, result);
+        }
+
+        log::info!("Bulk job {} started: {} ({} zones)", job_id, job.description, job.total);
+
+        let store = store.clone();
+        actix_web::rt::spawn(async move {
+            let op = &op;
+            let store_ref = &store;
+            let job_id_ref = &job_id;
+            stream::iter(targets)
+                .for_each_concurrent(concurrency, |target| async move {
+                    let outcome = op(target.client.clone(), target.zone_id.clone(), target.zone_name.clone()).await;
+                    let (success, message) = match outcome {
+                        Ok(message) => (true, message),
+                        Err(e) => (false, e),
+                    };
+                    store_ref.record(job_id_ref, BulkZoneResult {
+                        zone_id: target.zone_id,
+                        zone_name: target.zone_name,
+                        target: target.label,
+                        success,
+                        message,
+                        finished_at: chrono::Utc::now().to_rfc3339(),
+                    });
+                })
+                .await;
+
+            store.finish(&job_id);
+        });
+
+        job
+    }
+
+    fn record(&self, job_id: &str, result: BulkZoneResult) {
+        if let Some(entry) = self.jobs.write().unwrap().get_mut(job_id) {
+            let job = &mut entry.job;
+            job.completed += 1;
+            if result.success {
+                job.succeeded += 1;
+            } else {
+                job.failed += 1;
+            }
+            job.results.push(result);
+        }
+    }
+
+    fn finish(&self, job_id: &str) {
+        if let Some(entry) = self.jobs.write().unwrap().get_mut(job_id) {
+            entry.job.status = BulkJobStatus::Completed;
+            entry.job.finished_at = Some(chrono::Utc::now().to_rfc3339());
+            log::info!(
+                "Bulk job {} completed: {} succeeded, {} failed",
+                job_id, entry.job.succeeded, entry.job.failed
+            );
+        }
+    }
+
+    pub fn get(&self, credentials: &CloudflareCredentials, job_id: &str) -> Result<BulkJob, String> {
+        self.jobs.read().unwrap()
+            .get(job_id)
+            .filter(|entry| entry.owner == credentials.api_token.trim())
+            .map(|entry| entry.job.clone())
+            .ok_or_else(|| format!("Bulk job {} not found", job_id))
+    }
+
+    // 列出当前 API Token 创建的任务（最新的在前）
+    pub fn list(&self, credentials: &CloudflareCredentials) -> Vec<BulkJob> {
+        let mut jobs: Vec<BulkJob> = self.jobs.read().unwrap()
+            .values()
+            .filter(|entry| entry.owner == credentials.api_token.trim())
+            .map(|entry| entry.job.clone())
+            .collect();
+        jobs.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        jobs
+    }
+}
+
+fn prune(jobs: &mut HashMap<String, JobEntry>) {
+    while jobs.len() >= MAX_JOBS {
+        let oldest = jobs.iter()
+            .filter(|(_, e)| e.job.status == BulkJobStatus::Completed)
+            .min_by(|a, b| a.1.job.created_at.cmp(&b.1.job.created_at))
+            .map(|(id, _)| id.clone());
+        match oldest {
+            Some(id) => {
+                jobs.remove(&id);
+            }
+            None => break,
+        }
+    }
+}
+
+// 解析目标：每个 Token 对应的 Zone 列表；无法解析的目标作为失败结果返回
+pub async fn resolve_targets(
+    credentials: &CloudflareCredentials,
+    targets: &[BulkTarget],
+) -> (Vec<FanOutTarget>, Vec<BulkZoneResult>) {
+    let default_target = [BulkTarget { api_token: None, zone_ids: Vec::new(), label: None }];
+    let targets = if targets.is_empty() { &default_target[..] } else { targets };
+
+    let mut resolved = Vec::new();
+    let mut failed = Vec::new();
+
+    for target in targets {
+        let target_credentials = match &target.api_token {
+            Some(token) => CloudflareCredentials { api_token: token.clone() },
+            None => credentials.clone(),
+        };
+
+        let zones = match CloudflareClient::new(&target_credentials) {
+            Ok(client) => {
+                let client = Arc::new(client);
+                // 获取 Zone 名称；指定了 Zone 时获取失败不影响执行
+                match client.get_zones().await {
+                    Ok(zones) => {
+                        let names: HashMap<String, String> = zones.iter().map(|z| (z.id.clone(), z.name.clone())).collect();
+                        let ids: Vec<String> = if target.zone_ids.is_empty() {
+                            zones.into_iter().map(|z| z.id).collect()
+                        } else {
+                            target.zone_ids.clone()
+                        };
+                        Ok(ids.into_iter().map(|id| (client.clone(), names.get(&id).cloned(), id)).collect::<Vec<_>>())
+                    }
+                    Err(_) if !target.zone_ids.is_empty() => {
+                        Ok(target.zone_ids.iter().map(|id| (client.clone(), None, id.clone())).collect())
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            Err(e) => Err(e),
+        };
+
+        match zones {
+            Ok(zones) => {
+                for (client, zone_name, zone_id) in zones {
+                    resolved.push(FanOutTarget { client, zone_id, zone_name, label: target.label.clone() });
+                }
+            }
+            Err(e) => failed.push(BulkZoneResult {
+                zone_id: "*".to_string(),
+                zone_name: None,
+                target: target.label.clone(),
+                success: false,
+                message: format!("Failed to resolve zones: {}", e),
+                finished_at: chrono::Utc::now().to_rfc3339(),
+            }),
+        }
+    }
+
+    (resolved, failed)
+}
+
+// 创建批量操作任务
+pub async fn create_job(
+    store: &web::Data<BulkJobStore>,
+    credentials: &CloudflareCredentials,
+    request: &CreateBulkJobRequest,
+) -> Result<BulkJob, String> {
+    if !credentials.is_valid() {
+        return Err("Invalid credentials: API Token is required".to_string());
+    }
+
+    if let BulkOperation::CreateFirewallRule { rule } = &request.operation {
+        expression::validate(&rule.filter.expression)?;
+    }
+    if let BulkOperation::CreateAccessRule { rule } = &request.operation {
+        access_rules::validate(rule)?;
+    }
+
+    let (targets, failed) = resolve_targets(credentials, &request.targets).await;
+    let description = describe(&request.operation);
+    let operation = Arc::new(request.operation.clone());
+
+    Ok(BulkJobStore::spawn_job(
+        store,
+        credentials,
+        description,
+        targets,
+        failed,
+        concurrency_limit(request.concurrency),
+        move |client, zone_id, zone_name| {
+            let operation = operation.clone();
+            async move { run_operation(&client, &zone_id, zone_name.as_deref(), &operation).await }
+        },
+    ))
+}
+
+fn describe(operation: &BulkOperation) -> String {
+    match operation {
+        BulkOperation::UpdateSetting { id, value } => format!("Set {} = {}", id, value),
+        BulkOperation::PurgeEverything => "Purge everything".to_string(),
+        BulkOperation::CreateDnsRecord { record } => format!("Create {} record {}", record.record_type, record.name),
+        BulkOperation::CreateFirewallRule { rule } => format!("Create firewall rule ({})", rule.action),
+        BulkOperation::SetFirewallRulePaused { description, paused } => {
+            format!("{} firewall rule \"{}\"", if *paused { "Pause" } else { "Enable" }, description)
+        }
+        BulkOperation::CreateAccessRule { rule } => format!("Create access rule ({} {})", rule.mode, rule.configuration.value),
+    }
+}
+
+fn expand(template: &str, zone_name: Option<&str>) -> String {
+    match zone_name {
+        Some(name) => template.replace("{zone}", name),
+        None => template.to_string(),
+    }
+}
+
+async fn run_operation(
+    client: &CloudflareClient,
+    zone_id: &str,
+    zone_name: Option<&str>,
+    operation: &BulkOperation,
+) -> Result<String, String> {
+    match operation {
+        BulkOperation::UpdateSetting { id, value } => {
+            let setting = [UpdateSetting { id: id.clone(), value: value.clone() }];
+            client.update_zone_settings(zone_id, &setting).await
+        }
+        BulkOperation::PurgeEverything => {
+            let request = PurgeCacheRequest {
+                zone_id: zone_id.to_string(),
+                purge_everything: Some(true),
+                files: None,
+                tags: None,
+            };
+            client.purge_cache(&request).await.map(|r| format!("Purge request {} accepted", r.id))
+        }
+        BulkOperation::CreateDnsRecord { record } => {
+            if (record.name.contains("{zone}") || record.content.contains("{zone}")) && zone_name.is_none() {
+                return Err("Zone name unavailable for {zone} placeholder".to_string());
+            }
+            let mut record = record.clone();
+            record.id = None;
+            record.zone_id = Some(zone_id.to_string());
+            record.name = expand(&record.name, zone_name);
+            record.content = expand(&record.content, zone_name);
+            client.create_dns_record(&record).await
+                .map(|r| format!("Created record {}", r.id.unwrap_or_default()))
+        }
+        BulkOperation::CreateFirewallRule { rule } => {
+            let mut rule = rule.clone();
+            rule.id = None;
+            rule.filter.id = None;
+            client.create_firewall_rule(zone_id, &rule).await
+                .map(|r| format!("Created firewall rule {}", r.id.unwrap_or_default()))
+        }
+        BulkOperation::SetFirewallRulePaused { description, paused } => {
+            let rules = client.get_firewall_rules(zone_id).await?;
+            let matching: Vec<&FirewallRule> = rules.iter()
+                .filter(|r| r.description.as_deref() == Some(description.as_str()))
+                .collect();
+
+            if matching.is_empty() {
+                return Err(format!("No firewall rule with description \"{}\"", description));
+            }
+
+            for rule in &matching {
+                let rule_id = rule.id.as_deref().ok_or("Firewall rule has no ID")?;
+                let mut updated = (*rule).clone();
+                updated.paused = *paused;
+                client.update_firewall_rule(zone_id, rule_id, &updated).await?;
+            }
+            Ok(format!("Updated {} firewall rule(s)", matching.len()))
+        }
+        BulkOperation::CreateAccessRule { rule } => {
+            let rule = access_rules::validate(rule)?;
+            client.create_access_rule(&format!("zones/{}", zone_id), &rule).await
+                .map(|r| format!("Created access rule {}", r.id.unwrap_or_default()))
+        }
+    }
+}
@@ -1,9 +1,18 @@
 use crate::models::*;
+use crate::worker_scripts::{self, ModuleFile, WorkerScriptContent};
 use reqwest::{header, Client};
 use serde_json::json;
 
 const CLOUDFLARE_API_BASE: &str = "https://api.cloudflare.com/client/v4";
 
+// 多 Zone 并发请求的默认与最大并发数（避免触发 API 速率限制）
+const DEFAULT_CONCURRENCY: usize = 8;
+const MAX_CONCURRENCY: usize = 20;
+
+pub fn concurrency_limit(requested: Option<usize>) -> usize {
+    requested.unwrap_or(DEFAULT_CONCURRENCY).clamp(1, MAX_CONCURRENCY)
+}
+
 pub struct CloudflareClient {
     client: Client,
     credentials: CloudflareCredentials,
@@ -37,6 +46,13 @@ impl CloudflareClient {
         headers
     }
 
+    // multipart 请求由 reqwest 设置带 boundary 的 Content-Type，这里只保留认证头
+    fn get_multipart_headers(&self) -> header::HeaderMap {
+        let mut headers = self.get_headers();
+        headers.remove(header::CONTENT_TYPE);
+        headers
+    }
+
     // GraphQL API 也使用相同的 Bearer Token
     fn get_graphql_headers(&self) -> header::HeaderMap {
         self.get_headers()
@@ -63,7 +79,7 @@ impl CloudflareClient {
 
         if !json["success"].as_bool().unwrap_or(false) {
             let errors = json["errors"].as_array()
-                .and_then(|arr| arr.get(0))
+                .and_then(|arr| arr.first())
                 .and_then(|err| err["message"].as_str())
                 .unwrap_or("Unknown error");
             return Err(format!("API error ({}): {}", status, errors));
@@ -76,9 +92,47 @@ impl CloudflareClient {
         Ok(accounts)
     }
 
-    // 获取所有 Zone
+    // 获取所有 Zone（自动翻页）
     pub async fn get_zones(&self) -> Result<Vec<Zone>, String> {
-        let url = format!("{}/zones", CLOUDFLARE_API_BASE);
+        let mut zones: Vec<Zone> = Vec::new();
+        let mut page = 1;
+
+        loop {
+            let url = format!("{}/zones?page={}&per_page=50", CLOUDFLARE_API_BASE, page);
+
+            let response = self.client
+                .get(&url)
+                .headers(self.get_headers())
+                .send()
+                .await
+                .map_err(|e| format!("Request failed: {}", e))?;
+
+            let json: serde_json::Value = response
+                .json()
+                .await
+                .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+            if !json["success"].as_bool().unwrap_or(false) {
+                return Err(format!("API error: {:?}", json["errors"]));
+            }
+
+            let page_zones: Vec<Zone> = serde_json::from_value(json["result"].clone())
+                .map_err(|e| format!("Failed to parse zones: {}", e))?;
+            zones.extend(page_zones);
+
+            let total_pages = json["result_info"]["total_pages"].as_u64().unwrap_or(1);
+            if page >= total_pages {
+                break;
+            }
+            page += 1;
+        }
+
+        Ok(zones)
+    }
+
+    // 获取单个 Zone 详情（包含所属账户）
+    pub async fn get_zone(&self, zone_id: &str) -> Result<Zone, String> {
+        let url = format!("{}/zones/{}", CLOUDFLARE_API_BASE, zone_id);
 
         let response = self.client
             .get(&url)
@@ -96,10 +150,44 @@ impl CloudflareClient {
             return Err(format!("API error: {:?}", json["errors"]));
         }
 
-        let zones: Vec<Zone> = serde_json::from_value(json["result"].clone())
-            .map_err(|e| format!("Failed to parse zones: {}", e))?;
+        let zone: Zone = serde_json::from_value(json["result"].clone())
+            .map_err(|e| format!("Failed to parse zone: {}", e))?;
 
-        Ok(zones)
+        Ok(zone)
+    }
+
+    // 获取账户审计日志（可按 Zone 名称过滤）
+    pub async fn get_audit_logs(&self, account_id: &str, zone_name: Option<&str>, since: &str) -> Result<Vec<serde_json::Value>, String> {
+        let mut url = format!(
+            "{}/accounts/{}/audit_logs?since={}&direction=desc&per_page=100",
+            CLOUDFLARE_API_BASE, account_id, urlencoding::encode(since)
+        );
+
+        if let Some(name) = zone_name {
+            url = format!("{}&zone.name={}", url, urlencoding::encode(name));
+        }
+
+        let response = self.client
+            .get(&url)
+            .headers(self.get_headers())
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        let logs: Vec<serde_json::Value> = json["result"].as_array()
+            .cloned()
+            .unwrap_or_default();
+
+        Ok(logs)
     }
 
     // 获取 DNS 记录
@@ -128,6 +216,35 @@ impl CloudflareClient {
         Ok(records)
     }
 
+    // 按名称查询 DNS 记录（精确匹配，不受分页影响）
+    pub async fn get_dns_records_by_name(&self, zone_id: &str, name: &str) -> Result<Vec<DnsRecord>, String> {
+        let url = format!(
+            "{}/zones/{}/dns_records?name={}&per_page=100",
+            CLOUDFLARE_API_BASE, zone_id, urlencoding::encode(name)
+        );
+
+        let response = self.client
+            .get(&url)
+            .headers(self.get_headers())
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        let records: Vec<DnsRecord> = serde_json::from_value(json["result"].clone())
+            .map_err(|e| format!("Failed to parse DNS records: {}", e))?;
+
+        Ok(records)
+    }
+
     // 创建 DNS 记录
     pub async fn create_dns_record(&self, record: &DnsRecord) -> Result<DnsRecord, String> {
         let zone_id = record.zone_id.as_ref().ok_or("Zone ID is required for creating DNS record")?;
@@ -208,86 +325,6 @@ impl CloudflareClient {
         Ok(record_id.to_string())
     }
 
-    // 部署 Worker
-    pub async fn deploy_worker(&self, request: &DeployWorkerRequest) -> Result<String, String> {
-        // 生成 Worker 代码
-        let worker_script = self.generate_worker_script(request);
-
-        // 上传 Worker 脚本
-        let url = format!("{}/accounts/workers/scripts/{}", CLOUDFLARE_API_BASE, request.script_name);
-
-        let response = self.client
-            .put(&url)
-            .headers(self.get_headers())
-            .header(header::CONTENT_TYPE, "application/javascript")
-            .body(worker_script)
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
-
-        let json: serde_json::Value = response
-            .json()
-            .await
-            .map_err(|e| format!("JSON parse failed: {}", e))?;
-
-        if !json["success"].as_bool().unwrap_or(false) {
-            return Err(format!("API error: {:?}", json["errors"]));
-        }
-
-        Ok(format!("Worker {} deployed successfully", request.script_name))
-    }
-
-    fn generate_worker_script(&self, request: &DeployWorkerRequest) -> String {
-        format!(r#"
-addEventListener('fetch', event => {{
-    event.respondWith(handleRequest(event.request))
-}})
-
-async function handleRequest(request) {{
-    const targetUrl = '{target_url}';
-    const cdnNode = '{cdn_node}';
-    const cacheTtl = {cache_ttl};
-
-    const url = new URL(request.url);
-    url.hostname = new URL(targetUrl).hostname;
-
-    const modifiedRequest = new Request(url.toString(), {{
-        method: request.method,
-        headers: request.headers,
-        body: request.body,
-    }});
-
-    const cacheKey = new Request(url.toString(), modifiedRequest);
-    const cache = caches.default;
-
-    let response = await cache.match(cacheKey);
-
-    if (!response) {{
-        response = await fetch(modifiedRequest);
-
-        if (response.ok && cacheTtl > 0) {{
-            const newHeaders = new Headers(response.headers);
-            newHeaders.set('Cache-Control', `public, max-age=${{cacheTtl}}`);
-
-            response = new Response(response.body, {{
-                status: response.status,
-                statusText: response.statusText,
-                headers: newHeaders
-            }});
-
-            event.waitUntil(cache.put(cacheKey, response.clone()));
-        }}
-    }}
-
-    return response;
-}}
-"#,
-            target_url = request.target_url,
-            cdn_node = request.cdn_node,
-            cache_ttl = request.cache_ttl
-        )
-    }
-
     // 获取 Worker 列表
     pub async fn list_workers(&self, account_id: &str) -> Result<Vec<Worker>, String> {
         let url = format!("{}/accounts/{}/workers/scripts", CLOUDFLARE_API_BASE, account_id);
@@ -317,14 +354,18 @@ async function handleRequest(request) {{
                 etag: v["etag"].as_str().map(|s| s.to_string()),
                 created_on: v["created_on"].as_str().map(|s| s.to_string()),
                 modified_on: v["modified_on"].as_str().map(|s| s.to_string()),
+                handlers: Vec::new(),
+                compatibility_date: v["compatibility_date"].as_str().map(|s| s.to_string()),
+                compatibility_flags: Vec::new(),
+                usage_model: v["usage_model"].as_str().map(|s| s.to_string()),
             }))
             .collect();
 
         Ok(workers)
     }
 
-    // 获取单个 Worker
-    pub async fn get_worker(&self, account_id: &str, script_name: &str) -> Result<String, String> {
+    // 获取 Worker 脚本并解析为模块（模块脚本的响应为 multipart）
+    pub async fn get_worker(&self, account_id: &str, script_name: &str) -> Result<WorkerScriptContent, String> {
         let url = format!("{}/accounts/{}/workers/scripts/{}", CLOUDFLARE_API_BASE, account_id, script_name);
 
         let response = self.client
@@ -334,12 +375,33 @@ async function handleRequest(request) {{
             .await
             .map_err(|e| format!("Request failed: {}", e))?;
 
-        let script = response
-            .text()
+        let status = response.status();
+        let header_value = |name: &str| response.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+        let content_type = header_value(header::CONTENT_TYPE.as_str()).unwrap_or_default();
+        let entrypoint = header_value("cf-entrypoint");
+        let body = response
+            .bytes()
             .await
             .map_err(|e| format!("Failed to read script: {}", e))?;
 
-        Ok(script)
+        if !status.is_success() {
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap_or_default();
+            return Err(format!("API error ({}): {:?}", status, json["errors"]));
+        }
+
+        worker_scripts::parse_script(&content_type, entrypoint.as_deref(), body.to_vec())
+    }
+
+    // 获取单个 Worker 的元数据（脚本接口不返回元数据，从列表中查找）
+    pub async fn get_worker_metadata(&self, account_id: &str, script_name: &str) -> Result<Worker, String> {
+        self.list_workers(account_id)
+            .await?
+            .into_iter()
+            .find(|w| w.id == script_name)
+            .ok_or_else(|| format!("Worker {} not found", script_name))
     }
 
     // 删除 Worker
@@ -365,29 +427,23 @@ async function handleRequest(request) {{
         Ok(format!("Worker {} deleted successfully", script_name))
     }
 
-    // 上传/更新 Worker
-    pub async fn upload_worker(&self, account_id: &str, script_name: &str, script_content: &str) -> Result<serde_json::Value, String> {
+    // 上传/更新 Worker（每个模块作为一个 multipart 字段，字段名即模块名）
+    pub async fn upload_worker(
+        &self,
+        account_id: &str,
+        script_name: &str,
+        modules: &[ModuleFile],
+        metadata: &serde_json::Value,
+    ) -> Result<serde_json::Value, String> {
         let url = format!("{}/accounts/{}/workers/scripts/{}", CLOUDFLARE_API_BASE, account_id, script_name);
 
-        log::info!("Uploading Worker script {} to account {}", script_name, account_id);
-
-        let metadata = serde_json::json!({
-            "main_module": "worker.js",
-            "compatibility_date": "2024-01-01"
-        });
+        log::info!("Uploading Worker script {} ({} modules) to account {}", script_name, modules.len(), account_id);
 
-        let form = reqwest::multipart::Form::new()
-            .text("metadata", metadata.to_string())
-            .part(
-                "worker.js",
-                reqwest::multipart::Part::text(script_content.to_string())
-                    .mime_str("application/javascript+module")
-                    .map_err(|e| format!("Failed to set MIME type: {}", e))?
-            );
+        let form = Self::module_form(modules, metadata)?;
 
         let response = self.client
             .put(&url)
-            .headers(self.get_headers())
+            .headers(self.get_multipart_headers())
             .multipart(form)
             .send()
             .await
@@ -401,7 +457,7 @@ async function handleRequest(request) {{
 
         if !json["success"].as_bool().unwrap_or(false) {
             let errors = json["errors"].as_array()
-                .and_then(|arr| arr.get(0))
+                .and_then(|arr| arr.first())
                 .and_then(|err| err["message"].as_str())
                 .unwrap_or("Unknown error");
             log::error!("Worker upload failed ({}): {}", status, errors);
@@ -412,9 +468,99 @@ async function handleRequest(request) {{
         Ok(json["result"].clone())
     }
 
-    // 获取 Worker 路由列表
-    pub async fn get_worker_routes(&self, zone_id: &str) -> Result<Vec<WorkerRoute>, String> {
-        let url = format!("{}/zones/{}/workers/routes", CLOUDFLARE_API_BASE, zone_id);
+    // 上传接口的 multipart 表单：metadata 字段加上每个模块
+    fn module_form(modules: &[ModuleFile], metadata: &serde_json::Value) -> Result<reqwest::multipart::Form, String> {
+        let mut form = reqwest::multipart::Form::new()
+            .text("metadata", metadata.to_string());
+        for module in modules {
+            form = form.part(
+                module.name.clone(),
+                reqwest::multipart::Part::bytes(module.data.clone())
+                    .file_name(module.name.clone())
+                    .mime_str(&module.content_type)
+                    .map_err(|e| format!("Failed to set MIME type: {}", e))?
+            );
+        }
+        Ok(form)
+    }
+
+    // 获取 Worker 的全部版本（按版本号从新到旧）
+    pub async fn list_worker_versions(&self, account_id: &str, script_name: &str) -> Result<Vec<WorkerVersion>, String> {
+        let mut versions: Vec<WorkerVersion> = Vec::new();
+        let mut page = 1;
+
+        loop {
+            let url = format!(
+                "{}/accounts/{}/workers/scripts/{}/versions?page={}&per_page=100",
+                CLOUDFLARE_API_BASE, account_id, script_name, page
+            );
+
+            let response = self.client
+                .get(&url)
+                .headers(self.get_headers())
+                .send()
+                .await
+                .map_err(|e| format!("Request failed: {}", e))?;
+
+            let json: serde_json::Value = response
+                .json()
+                .await
+                .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+            if !json["success"].as_bool().unwrap_or(false) {
+                return Err(format!("API error: {:?}", json["errors"]));
+            }
+
+            let page_versions: Vec<WorkerVersion> = serde_json::from_value(json["result"]["items"].clone())
+                .map_err(|e| format!("Failed to parse worker versions: {}", e))?;
+            let count = page_versions.len();
+            versions.extend(page_versions);
+
+            if count < 100 {
+                break;
+            }
+            page += 1;
+        }
+
+        Ok(versions)
+    }
+
+    // 上传新版本但不部署（不影响当前流量）
+    pub async fn upload_worker_version(
+        &self,
+        account_id: &str,
+        script_name: &str,
+        modules: &[ModuleFile],
+        metadata: &serde_json::Value,
+    ) -> Result<WorkerVersion, String> {
+        let url = format!("{}/accounts/{}/workers/scripts/{}/versions", CLOUDFLARE_API_BASE, account_id, script_name);
+
+        log::info!("Uploading new version of Worker {} ({} modules) to account {}", script_name, modules.len(), account_id);
+
+        let response = self.client
+            .post(&url)
+            .headers(self.get_multipart_headers())
+            .multipart(Self::module_form(modules, metadata)?)
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        serde_json::from_value(json["result"].clone())
+            .map_err(|e| format!("Failed to parse worker version: {}", e))
+    }
+
+    // 获取 Worker 的部署记录（第一条为当前生效的部署）
+    pub async fn list_worker_deployments(&self, account_id: &str, script_name: &str) -> Result<Vec<WorkerDeployment>, String> {
+        let url = format!("{}/accounts/{}/workers/scripts/{}/deployments", CLOUDFLARE_API_BASE, account_id, script_name);
 
         let response = self.client
             .get(&url)
@@ -432,33 +578,1183 @@ async function handleRequest(request) {{
             return Err(format!("API error: {:?}", json["errors"]));
         }
 
-        let routes: Vec<WorkerRoute> = json["result"]
-            .as_array()
-            .ok_or("Invalid result format")?
-            .iter()
-            .map(|v| serde_json::from_value(v.clone()).unwrap_or(WorkerRoute {
-                id: v["id"].as_str().unwrap_or("").to_string(),
-                pattern: v["pattern"].as_str().unwrap_or("").to_string(),
-                script: v["script"].as_str().map(|s| s.to_string()),
-            }))
-            .collect();
+        serde_json::from_value(json["result"]["deployments"].clone())
+            .map_err(|e| format!("Failed to parse worker deployments: {}", e))
+    }
+
+    // 创建部署：按百分比把流量分配到一个或两个版本
+    pub async fn create_worker_deployment(
+        &self,
+        account_id: &str,
+        script_name: &str,
+        deployment: &serde_json::Value,
+        force: bool,
+    ) -> Result<WorkerDeployment, String> {
+        let url = format!(
+            "{}/accounts/{}/workers/scripts/{}/deployments{}",
+            CLOUDFLARE_API_BASE, account_id, script_name, if force { "?force=true" } else { "" }
+        );
+
+        let response = self.client
+            .post(&url)
+            .headers(self.get_headers())
+            .json(deployment)
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        serde_json::from_value(json["result"].clone())
+            .map_err(|e| format!("Failed to parse worker deployment: {}", e))
+    }
+
+    // 创建日志 tail 会话，返回用于接收事件的 WebSocket 地址
+    pub async fn create_worker_tail(&self, account_id: &str, script_name: &str) -> Result<WorkerTail, String> {
+        let url = format!("{}/accounts/{}/workers/scripts/{}/tails", CLOUDFLARE_API_BASE, account_id, script_name);
+
+        let response = self.client
+            .post(&url)
+            .headers(self.get_headers())
+            .json(&json!({}))
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        serde_json::from_value(json["result"].clone())
+            .map_err(|e| format!("Failed to parse worker tail: {}", e))
+    }
+
+    // 删除 tail 会话
+    pub async fn delete_worker_tail(&self, account_id: &str, script_name: &str, tail_id: &str) -> Result<(), String> {
+        let url = format!("{}/accounts/{}/workers/scripts/{}/tails/{}", CLOUDFLARE_API_BASE, account_id, script_name, tail_id);
+
+        let response = self.client
+            .delete(&url)
+            .headers(self.get_headers())
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        Ok(())
+    }
+
+    // 获取 Worker 的 Cron Triggers
+    pub async fn get_worker_schedules(&self, account_id: &str, script_name: &str) -> Result<Vec<WorkerSchedule>, String> {
+        let url = format!("{}/accounts/{}/workers/scripts/{}/schedules", CLOUDFLARE_API_BASE, account_id, script_name);
+
+        let response = self.client
+            .get(&url)
+            .headers(self.get_headers())
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        serde_json::from_value(json["result"]["schedules"].clone())
+            .map_err(|e| format!("Failed to parse worker schedules: {}", e))
+    }
+
+    // 替换 Worker 的全部 Cron Triggers
+    pub async fn update_worker_schedules(&self, account_id: &str, script_name: &str, crons: &[String]) -> Result<Vec<WorkerSchedule>, String> {
+        let url = format!("{}/accounts/{}/workers/scripts/{}/schedules", CLOUDFLARE_API_BASE, account_id, script_name);
+        let body: Vec<serde_json::Value> = crons.iter().map(|cron| json!({ "cron": cron })).collect();
+
+        let response = self.client
+            .put(&url)
+            .headers(self.get_headers())
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        serde_json::from_value(json["result"]["schedules"].clone())
+            .map_err(|e| format!("Failed to parse worker schedules: {}", e))
+    }
+
+    // 获取 Worker 脚本设置（绑定、兼容性日期、使用模式等）
+    pub async fn get_worker_settings(&self, account_id: &str, script_name: &str) -> Result<WorkerSettings, String> {
+        let url = format!("{}/accounts/{}/workers/scripts/{}/settings", CLOUDFLARE_API_BASE, account_id, script_name);
+
+        let response = self.client
+            .get(&url)
+            .headers(self.get_headers())
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        serde_json::from_value(json["result"].clone())
+            .map_err(|e| format!("Failed to parse worker settings: {}", e))
+    }
+
+    // 获取 Worker 路由列表
+    pub async fn get_worker_routes(&self, zone_id: &str) -> Result<Vec<WorkerRoute>, String> {
+        let url = format!("{}/zones/{}/workers/routes", CLOUDFLARE_API_BASE, zone_id);
+
+        let response = self.client
+            .get(&url)
+            .headers(self.get_headers())
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        let routes: Vec<WorkerRoute> = json["result"]
+            .as_array()
+            .ok_or("Invalid result format")?
+            .iter()
+            .map(|v| serde_json::from_value(v.clone()).unwrap_or(WorkerRoute {
+                id: v["id"].as_str().unwrap_or("").to_string(),
+                pattern: v["pattern"].as_str().unwrap_or("").to_string(),
+                script: v["script"].as_str().map(|s| s.to_string()),
+            }))
+            .collect();
+
+        Ok(routes)
+    }
+
+    // 创建 Worker 路由
+    pub async fn create_worker_route(&self, zone_id: &str, pattern: &str, script_name: &str) -> Result<WorkerRoute, String> {
+        let url = format!("{}/zones/{}/workers/routes", CLOUDFLARE_API_BASE, zone_id);
+
+        let body = serde_json::json!({
+            "pattern": pattern,
+            "script": script_name
+        });
+
+        let response = self.client
+            .post(&url)
+            .headers(self.get_headers())
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        let route: WorkerRoute = serde_json::from_value(json["result"].clone())
+            .map_err(|e| format!("Failed to parse route: {}", e))?;
+
+        Ok(route)
+    }
+
+    // 删除 Worker 路由
+    pub async fn delete_worker_route(&self, zone_id: &str, route_id: &str) -> Result<String, String> {
+        let url = format!("{}/zones/{}/workers/routes/{}", CLOUDFLARE_API_BASE, zone_id, route_id);
+
+        let response = self.client
+            .delete(&url)
+            .headers(self.get_headers())
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        Ok("Route deleted successfully".to_string())
+    }
+
+    // 获取 Zone 设置
+    pub async fn get_zone_settings(&self, zone_id: &str) -> Result<Vec<ZoneSetting>, String> {
+        let url = format!("{}/zones/{}/settings", CLOUDFLARE_API_BASE, zone_id);
+
+        let response = self.client
+            .get(&url)
+            .headers(self.get_headers())
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        let settings: Vec<ZoneSetting> = serde_json::from_value(json["result"].clone())
+            .map_err(|e| format!("Failed to parse zone settings: {}", e))?;
+
+        Ok(settings)
+    }
+
+    // 更新 Zone 设置
+    pub async fn update_zone_settings(&self, zone_id: &str, settings: &[UpdateSetting]) -> Result<String, String> {
+        for setting in settings {
+            let url = format!("{}/zones/{}/settings/{}", CLOUDFLARE_API_BASE, zone_id, setting.id);
+
+            let body = json!({ "value": setting.value });
+
+            let response = self.client
+                .patch(&url)
+                .headers(self.get_headers())
+                .json(&body)
+                .send()
+                .await
+                .map_err(|e| format!("Request failed: {}", e))?;
+
+            let json: serde_json::Value = response
+                .json()
+                .await
+                .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+            if !json["success"].as_bool().unwrap_or(false) {
+                return Err(format!("API error for setting {}: {:?}", setting.id, json["errors"]));
+            }
+        }
+
+        Ok("Settings updated successfully".to_string())
+    }
+
+    // 自动优化 Zone
+    pub async fn optimize_zone(&self, zone_id: &str, mode: &OptimizeMode) -> Result<String, String> {
+        let settings = match mode {
+            OptimizeMode::Security => self.get_security_settings(),
+            OptimizeMode::Performance => self.get_performance_settings(),
+        };
+
+        self.update_zone_settings(zone_id, &settings).await
+    }
+
+    fn get_security_settings(&self) -> Vec<UpdateSetting> {
+        vec![
+            UpdateSetting { id: "security_level".to_string(), value: json!("high") },
+            UpdateSetting { id: "ssl".to_string(), value: json!("strict") },
+            UpdateSetting { id: "always_use_https".to_string(), value: json!("on") },
+            UpdateSetting { id: "automatic_https_rewrites".to_string(), value: json!("on") },
+            UpdateSetting { id: "min_tls_version".to_string(), value: json!("1.2") },
+            UpdateSetting { id: "tls_1_3".to_string(), value: json!("on") },
+            UpdateSetting { id: "opportunistic_encryption".to_string(), value: json!("on") },
+            UpdateSetting { id: "browser_check".to_string(), value: json!("on") },
+            UpdateSetting { id: "challenge_ttl".to_string(), value: json!(1800) },
+            UpdateSetting { id: "hotlink_protection".to_string(), value: json!("on") },
+            UpdateSetting { id: "cache_level".to_string(), value: json!("basic") },
+            UpdateSetting { id: "browser_cache_ttl".to_string(), value: json!(14400) },
+        ]
+    }
+
+    fn get_performance_settings(&self) -> Vec<UpdateSetting> {
+        vec![
+            UpdateSetting { id: "cache_level".to_string(), value: json!("aggressive") },
+            UpdateSetting { id: "browser_cache_ttl".to_string(), value: json!(31536000) },
+            UpdateSetting { id: "minify".to_string(), value: json!({"css": "on", "html": "on", "js": "on"}) },
+            UpdateSetting { id: "brotli".to_string(), value: json!("on") },
+            UpdateSetting { id: "http3".to_string(), value: json!("on") },
+            UpdateSetting { id: "early_hints".to_string(), value: json!("on") },
+            UpdateSetting { id: "polish".to_string(), value: json!("lossless") },
+            UpdateSetting { id: "security_level".to_string(), value: json!("low") },
+            UpdateSetting { id: "ssl".to_string(), value: json!("flexible") },
+            UpdateSetting { id: "rocket_loader".to_string(), value: json!("on") },
+            UpdateSetting { id: "0rtt".to_string(), value: json!("on") },
+        ]
+    }
+
+    // 获取防火墙规则
+    pub async fn get_firewall_rules(&self, zone_id: &str) -> Result<Vec<FirewallRule>, String> {
+        let url = format!("{}/zones/{}/firewall/rules", CLOUDFLARE_API_BASE, zone_id);
+
+        let response = self.client
+            .get(&url)
+            .headers(self.get_headers())
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        let rules: Vec<FirewallRule> = serde_json::from_value(json["result"].clone())
+            .map_err(|e| format!("Failed to parse firewall rules: {}", e))?;
+
+        Ok(rules)
+    }
+
+    // 创建防火墙规则
+    pub async fn create_firewall_rule(&self, zone_id: &str, rule: &FirewallRule) -> Result<FirewallRule, String> {
+        // 先创建 filter
+        let filter_url = format!("{}/zones/{}/filters", CLOUDFLARE_API_BASE, zone_id);
+
+        let filter_body = json!([{
+            "expression": rule.filter.expression,
+            "description": rule.filter.description
+        }]);
+
+        let filter_response = self.client
+            .post(&filter_url)
+            .headers(self.get_headers())
+            .json(&filter_body)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to create filter: {}", e))?;
+
+        let filter_json: serde_json::Value = filter_response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !filter_json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", filter_json["errors"]));
+        }
+
+        let filter_id = filter_json["result"][0]["id"]
+            .as_str()
+            .ok_or("Failed to get filter ID")?
+            .to_string();
+
+        // 然后创建规则
+        let rule_url = format!("{}/zones/{}/firewall/rules", CLOUDFLARE_API_BASE, zone_id);
+
+        let rule_body = json!([{
+            "filter": { "id": filter_id },
+            "action": rule.action,
+            "description": rule.description,
+            "paused": rule.paused
+        }]);
+
+        let rule_response = self.client
+            .post(&rule_url)
+            .headers(self.get_headers())
+            .json(&rule_body)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to create rule: {}", e))?;
+
+        let rule_json: serde_json::Value = rule_response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !rule_json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", rule_json["errors"]));
+        }
+
+        let created_rule: FirewallRule = serde_json::from_value(rule_json["result"][0].clone())
+            .map_err(|e| format!("Failed to parse rule: {}", e))?;
+
+        Ok(created_rule)
+    }
+
+    // 获取单条防火墙规则
+    pub async fn get_firewall_rule(&self, zone_id: &str, rule_id: &str) -> Result<FirewallRule, String> {
+        let url = format!("{}/zones/{}/firewall/rules/{}", CLOUDFLARE_API_BASE, zone_id, rule_id);
+
+        let response = self.client
+            .get(&url)
+            .headers(self.get_headers())
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        serde_json::from_value(json["result"].clone())
+            .map_err(|e| format!("Failed to parse rule: {}", e))
+    }
+
+    // 更新过滤器表达式
+    pub async fn update_filter(&self, zone_id: &str, filter_id: &str, filter: &FirewallFilter) -> Result<FirewallFilter, String> {
+        let url = format!("{}/zones/{}/filters/{}", CLOUDFLARE_API_BASE, zone_id, filter_id);
+
+        let mut body = json!({
+            "id": filter_id,
+            "expression": filter.expression,
+            "description": filter.description
+        });
+        // 未知暂停状态时不发送 paused，保持 filter 现有状态
+        if let Some(paused) = filter.paused {
+            body["paused"] = json!(paused);
+        }
+
+        let response = self.client
+            .put(&url)
+            .headers(self.get_headers())
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        serde_json::from_value(json["result"].clone())
+            .map_err(|e| format!("Failed to parse filter: {}", e))
+    }
+
+    // 更新防火墙规则：先更新关联的 filter，再更新规则本身；规则更新失败时回滚 filter
+    pub async fn update_firewall_rule(&self, zone_id: &str, rule_id: &str, rule: &FirewallRule) -> Result<FirewallRule, String> {
+        let current = self.get_firewall_rule(zone_id, rule_id).await?;
+        let filter_id = current.filter.id.clone()
+            .ok_or("Firewall rule has no associated filter")?;
+
+        let filter_changed = current.filter.expression != rule.filter.expression
+            || current.filter.description != rule.filter.description;
+
+        let effective_filter = if filter_changed {
+            let filter = FirewallFilter { paused: current.filter.paused, ..rule.filter.clone() };
+            self.update_filter(zone_id, &filter_id, &filter).await
+                .map_err(|e| format!("Failed to update filter: {}", e))?
+        } else {
+            current.filter.clone()
+        };
+
+        let url = format!("{}/zones/{}/firewall/rules/{}", CLOUDFLARE_API_BASE, zone_id, rule_id);
+
+        let mut body = json!({
+            "id": rule_id,
+            "filter": { "id": filter_id },
+            "action": rule.action,
+            "description": rule.description,
+            "paused": rule.paused
+        });
+        // PUT 会整体替换规则：请求未指定 priority 时沿用现有值，避免清空按优先级排序的规则
+        if let Some(priority) = rule.priority.or(current.priority) {
+            body["priority"] = json!(priority);
+        }
+
+        let result = async {
+            let response = self.client
+                .put(&url)
+                .headers(self.get_headers())
+                .json(&body)
+                .send()
+                .await
+                .map_err(|e| format!("Request failed: {}", e))?;
+
+            let json: serde_json::Value = response
+                .json()
+                .await
+                .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+            if !json["success"].as_bool().unwrap_or(false) {
+                return Err(format!("API error: {:?}", json["errors"]));
+            }
+
+            serde_json::from_value::<FirewallRule>(json["result"].clone())
+                .map_err(|e| format!("Failed to parse rule: {}", e))
+        }.await;
+
+        let mut updated_rule = match result {
+            Ok(updated_rule) => updated_rule,
+            Err(e) if filter_changed => {
+                return match self.update_filter(zone_id, &filter_id, &current.filter).await {
+                    Ok(_) => Err(format!("{} (filter change rolled back)", e)),
+                    Err(rollback) => {
+                        log::error!("Failed to roll back filter {} on zone {}: {}", filter_id, zone_id, rollback);
+                        Err(format!("{} (filter rollback failed: {})", e, rollback))
+                    }
+                };
+            }
+            Err(e) => return Err(e),
+        };
+
+        // 规则接口返回的 filter 可能只包含 ID，这里回填实际生效的表达式
+        updated_rule.filter = effective_filter;
+
+        Ok(updated_rule)
+    }
+
+    // 删除防火墙规则
+    pub async fn delete_firewall_rule(&self, zone_id: &str, rule_id: &str) -> Result<String, String> {
+        let url = format!("{}/zones/{}/firewall/rules/{}", CLOUDFLARE_API_BASE, zone_id, rule_id);
+
+        let response = self.client
+            .delete(&url)
+            .headers(self.get_headers())
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        Ok(rule_id.to_string())
+    }
+
+    // 列出 Zone 的 Rulesets
+    pub async fn list_rulesets(&self, zone_id: &str) -> Result<Vec<serde_json::Value>, String> {
+        let url = format!("{}/zones/{}/rulesets", CLOUDFLARE_API_BASE, zone_id);
+
+        let response = self.client
+            .get(&url)
+            .headers(self.get_headers())
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        Ok(json["result"].as_array().cloned().unwrap_or_default())
+    }
+
+    // 获取单个 Ruleset（包含规则）
+    pub async fn get_ruleset(&self, zone_id: &str, ruleset_id: &str) -> Result<serde_json::Value, String> {
+        let url = format!("{}/zones/{}/rulesets/{}", CLOUDFLARE_API_BASE, zone_id, ruleset_id);
+
+        let response = self.client
+            .get(&url)
+            .headers(self.get_headers())
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        Ok(json["result"].clone())
+    }
+
+    // 获取阶段入口规则集，尚未创建时返回 None
+    pub async fn get_phase_entrypoint(&self, zone_id: &str, phase: &str) -> Result<Option<Ruleset>, String> {
+        let Some(result) = self.get_phase_entrypoint_raw(zone_id, phase).await? else {
+            return Ok(None);
+        };
+
+        let ruleset: Ruleset = serde_json::from_value(result)
+            .map_err(|e| format!("Failed to parse ruleset: {}", e))?;
+
+        Ok(Some(ruleset))
+    }
+
+    // 获取阶段入口规则集的原始 JSON，整体替换时用于保留模型未覆盖的规则字段
+    pub async fn get_phase_entrypoint_raw(&self, zone_id: &str, phase: &str) -> Result<Option<serde_json::Value>, String> {
+        let url = format!("{}/zones/{}/rulesets/phases/{}/entrypoint", CLOUDFLARE_API_BASE, zone_id, phase);
+
+        let response = self.client
+            .get(&url)
+            .headers(self.get_headers())
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        Ok(Some(json["result"].clone()))
+    }
+
+    // 整体替换阶段入口规则集的规则（不存在时创建）
+    pub async fn update_phase_entrypoint<R: serde::Serialize>(&self, zone_id: &str, phase: &str, rules: &[R]) -> Result<Ruleset, String> {
+        let url = format!("{}/zones/{}/rulesets/phases/{}/entrypoint", CLOUDFLARE_API_BASE, zone_id, phase);
+
+        let response = self.client
+            .put(&url)
+            .headers(self.get_headers())
+            .json(&json!({ "rules": rules }))
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        let ruleset: Ruleset = serde_json::from_value(json["result"].clone())
+            .map_err(|e| format!("Failed to parse ruleset: {}", e))?;
+
+        Ok(ruleset)
+    }
+
+    // 向规则集添加规则
+    pub async fn create_ruleset_rule(&self, zone_id: &str, ruleset_id: &str, rule: &RulesetRule) -> Result<Ruleset, String> {
+        let url = format!("{}/zones/{}/rulesets/{}/rules", CLOUDFLARE_API_BASE, zone_id, ruleset_id);
+
+        let response = self.client
+            .post(&url)
+            .headers(self.get_headers())
+            .json(rule)
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        let ruleset: Ruleset = serde_json::from_value(json["result"].clone())
+            .map_err(|e| format!("Failed to parse ruleset: {}", e))?;
+
+        Ok(ruleset)
+    }
+
+    // 更新规则集中的规则
+    pub async fn update_ruleset_rule<R: serde::Serialize>(
+        &self,
+        zone_id: &str,
+        ruleset_id: &str,
+        rule_id: &str,
+        rule: &R,
+    ) -> Result<Ruleset, String> {
+        let url = format!("{}/zones/{}/rulesets/{}/rules/{}", CLOUDFLARE_API_BASE, zone_id, ruleset_id, rule_id);
+
+        let response = self.client
+            .patch(&url)
+            .headers(self.get_headers())
+            .json(rule)
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        let ruleset: Ruleset = serde_json::from_value(json["result"].clone())
+            .map_err(|e| format!("Failed to parse ruleset: {}", e))?;
+
+        Ok(ruleset)
+    }
+
+    // 删除规则集中的规则
+    pub async fn delete_ruleset_rule(&self, zone_id: &str, ruleset_id: &str, rule_id: &str) -> Result<Ruleset, String> {
+        let url = format!("{}/zones/{}/rulesets/{}/rules/{}", CLOUDFLARE_API_BASE, zone_id, ruleset_id, rule_id);
+
+        let response = self.client
+            .delete(&url)
+            .headers(self.get_headers())
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        let ruleset: Ruleset = serde_json::from_value(json["result"].clone())
+            .map_err(|e| format!("Failed to parse ruleset: {}", e))?;
+
+        Ok(ruleset)
+    }
+
+    // 获取 IP 访问规则（自动翻页）；scope 形如 zones/{id} 或 accounts/{id}
+    pub async fn list_access_rules(&self, scope: &str, filters: &[(&str, String)]) -> Result<Vec<AccessRule>, String> {
+        let mut rules: Vec<AccessRule> = Vec::new();
+        let mut page = 1;
+
+        let query: String = filters.iter()
+            .map(|(key, value)| format!("&{}={}", key, urlencoding::encode(value)))
+            .collect();
+
+        loop {
+            let url = format!(
+                "{}/{}/firewall/access_rules/rules?page={}&per_page=1000{}",
+                CLOUDFLARE_API_BASE, scope, page, query
+            );
+
+            let response = self.client
+                .get(&url)
+                .headers(self.get_headers())
+                .send()
+                .await
+                .map_err(|e| format!("Request failed: {}", e))?;
+
+            let json: serde_json::Value = response
+                .json()
+                .await
+                .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+            if !json["success"].as_bool().unwrap_or(false) {
+                return Err(format!("API error: {:?}", json["errors"]));
+            }
+
+            let page_rules: Vec<AccessRule> = serde_json::from_value(json["result"].clone())
+                .map_err(|e| format!("Failed to parse access rules: {}", e))?;
+            rules.extend(page_rules);
+
+            let total_pages = json["result_info"]["total_pages"].as_u64().unwrap_or(1);
+            if page >= total_pages {
+                break;
+            }
+            page += 1;
+        }
+
+        Ok(rules)
+    }
+
+    // 创建 IP 访问规则
+    pub async fn create_access_rule(&self, scope: &str, rule: &AccessRule) -> Result<AccessRule, String> {
+        let url = format!("{}/{}/firewall/access_rules/rules", CLOUDFLARE_API_BASE, scope);
+
+        let body = json!({
+            "mode": rule.mode,
+            "configuration": rule.configuration,
+            "notes": rule.notes.clone().unwrap_or_default()
+        });
+
+        let response = self.client
+            .post(&url)
+            .headers(self.get_headers())
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        serde_json::from_value(json["result"].clone())
+            .map_err(|e| format!("Failed to parse access rule: {}", e))
+    }
+
+    // 更新 IP 访问规则（仅 mode 与 notes 可修改）
+    pub async fn update_access_rule(&self, scope: &str, rule_id: &str, mode: &str, notes: &str) -> Result<AccessRule, String> {
+        let url = format!("{}/{}/firewall/access_rules/rules/{}", CLOUDFLARE_API_BASE, scope, rule_id);
+
+        let response = self.client
+            .patch(&url)
+            .headers(self.get_headers())
+            .json(&json!({ "mode": mode, "notes": notes }))
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        serde_json::from_value(json["result"].clone())
+            .map_err(|e| format!("Failed to parse access rule: {}", e))
+    }
+
+    // 删除 IP 访问规则
+    pub async fn delete_access_rule(&self, scope: &str, rule_id: &str) -> Result<String, String> {
+        let url = format!("{}/{}/firewall/access_rules/rules/{}", CLOUDFLARE_API_BASE, scope, rule_id);
+
+        let response = self.client
+            .delete(&url)
+            .headers(self.get_headers())
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        Ok(rule_id.to_string())
+    }
+
+    // 获取 Zone Lockdown 规则
+    pub async fn list_zone_lockdowns(&self, zone_id: &str) -> Result<Vec<ZoneLockdown>, String> {
+        let mut lockdowns: Vec<ZoneLockdown> = Vec::new();
+        let mut page = 1;
+
+        loop {
+            let url = format!(
+                "{}/zones/{}/firewall/lockdowns?page={}&per_page=100",
+                CLOUDFLARE_API_BASE, zone_id, page
+            );
+
+            let response = self.client
+                .get(&url)
+                .headers(self.get_headers())
+                .send()
+                .await
+                .map_err(|e| format!("Request failed: {}", e))?;
+
+            let json: serde_json::Value = response
+                .json()
+                .await
+                .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+            if !json["success"].as_bool().unwrap_or(false) {
+                return Err(format!("API error: {:?}", json["errors"]));
+            }
+
+            let page_lockdowns: Vec<ZoneLockdown> = serde_json::from_value(json["result"].clone())
+                .map_err(|e| format!("Failed to parse zone lockdowns: {}", e))?;
+            lockdowns.extend(page_lockdowns);
+
+            let total_pages = json["result_info"]["total_pages"].as_u64().unwrap_or(1);
+            if page >= total_pages {
+                break;
+            }
+            page += 1;
+        }
+
+        Ok(lockdowns)
+    }
+
+    // 创建或更新 Zone Lockdown 规则（lockdown_id 为 None 时创建）
+    pub async fn save_zone_lockdown(
+        &self,
+        zone_id: &str,
+        lockdown_id: Option<&str>,
+        lockdown: &ZoneLockdown,
+    ) -> Result<ZoneLockdown, String> {
+        let mut body = json!({
+            "urls": lockdown.urls,
+            "configurations": lockdown.configurations,
+            "description": lockdown.description.clone().unwrap_or_default(),
+            "paused": lockdown.paused
+        });
+        if let Some(priority) = lockdown.priority {
+            body["priority"] = json!(priority);
+        }
+
+        let request = match lockdown_id {
+            Some(id) => self.client.put(format!("{}/zones/{}/firewall/lockdowns/{}", CLOUDFLARE_API_BASE, zone_id, id)),
+            None => self.client.post(format!("{}/zones/{}/firewall/lockdowns", CLOUDFLARE_API_BASE, zone_id)),
+        };
+
+        let response = request
+            .headers(self.get_headers())
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        serde_json::from_value(json["result"].clone())
+            .map_err(|e| format!("Failed to parse zone lockdown: {}", e))
+    }
+
+    // 删除 Zone Lockdown 规则
+    pub async fn delete_zone_lockdown(&self, zone_id: &str, lockdown_id: &str) -> Result<String, String> {
+        let url = format!("{}/zones/{}/firewall/lockdowns/{}", CLOUDFLARE_API_BASE, zone_id, lockdown_id);
+
+        let response = self.client
+            .delete(&url)
+            .headers(self.get_headers())
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        Ok(lockdown_id.to_string())
+    }
+
+    // 获取 User Agent 拦截规则
+    pub async fn list_ua_rules(&self, zone_id: &str) -> Result<Vec<UserAgentRule>, String> {
+        let mut rules: Vec<UserAgentRule> = Vec::new();
+        let mut page = 1;
+
+        loop {
+            let url = format!(
+                "{}/zones/{}/firewall/ua_rules?page={}&per_page=100",
+                CLOUDFLARE_API_BASE, zone_id, page
+            );
+
+            let response = self.client
+                .get(&url)
+                .headers(self.get_headers())
+                .send()
+                .await
+                .map_err(|e| format!("Request failed: {}", e))?;
+
+            let json: serde_json::Value = response
+                .json()
+                .await
+                .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+            if !json["success"].as_bool().unwrap_or(false) {
+                return Err(format!("API error: {:?}", json["errors"]));
+            }
+
+            let page_rules: Vec<UserAgentRule> = serde_json::from_value(json["result"].clone())
+                .map_err(|e| format!("Failed to parse user agent rules: {}", e))?;
+            rules.extend(page_rules);
+
+            let total_pages = json["result_info"]["total_pages"].as_u64().unwrap_or(1);
+            if page >= total_pages {
+                break;
+            }
+            page += 1;
+        }
+
+        Ok(rules)
+    }
+
+    // 创建或更新 User Agent 拦截规则（rule_id 为 None 时创建）
+    pub async fn save_ua_rule(&self, zone_id: &str, rule_id: Option<&str>, rule: &UserAgentRule) -> Result<UserAgentRule, String> {
+        let mut body = json!({
+            "mode": rule.mode,
+            "configuration": rule.configuration,
+            "description": rule.description.clone().unwrap_or_default(),
+            "paused": rule.paused
+        });
+
+        let request = match rule_id {
+            Some(id) => {
+                body["id"] = json!(id);
+                self.client.put(format!("{}/zones/{}/firewall/ua_rules/{}", CLOUDFLARE_API_BASE, zone_id, id))
+            }
+            None => self.client.post(format!("{}/zones/{}/firewall/ua_rules", CLOUDFLARE_API_BASE, zone_id)),
+        };
+
+        let response = request
+            .headers(self.get_headers())
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        serde_json::from_value(json["result"].clone())
+            .map_err(|e| format!("Failed to parse user agent rule: {}", e))
+    }
+
+    // 删除 User Agent 拦截规则
+    pub async fn delete_ua_rule(&self, zone_id: &str, rule_id: &str) -> Result<String, String> {
+        let url = format!("{}/zones/{}/firewall/ua_rules/{}", CLOUDFLARE_API_BASE, zone_id, rule_id);
+
+        let response = self.client
+            .delete(&url)
+            .headers(self.get_headers())
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        Ok(rule_id.to_string())
+    }
+
+    // 获取账户下的 Lists
+    pub async fn list_rules_lists(&self, account_id: &str) -> Result<Vec<RulesList>, String> {
+        let url = format!("{}/accounts/{}/rules/lists", CLOUDFLARE_API_BASE, account_id);
+
+        let response = self.client
+            .get(&url)
+            .headers(self.get_headers())
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
+
+        serde_json::from_value(json["result"].clone())
+            .map_err(|e| format!("Failed to parse lists: {}", e))
+    }
+
+    // 创建 List
+    pub async fn create_rules_list(&self, account_id: &str, list: &RulesList) -> Result<RulesList, String> {
+        let url = format!("{}/accounts/{}/rules/lists", CLOUDFLARE_API_BASE, account_id);
+
+        let response = self.client
+            .post(&url)
+            .headers(self.get_headers())
+            .json(&json!({ "name": list.name, "kind": list.kind, "description": list.description }))
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
+        }
 
-        Ok(routes)
+        serde_json::from_value(json["result"].clone())
+            .map_err(|e| format!("Failed to parse list: {}", e))
     }
 
-    // 创建 Worker 路由
-    pub async fn create_worker_route(&self, zone_id: &str, pattern: &str, script_name: &str) -> Result<WorkerRoute, String> {
-        let url = format!("{}/zones/{}/workers/routes", CLOUDFLARE_API_BASE, zone_id);
-
-        let body = serde_json::json!({
-            "pattern": pattern,
-            "script": script_name
-        });
+    // 获取单个 List
+    pub async fn get_rules_list(&self, account_id: &str, list_id: &str) -> Result<RulesList, String> {
+        let url = format!("{}/accounts/{}/rules/lists/{}", CLOUDFLARE_API_BASE, account_id, list_id);
 
         let response = self.client
-            .post(&url)
+            .get(&url)
             .headers(self.get_headers())
-            .json(&body)
             .send()
             .await
             .map_err(|e| format!("Request failed: {}", e))?;
@@ -472,19 +1768,18 @@ async function handleRequest(request) {{
             return Err(format!("API error: {:?}", json["errors"]));
         }
 
-        let route: WorkerRoute = serde_json::from_value(json["result"].clone())
-            .map_err(|e| format!("Failed to parse route: {}", e))?;
-
-        Ok(route)
+        serde_json::from_value(json["result"].clone())
+            .map_err(|e| format!("Failed to parse list: {}", e))
     }
 
-    // 删除 Worker 路由
-    pub async fn delete_worker_route(&self, zone_id: &str, route_id: &str) -> Result<String, String> {
-        let url = format!("{}/zones/{}/workers/routes/{}", CLOUDFLARE_API_BASE, zone_id, route_id);
+    // 更新 List 描述（名称与类型创建后不可修改）
+    pub async fn update_rules_list(&self, account_id: &str, list_id: &str, description: &str) -> Result<RulesList, String> {
+        let url = format!("{}/accounts/{}/rules/lists/{}", CLOUDFLARE_API_BASE, account_id, list_id);
 
         let response = self.client
-            .delete(&url)
+            .put(&url)
             .headers(self.get_headers())
+            .json(&json!({ "description": description }))
             .send()
             .await
             .map_err(|e| format!("Request failed: {}", e))?;
@@ -498,15 +1793,16 @@ async function handleRequest(request) {{
             return Err(format!("API error: {:?}", json["errors"]));
         }
 
-        Ok("Route deleted successfully".to_string())
+        serde_json::from_value(json["result"].clone())
+            .map_err(|e| format!("Failed to parse list: {}", e))
     }
 
-    // 获取 Zone 设置
-    pub async fn get_zone_settings(&self, zone_id: &str) -> Result<Vec<ZoneSetting>, String> {
-        let url = format!("{}/zones/{}/settings", CLOUDFLARE_API_BASE, zone_id);
+    // 删除 List
+    pub async fn delete_rules_list(&self, account_id: &str, list_id: &str) -> Result<String, String> {
+        let url = format!("{}/accounts/{}/rules/lists/{}", CLOUDFLARE_API_BASE, account_id, list_id);
 
         let response = self.client
-            .get(&url)
+            .delete(&url)
             .headers(self.get_headers())
             .send()
             .await
@@ -521,23 +1817,23 @@ async function handleRequest(request) {{
             return Err(format!("API error: {:?}", json["errors"]));
         }
 
-        let settings: Vec<ZoneSetting> = serde_json::from_value(json["result"].clone())
-            .map_err(|e| format!("Failed to parse zone settings: {}", e))?;
-
-        Ok(settings)
+        Ok(list_id.to_string())
     }
 
-    // 更新 Zone 设置
-    pub async fn update_zone_settings(&self, zone_id: &str, settings: &[UpdateSetting]) -> Result<String, String> {
-        for setting in settings {
-            let url = format!("{}/zones/{}/settings/{}", CLOUDFLARE_API_BASE, zone_id, setting.id);
+    // 获取 List 的全部条目（游标翻页）
+    pub async fn list_rules_list_items(&self, account_id: &str, list_id: &str) -> Result<Vec<ListItem>, String> {
+        let mut items: Vec<ListItem> = Vec::new();
+        let mut cursor: Option<String> = None;
 
-            let body = json!({ "value": setting.value });
+        loop {
+            let mut url = format!("{}/accounts/{}/rules/lists/{}/items?per_page=500", CLOUDFLARE_API_BASE, account_id, list_id);
+            if let Some(cursor) = &cursor {
+                url.push_str(&format!("&cursor={}", urlencoding::encode(cursor)));
+            }
 
             let response = self.client
-                .patch(&url)
+                .get(&url)
                 .headers(self.get_headers())
-                .json(&body)
                 .send()
                 .await
                 .map_err(|e| format!("Request failed: {}", e))?;
@@ -548,63 +1844,32 @@ async function handleRequest(request) {{
                 .map_err(|e| format!("JSON parse failed: {}", e))?;
 
             if !json["success"].as_bool().unwrap_or(false) {
-                return Err(format!("API error for setting {}: {:?}", setting.id, json["errors"]));
+                return Err(format!("API error: {:?}", json["errors"]));
             }
-        }
-
-        Ok("Settings updated successfully".to_string())
-    }
-
-    // 自动优化 Zone
-    pub async fn optimize_zone(&self, zone_id: &str, mode: &OptimizeMode) -> Result<String, String> {
-        let settings = match mode {
-            OptimizeMode::Security => self.get_security_settings(),
-            OptimizeMode::Performance => self.get_performance_settings(),
-        };
 
-        self.update_zone_settings(zone_id, &settings).await
-    }
+            let page_items: Vec<ListItem> = serde_json::from_value(json["result"].clone())
+                .map_err(|e| format!("Failed to parse list items: {}", e))?;
+            items.extend(page_items);
 
-    fn get_security_settings(&self) -> Vec<UpdateSetting> {
-        vec![
-            UpdateSetting { id: "security_level".to_string(), value: json!("high") },
-            UpdateSetting { id: "ssl".to_string(), value: json!("strict") },
-            UpdateSetting { id: "always_use_https".to_string(), value: json!("on") },
-            UpdateSetting { id: "automatic_https_rewrites".to_string(), value: json!("on") },
-            UpdateSetting { id: "min_tls_version".to_string(), value: json!("1.2") },
-            UpdateSetting { id: "tls_1_3".to_string(), value: json!("on") },
-            UpdateSetting { id: "opportunistic_encryption".to_string(), value: json!("on") },
-            UpdateSetting { id: "browser_check".to_string(), value: json!("on") },
-            UpdateSetting { id: "challenge_ttl".to_string(), value: json!(1800) },
-            UpdateSetting { id: "hotlink_protection".to_string(), value: json!("on") },
-            UpdateSetting { id: "cache_level".to_string(), value: json!("basic") },
-            UpdateSetting { id: "browser_cache_ttl".to_string(), value: json!(14400) },
-        ]
-    }
+            cursor = json["result_info"]["cursors"]["after"].as_str()
+                .filter(|c| !c.is_empty())
+                .map(str::to_string);
+            if cursor.is_none() {
+                break;
+            }
+        }
 
-    fn get_performance_settings(&self) -> Vec<UpdateSetting> {
-        vec![
-            UpdateSetting { id: "cache_level".to_string(), value: json!("aggressive") },
-            UpdateSetting { id: "browser_cache_ttl".to_string(), value: json!(31536000) },
-            UpdateSetting { id: "minify".to_string(), value: json!({"css": "on", "html": "on", "js": "on"}) },
-            UpdateSetting { id: "brotli".to_string(), value: json!("on") },
-            UpdateSetting { id: "http3".to_string(), value: json!("on") },
-            UpdateSetting { id: "early_hints".to_string(), value: json!("on") },
-            UpdateSetting { id: "polish".to_string(), value: json!("lossless") },
-            UpdateSetting { id: "security_level".to_string(), value: json!("low") },
-            UpdateSetting { id: "ssl".to_string(), value: json!("flexible") },
-            UpdateSetting { id: "rocket_loader".to_string(), value: json!("on") },
-            UpdateSetting { id: "0rtt".to_string(), value: json!("on") },
-        ]
+        Ok(items)
     }
 
-    // 获取防火墙规则
-    pub async fn get_firewall_rules(&self, zone_id: &str) -> Result<Vec<FirewallRule>, String> {
-        let url = format!("{}/zones/{}/firewall/rules", CLOUDFLARE_API_BASE, zone_id);
+    // 追加 List 条目，返回异步批量操作 ID
+    pub async fn add_rules_list_items(&self, account_id: &str, list_id: &str, items: &[ListItem]) -> Result<String, String> {
+        let url = format!("{}/accounts/{}/rules/lists/{}/items", CLOUDFLARE_API_BASE, account_id, list_id);
 
         let response = self.client
-            .get(&url)
+            .post(&url)
             .headers(self.get_headers())
+            .json(&items)
             .send()
             .await
             .map_err(|e| format!("Request failed: {}", e))?;
@@ -618,91 +1883,47 @@ async function handleRequest(request) {{
             return Err(format!("API error: {:?}", json["errors"]));
         }
 
-        let rules: Vec<FirewallRule> = serde_json::from_value(json["result"].clone())
-            .map_err(|e| format!("Failed to parse firewall rules: {}", e))?;
-
-        Ok(rules)
+        json["result"]["operation_id"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| "Failed to get bulk operation ID".to_string())
     }
 
-    // 创建防火墙规则
-    pub async fn create_firewall_rule(&self, zone_id: &str, rule: &FirewallRule) -> Result<FirewallRule, String> {
-        // 先创建 filter
-        let filter_url = format!("{}/zones/{}/filters", CLOUDFLARE_API_BASE, zone_id);
-
-        let filter_body = json!([{
-            "expression": rule.filter.expression,
-            "description": rule.filter.description
-        }]);
+    // 整体替换 List 条目，返回异步批量操作 ID
+    pub async fn replace_rules_list_items(&self, account_id: &str, list_id: &str, items: &[ListItem]) -> Result<String, String> {
+        let url = format!("{}/accounts/{}/rules/lists/{}/items", CLOUDFLARE_API_BASE, account_id, list_id);
 
-        let filter_response = self.client
-            .post(&filter_url)
+        let response = self.client
+            .put(&url)
             .headers(self.get_headers())
-            .json(&filter_body)
+            .json(&items)
             .send()
             .await
-            .map_err(|e| format!("Failed to create filter: {}", e))?;
+            .map_err(|e| format!("Request failed: {}", e))?;
 
-        let filter_json: serde_json::Value = filter_response
+        let json: serde_json::Value = response
             .json()
             .await
             .map_err(|e| format!("JSON parse failed: {}", e))?;
 
-        if !filter_json["success"].as_bool().unwrap_or(false) {
-            return Err(format!("API error: {:?}", filter_json["errors"]));
+        if !json["success"].as_bool().unwrap_or(false) {
+            return Err(format!("API error: {:?}", json["errors"]));
         }
 
-        let filter_id = filter_json["result"][0]["id"]
+        json["result"]["operation_id"]
             .as_str()
-            .ok_or("Failed to get filter ID")?
-            .to_string();
-
-        // 然后创建规则
-        let rule_url = format!("{}/zones/{}/firewall/rules", CLOUDFLARE_API_BASE, zone_id);
-
-        let rule_body = json!([{
-            "filter": { "id": filter_id },
-            "action": rule.action,
-            "description": rule.description,
-            "paused": rule.paused
-        }]);
-
-        let rule_response = self.client
-            .post(&rule_url)
-            .headers(self.get_headers())
-            .json(&rule_body)
-            .send()
-            .await
-            .map_err(|e| format!("Failed to create rule: {}", e))?;
-
-        let rule_json: serde_json::Value = rule_response
-            .json()
-            .await
-            .map_err(|e| format!("JSON parse failed: {}", e))?;
-
-        if !rule_json["success"].as_bool().unwrap_or(false) {
-            return Err(format!("API error: {:?}", rule_json["errors"]));
-        }
-
-        let created_rule: FirewallRule = serde_json::from_value(rule_json["result"][0].clone())
-            .map_err(|e| format!("Failed to parse rule: {}", e))?;
-
-        Ok(created_rule)
+            .map(str::to_string)
+            .ok_or_else(|| "Failed to get bulk operation ID".to_string())
     }
 
-    // 更新防火墙规则
-    pub async fn update_firewall_rule(&self, zone_id: &str, rule_id: &str, rule: &FirewallRule) -> Result<FirewallRule, String> {
-        let url = format!("{}/zones/{}/firewall/rules/{}", CLOUDFLARE_API_BASE, zone_id, rule_id);
-
-        let body = json!({
-            "action": rule.action,
-            "description": rule.description,
-            "paused": rule.paused
-        });
+    // 按 ID 删除 List 条目，返回异步批量操作 ID
+    pub async fn delete_rules_list_items(&self, account_id: &str, list_id: &str, item_ids: &[String]) -> Result<String, String> {
+        let url = format!("{}/accounts/{}/rules/lists/{}/items", CLOUDFLARE_API_BASE, account_id, list_id);
 
         let response = self.client
-            .put(&url)
+            .delete(&url)
             .headers(self.get_headers())
-            .json(&body)
+            .json(&json!({ "items": item_ids.iter().map(|id| json!({ "id": id })).collect::<Vec<_>>() }))
             .send()
             .await
             .map_err(|e| format!("Request failed: {}", e))?;
@@ -716,18 +1937,18 @@ async function handleRequest(request) {{
             return Err(format!("API error: {:?}", json["errors"]));
         }
 
-        let updated_rule: FirewallRule = serde_json::from_value(json["result"].clone())
-            .map_err(|e| format!("Failed to parse rule: {}", e))?;
-
-        Ok(updated_rule)
+        json["result"]["operation_id"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| "Failed to get bulk operation ID".to_string())
     }
 
-    // 删除防火墙规则
-    pub async fn delete_firewall_rule(&self, zone_id: &str, rule_id: &str) -> Result<String, String> {
-        let url = format!("{}/zones/{}/firewall/rules/{}", CLOUDFLARE_API_BASE, zone_id, rule_id);
+    // 查询 List 批量操作状态
+    pub async fn get_rules_list_operation(&self, account_id: &str, operation_id: &str) -> Result<ListOperation, String> {
+        let url = format!("{}/accounts/{}/rules/lists/bulk_operations/{}", CLOUDFLARE_API_BASE, account_id, operation_id);
 
         let response = self.client
-            .delete(&url)
+            .get(&url)
             .headers(self.get_headers())
             .send()
             .await
@@ -742,7 +1963,8 @@ async function handleRequest(request) {{
             return Err(format!("API error: {:?}", json["errors"]));
         }
 
-        Ok(rule_id.to_string())
+        serde_json::from_value(json["result"].clone())
+            .map_err(|e| format!("Failed to parse bulk operation: {}", e))
     }
 
     // 获取 Analytics 数据
@@ -758,128 +1980,60 @@ async function handleRequest(request) {{
             _ => (now - Duration::hours(24), "httpRequests1hGroups"),
         };
 
-        let since_str = since.format("%Y-%m-%dT%H:%M:%SZ").to_string();
-        let until_str = now.format("%Y-%m-%dT%H:%M:%SZ").to_string();
-
-        // 使用 Cloudflare GraphQL API
-        let url = format!("{}/graphql", CLOUDFLARE_API_BASE);
-
-        // 构建 GraphQL 查询 - 使用更简单和更可靠的查询
-        let query_string = if interval == "httpRequests1hGroups" {
-            format!(r#"
-                query {{
-                    viewer {{
-                        zones(filter: {{zoneTag: "{}"}}) {{
+        // 小时粒度按时间过滤，天粒度按日期过滤
+        let (query, since_str, until_str) = if interval == "httpRequests1hGroups" {
+            (
+                r#"
+                query ($zoneTag: string, $since: Time, $until: Time) {
+                    viewer {
+                        zones(filter: {zoneTag: $zoneTag}) {
                             httpRequests1hGroups(
                                 limit: 168
-                                filter: {{
-                                    datetime_geq: "{}"
-                                    datetime_leq: "{}"
-                                }}
-                            ) {{
-                                dimensions {{
-                                    datetime
-                                }}
-                                sum {{
-                                    requests
-                                    cachedRequests
-                                    bytes
-                                    threats
-                                }}
-                            }}
-                        }}
-                    }}
-                }}
-            "#, zone_id, since_str, until_str)
+                                filter: { datetime_geq: $since, datetime_leq: $until }
+                            ) {
+                                dimensions { datetime }
+                                sum { requests cachedRequests bytes threats }
+                            }
+                        }
+                    }
+                }
+                "#,
+                since.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+                now.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            )
         } else {
-            format!(r#"
-                query {{
-                    viewer {{
-                        zones(filter: {{zoneTag: "{}"}}) {{
+            (
+                r#"
+                query ($zoneTag: string, $since: Date, $until: Date) {
+                    viewer {
+                        zones(filter: {zoneTag: $zoneTag}) {
                             httpRequests1dGroups(
                                 limit: 31
-                                filter: {{
-                                    date_geq: "{}"
-                                    date_leq: "{}"
-                                }}
-                            ) {{
-                                dimensions {{
-                                    date
-                                }}
-                                sum {{
-                                    requests
-                                    cachedRequests
-                                    bytes
-                                    threats
-                                }}
-                            }}
-                        }}
-                    }}
-                }}
-            "#, zone_id, since.format("%Y-%m-%d"), now.format("%Y-%m-%d"))
+                                filter: { date_geq: $since, date_leq: $until }
+                            ) {
+                                dimensions { date }
+                                sum { requests cachedRequests bytes threats }
+                            }
+                        }
+                    }
+                }
+                "#,
+                since.format("%Y-%m-%d").to_string(),
+                now.format("%Y-%m-%d").to_string(),
+            )
         };
 
-        let graphql_query = json!({
-            "query": query_string
-        });
-
         log::info!("Sending GraphQL query for zone {} with time range {}", zone_id, time_range);
-        log::debug!("GraphQL query: {}", serde_json::to_string_pretty(&graphql_query).unwrap_or_default());
-
-        // GraphQL API 使用专用的认证头（API Token 优先）
-        let response = self.client
-            .post(&url)
-            .headers(self.get_graphql_headers())
-            .json(&graphql_query)
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
-
-        let json: serde_json::Value = response
-            .json()
-            .await
-            .map_err(|e| format!("JSON parse failed: {}", e))?;
-
-        log::info!("GraphQL response received. Has data: {}, Has errors: {}",
-            json.get("data").is_some(),
-            json.get("errors").map(|e| !e.is_null()).unwrap_or(false));
-
-        // 打印响应的主要结构（用于调试）
-        if let Some(data) = json.get("data") {
-            if data.is_null() {
-                log::warn!("GraphQL data field is null");
-            } else {
-                log::info!("GraphQL data structure: viewer={}, zones={}",
-                    data.get("viewer").is_some(),
-                    data.get("viewer").and_then(|v| v.get("zones")).is_some());
-            }
-        }
-
-        // 检查 GraphQL 错误
-        if let Some(errors) = json.get("errors") {
-            if !errors.is_null() {
-                log::error!("GraphQL errors: {:?}", errors);
-                return Err(format!("GraphQL API 错误: {:?}", errors));
-            }
-        }
 
-        // 检查是否有 data
-        let data_field = json.get("data");
-        if data_field.is_none() || data_field.unwrap().is_null() {
-            log::error!("No data in GraphQL response. Full response: {:?}", json);
-            return Err("GraphQL 未返回数据。可能原因：\n1. API Token 权限不足（需要 Zone.Analytics Read 权限）\n2. Zone ID 不正确\n3. 该域名可能没有足够的历史数据".to_string());
-        }
+        let zone = self.query_zone_analytics(query, json!({
+            "zoneTag": zone_id,
+            "since": since_str,
+            "until": until_str,
+        })).await?;
 
         // 解析 GraphQL 响应
-        let groups_key = if interval == "httpRequests1hGroups" { "httpRequests1hGroups" } else { "httpRequests1dGroups" };
         let time_key = if interval == "httpRequests1hGroups" { "datetime" } else { "date" };
-
-        let data = json.get("data")
-            .and_then(|d| d.get("viewer"))
-            .and_then(|v| v.get("zones"))
-            .and_then(|z| z.as_array())
-            .and_then(|arr| arr.get(0))
-            .and_then(|zone| zone.get(groups_key))
+        let data = zone.get(interval)
             .and_then(|g| g.as_array())
             .ok_or_else(|| "Failed to parse GraphQL response structure".to_string())?;
 
@@ -999,6 +2153,37 @@ async function handleRequest(request) {{
         })
     }
 
+    // 执行 Zone 范围的 GraphQL Analytics 查询，返回 viewer.zones[0]
+    pub async fn query_zone_analytics(&self, query: &str, variables: serde_json::Value) -> Result<serde_json::Value, String> {
+        let url = format!("{}/graphql", CLOUDFLARE_API_BASE);
+
+        let response = self.client
+            .post(&url)
+            .headers(self.get_graphql_headers())
+            .json(&json!({ "query": query, "variables": variables }))
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+        if let Some(errors) = json.get("errors") {
+            if !errors.is_null() {
+                log::error!("GraphQL errors: {:?}", errors);
+                return Err(format!("GraphQL API 错误: {:?}", errors));
+            }
+        }
+
+        json["data"]["viewer"]["zones"]
+            .as_array()
+            .and_then(|zones| zones.first())
+            .cloned()
+            .ok_or_else(|| "GraphQL 未返回数据，请检查 Zone ID 与 Zone.Analytics Read 权限".to_string())
+    }
+
     // 清除缓存
     pub async fn purge_cache(&self, request: &PurgeCacheRequest) -> Result<PurgeCacheResponse, String> {
         let url = format!("{}/zones/{}/purge_cache", CLOUDFLARE_API_BASE, request.zone_id);
@@ -1044,7 +2229,7 @@ async function handleRequest(request) {{
 
         if !json["success"].as_bool().unwrap_or(false) {
             let errors = json["errors"].as_array()
-                .and_then(|arr| arr.get(0))
+                .and_then(|arr| arr.first())
                 .and_then(|err| err["message"].as_str())
                 .unwrap_or("Unknown error");
             return Err(format!("API error ({}): {}", status, errors));
@@ -1084,7 +2269,7 @@ async function handleRequest(request) {{
 
         if !json["success"].as_bool().unwrap_or(false) {
             let errors = json["errors"].as_array()
-                .and_then(|arr| arr.get(0))
+                .and_then(|arr| arr.first())
                 .and_then(|err| err["message"].as_str())
                 .unwrap_or("Unknown error");
             log::error!("SSL certificates API error ({}): {}, Full response: {:?}", status, errors, json);
@@ -1221,7 +2406,7 @@ async function handleRequest(request) {{
 
         if !json["success"].as_bool().unwrap_or(false) {
             let errors = json["errors"].as_array()
-                .and_then(|arr| arr.get(0))
+                .and_then(|arr| arr.first())
                 .and_then(|err| err["message"].as_str())
                 .unwrap_or("Unknown error");
             log::error!("Page rules API error ({}): {}, Full response: {:?}", status, errors, json);
@@ -1257,7 +2442,7 @@ async function handleRequest(request) {{
 
         if !json["success"].as_bool().unwrap_or(false) {
             let errors = json["errors"].as_array()
-                .and_then(|arr| arr.get(0))
+                .and_then(|arr| arr.first())
                 .and_then(|err| err["message"].as_str())
                 .unwrap_or("Unknown error");
             return Err(format!("API error ({}): {}", status, errors));
@@ -1292,7 +2477,7 @@ async function handleRequest(request) {{
 
         if !json["success"].as_bool().unwrap_or(false) {
             let errors = json["errors"].as_array()
-                .and_then(|arr| arr.get(0))
+                .and_then(|arr| arr.first())
                 .and_then(|err| err["message"].as_str())
                 .unwrap_or("Unknown error");
             return Err(format!("API error ({}): {}", status, errors));
@@ -1326,7 +2511,7 @@ async function handleRequest(request) {{
 
         if !json["success"].as_bool().unwrap_or(false) {
             let errors = json["errors"].as_array()
-                .and_then(|arr| arr.get(0))
+                .and_then(|arr| arr.first())
                 .and_then(|err| err["message"].as_str())
                 .unwrap_or("Unknown error");
             return Err(format!("API error ({}): {}", status, errors));
@@ -1652,7 +2837,7 @@ async function handleRequest(request) {{
         }
 
         let namespaces: Vec<serde_json::Value> = json["result"].as_array()
-            .map(|arr| arr.clone())
+            .cloned()
             .unwrap_or_default();
 
         Ok(namespaces)
@@ -1734,12 +2919,53 @@ async function handleRequest(request) {{
         }
 
         let keys: Vec<serde_json::Value> = json["result"].as_array()
-            .map(|arr| arr.clone())
+            .cloned()
             .unwrap_or_default();
 
         Ok(keys)
     }
 
+    // 列出 Namespace 中的所有 KV 键（按 cursor 翻页）
+    pub async fn list_all_kv_keys(&self, account_id: &str, namespace_id: &str) -> Result<Vec<serde_json::Value>, String> {
+        let mut keys = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let mut url = format!("{}/accounts/{}/storage/kv/namespaces/{}/keys?limit=1000",
+                CLOUDFLARE_API_BASE, account_id, namespace_id);
+            if let Some(c) = &cursor {
+                url = format!("{}&cursor={}", url, urlencoding::encode(c));
+            }
+
+            let response = self.client
+                .get(&url)
+                .headers(self.get_headers())
+                .send()
+                .await
+                .map_err(|e| format!("Request failed: {}", e))?;
+
+            let json: serde_json::Value = response
+                .json()
+                .await
+                .map_err(|e| format!("JSON parse failed: {}", e))?;
+
+            if !json["success"].as_bool().unwrap_or(false) {
+                return Err(format!("API error: {:?}", json["errors"]));
+            }
+
+            keys.extend(json["result"].as_array().cloned().unwrap_or_default());
+
+            cursor = json["result_info"]["cursor"].as_str()
+                .filter(|c| !c.is_empty())
+                .map(|c| c.to_string());
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        Ok(keys)
+    }
+
     // 读取 KV 值
     pub async fn read_kv_value(&self, account_id: &str, namespace_id: &str, key: &str) -> Result<String, String> {
         let url = format!("{}/accounts/{}/storage/kv/namespaces/{}/values/{}",
@@ -1853,7 +3079,7 @@ async function handleRequest(request) {{
         }
 
         let databases: Vec<serde_json::Value> = json["result"].as_array()
-            .map(|arr| arr.clone())
+            .cloned()
             .unwrap_or_default();
 
         Ok(databases)
@@ -0,0 +1,291 @@
+use crate::cloudflare::CloudflareClient;
+use crate::expression;
+use crate::models::*;
+use serde_json::{json, Value};
+use std::future::Future;
+
+// WAF 自定义规则所在的阶段
+pub const PHASE: &str = "http_request_firewall_custom";
+
+// 自定义规则阶段支持的动作
+const ALLOWED_ACTIONS: &[&str] = &["block", "challenge", "js_challenge", "managed_challenge", "log", "skip"];
+
+fn to_list(ruleset: Option<Ruleset>) -> PhaseRuleList {
+    match ruleset {
+        Some(ruleset) => PhaseRuleList { ruleset_id: Some(ruleset.id), rules: ruleset.rules },
+        None => PhaseRuleList { ruleset_id: None, rules: Vec::new() },
+    }
+}
+
+async fn entrypoint(client: &CloudflareClient, zone_id: &str) -> Result<Ruleset, String> {
+    client.get_phase_entrypoint(zone_id, PHASE).await?
+        .ok_or_else(|| "No custom rules are configured for this zone".to_string())
+}
+
+pub fn validate(rule: &RulesetRule) -> Result<(), String> {
+    expression::validate(&rule.expression)?;
+    if !ALLOWED_ACTIONS.contains(&rule.action.as_str()) {
+        return Err(format!(
+            "Unsupported action '{}' for custom rules (expected one of: {})",
+            rule.action,
+            ALLOWED_ACTIONS.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+pub async fn list_rules(client: &CloudflareClient, zone_id: &str) -> Result<PhaseRuleList, String> {
+    Ok(to_list(client.get_phase_entrypoint(zone_id, PHASE).await?))
+}
+
+// 创建规则；入口规则集不存在时一并创建
+pub async fn create_rule(client: &CloudflareClient, zone_id: &str, rule: &RulesetRule) -> Result<PhaseRuleList, String> {
+    validate(rule)?;
+    let mut rule = rule.clone();
+    rule.id = None;
+
+    let ruleset = match client.get_phase_entrypoint(zone_id, PHASE).await? {
+        Some(ruleset) => client.create_ruleset_rule(zone_id, &ruleset.id, &rule).await?,
+        None => {
+            rule.position = None;
+            client.update_phase_entrypoint(zone_id, PHASE, &[rule]).await?
+        }
+    };
+
+    Ok(to_list(Some(ruleset)))
+}
+
+pub async fn update_rule(
+    client: &CloudflareClient,
+    zone_id: &str,
+    rule_id: &str,
+    rule: &RulesetRule,
+) -> Result<PhaseRuleList, String> {
+    validate(rule)?;
+    let ruleset = entrypoint(client, zone_id).await?;
+    let mut rule = rule.clone();
+    rule.id = None;
+
+    let ruleset = client.update_ruleset_rule(zone_id, &ruleset.id, rule_id, &rule).await?;
+    Ok(to_list(Some(ruleset)))
+}
+
+pub async fn delete_rule(client: &CloudflareClient, zone_id: &str, rule_id: &str) -> Result<PhaseRuleList, String> {
+    let ruleset = entrypoint(client, zone_id).await?;
+    let ruleset = client.delete_ruleset_rule(zone_id, &ruleset.id, rule_id).await?;
+    Ok(to_list(Some(ruleset)))
+}
+
+pub async fn set_enabled(
+    client: &CloudflareClient,
+    zone_id: &str,
+    rule_id: &str,
+    enabled: bool,
+) -> Result<PhaseRuleList, String> {
+    // 只切换 enabled，其余字段按原始 JSON 带回
+    let entrypoint = client.get_phase_entrypoint_raw(zone_id, PHASE).await?
+        .ok_or_else(|| "No custom rules are configured for this zone".to_string())?;
+    let ruleset_id = entrypoint["id"].as_str().unwrap_or_default();
+    let mut rule = entrypoint["rules"].as_array()
+        .and_then(|rules| rules.iter().find(|r| r["id"].as_str() == Some(rule_id)))
+        .cloned()
+        .ok_or_else(|| format!("Rule {} not found", rule_id))?;
+
+    writable_rule(&mut rule);
+    rule["enabled"] = Value::Bool(enabled);
+
+    let ruleset = client.update_ruleset_rule(zone_id, ruleset_id, rule_id, &rule).await?;
+    Ok(to_list(Some(ruleset)))
+}
+
+// 去掉规则原始 JSON 中的只读字段，供所有写回路径使用。
+// 保留 id：整体替换入口规则集时，带 id 的规则原地更新并保持 ID 不变，否则会被当作新规则重建；
+// 单条规则 PATCH 时 id 与路径中的规则 ID 一致
+fn writable_rule(rule: &mut Value) {
+    if let Some(fields) = rule.as_object_mut() {
+        fields.remove("version");
+        fields.remove("last_updated");
+    }
+}
+
+// 读取入口规则集中每条规则的原始 JSON（已去掉只读字段）；整体替换时原样带回，避免丢失模型未覆盖的字段
+async fn raw_rules(client: &CloudflareClient, zone_id: &str, phase: &str) -> Result<Option<Vec<Value>>, String> {
+    Ok(client.get_phase_entrypoint_raw(zone_id, phase).await?
+        .map(|ruleset| {
+            let mut rules = ruleset["rules"].as_array().cloned().unwrap_or_default();
+            rules.iter_mut().for_each(writable_rule);
+            rules
+        }))
+}
+
+// 按给定 ID 顺序重排规则（整体替换入口规则集）
+pub async fn reorder(client: &CloudflareClient, zone_id: &str, rule_ids: &[String]) -> Result<PhaseRuleList, String> {
+    let mut remaining = raw_rules(client, zone_id, PHASE).await?
+        .ok_or_else(|| "No custom rules are configured for this zone".to_string())?;
+
+    let mut ordered = Vec::with_capacity(remaining.len());
+    for rule_id in rule_ids {
+        let index = remaining.iter()
+            .position(|r| r["id"].as_str() == Some(rule_id.as_str()))
+            .ok_or_else(|| format!("Rule {} not found", rule_id))?;
+        ordered.push(remaining.remove(index));
+    }
+    ordered.extend(remaining);
+
+    let ruleset = client.update_phase_entrypoint(zone_id, PHASE, &ordered).await?;
+    Ok(to_list(Some(ruleset)))
+}
+
+// 旧版动作的评估优先级（同一优先级内依次为 log、bypass、allow、质询、block）
+fn action_precedence(action: &str) -> u8 {
+    match action {
+        "log" => 0,
+        "bypass" => 1,
+        "allow" => 2,
+        "managed_challenge" => 3,
+        "js_challenge" => 4,
+        "challenge" => 5,
+        "block" => 6,
+        _ => 7,
+    }
+}
+
+// 按旧版防火墙的评估顺序排序：先按 priority（未设置的排在最后），再按动作优先级
+pub fn sort_legacy(rules: &mut [FirewallRule]) {
+    rules.sort_by_key(|r| (r.priority.is_none(), r.priority, action_precedence(&r.action)));
+}
+
+// 将旧版防火墙规则（FirewallRule + FirewallFilter）转换为自定义规则
+pub fn convert_legacy(rule: &FirewallRule) -> Result<MigratedRule, String> {
+    let mut notes = Vec::new();
+
+    let (action, action_parameters) = match rule.action.as_str() {
+        "block" | "challenge" | "js_challenge" | "managed_challenge" | "log" => (rule.action.clone(), None),
+        "allow" => {
+            notes.push("allow converted to skip of the remaining custom rules; review other security features it used to bypass".to_string());
+            ("skip".to_string(), Some(json!({ "ruleset": "current" })))
+        }
+        "bypass" => {
+            notes.push("bypass converted to skip of the remaining custom rules; re-select the bypassed products as skip phases".to_string());
+            ("skip".to_string(), Some(json!({ "ruleset": "current" })))
+        }
+        other => return Err(format!("Action '{}' has no custom rule equivalent", other)),
+    };
+
+    expression::validate(&rule.filter.expression)?;
+    if rule.paused {
+        notes.push("Legacy rule was paused; converted rule is disabled".to_string());
+    }
+
+    Ok(MigratedRule {
+        legacy_id: rule.id.clone(),
+        rule: RulesetRule {
+            id: None,
+            expression: rule.filter.expression.clone(),
+            action,
+            description: rule.description.clone().or_else(|| rule.filter.description.clone()),
+            enabled: !rule.paused,
+            action_parameters,
+            ratelimit: None,
+            logging: None,
+            rule_ref: rule.id.clone(),
+            position: None,
+        },
+        notes,
+    })
+}
+
+// 将迁移后的规则追加到指定阶段的入口规则集，按 ref 跳过已迁移的规则；返回规则集 ID
+pub async fn append_migrated(
+    client: &CloudflareClient,
+    zone_id: &str,
+    phase: &str,
+    converted: &[MigratedRule],
+) -> Result<String, String> {
+    let mut rules = raw_rules(client, zone_id, phase).await?.unwrap_or_default();
+    for migrated in converted {
+        let rule_ref = migrated.rule.rule_ref.as_deref();
+        let already = rule_ref.is_some() && rules.iter().any(|r| r["ref"].as_str() == rule_ref);
+        if !already {
+            rules.push(json!(migrated.rule));
+        }
+    }
+
+    let ruleset = client.update_phase_entrypoint(zone_id, phase, &rules).await?;
+    Ok(ruleset.id)
+}
+
+// 通用的旧版规则迁移流程：拉取、逐条转换、追加到指定阶段，按需删除已迁移的旧规则。
+// convert 返回的错误应带上旧规则 ID；kind 用于日志与错误信息（如 "firewall rule"）
+pub async fn migrate_legacy<T, Fetch, Convert, Delete, DeleteFut>(
+    client: &CloudflareClient,
+    request: &MigrateLegacyRulesRequest,
+    phase: &str,
+    kind: &str,
+    fetch: Fetch,
+    convert: Convert,
+    delete: Delete,
+) -> Result<RuleMigrationReport, String>
+where
+    Fetch: Future<Output = Result<Vec<T>, String>>,
+    Convert: Fn(&T) -> Result<MigratedRule, String>,
+    Delete: Fn(String) -> DeleteFut,
+    DeleteFut: Future<Output = Result<String, String>>,
+{
+    let zone_id = &request.zone_id;
+    let legacy = fetch.await?;
+
+    let mut report = RuleMigrationReport {
+        converted: Vec::new(),
+        applied: false,
+        ruleset_id: None,
+        legacy_deleted: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    for item in &legacy {
+        match convert(item) {
+            Ok(migrated) => report.converted.push(migrated),
+            Err(e) => report.errors.push(e),
+        }
+    }
+
+    if request.dry_run || report.converted.is_empty() {
+        return Ok(report);
+    }
+
+    report.ruleset_id = Some(append_migrated(client, zone_id, phase, &report.converted).await?);
+    report.applied = true;
+
+    log::info!("Migrated {} {}(s) to phase {} on zone {}", report.converted.len(), kind, phase, zone_id);
+
+    if request.delete_legacy {
+        for migrated in &report.converted {
+            let Some(legacy_id) = &migrated.legacy_id else { continue };
+            match delete(legacy_id.clone()).await {
+                Ok(_) => report.legacy_deleted.push(legacy_id.clone()),
+                Err(e) => report.errors.push(format!("Failed to delete {} {}: {}", kind, legacy_id, e)),
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+// 按旧版评估顺序迁移 Zone 的全部防火墙规则；已按 ref 迁移过的规则不会重复添加
+pub async fn migrate(client: &CloudflareClient, request: &MigrateLegacyRulesRequest) -> Result<RuleMigrationReport, String> {
+    let zone_id = request.zone_id.as_str();
+    migrate_legacy(
+        client,
+        request,
+        PHASE,
+        "firewall rule",
+        async {
+            let mut rules = client.get_firewall_rules(zone_id).await?;
+            sort_legacy(&mut rules);
+            Ok(rules)
+        },
+        |rule| convert_legacy(rule).map_err(|e| format!("{}: {}", rule.id.as_deref().unwrap_or("?"), e)),
+        move |legacy_id| async move { client.delete_firewall_rule(zone_id, &legacy_id).await },
+    ).await
+}
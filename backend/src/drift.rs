@@ -0,0 +1,462 @@
+use crate::cloudflare::CloudflareClient;
+use crate::models::*;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::sync::RwLock;
+use std::time::Duration;
+
+// 每个 Zone 保留的最大报告数量
+const MAX_REPORTS_PER_ZONE: usize = 100;
+
+// 比较时忽略的易变字段（只记录在报告中，不视为漂移）
+const VOLATILE_FIELDS: &[&str] = &["modified_on", "created_on", "meta", "editable", "locked"];
+
+struct DriftBaseline {
+    credentials: CloudflareCredentials,
+    info: DriftBaselineInfo,
+}
+
+// 配置漂移基线与检测报告（内存存储，服务重启后需重新设置基线）
+#[derive(Default)]
+pub struct DriftStore {
+    baselines: RwLock<HashMap<String, DriftBaseline>>,
+    reports: RwLock<HashMap<String, VecDeque<DriftReport>>>,
+    http: reqwest::Client,
+}
+
+impl DriftStore {
+    // 将 Zone 当前配置保存为基线（覆盖同一 API Token 的已有基线）
+    pub async fn set_baseline(
+        &self,
+        credentials: &CloudflareCredentials,
+        request: &SetDriftBaselineRequest,
+    ) -> Result<DriftBaselineInfo, String> {
+        let client = CloudflareClient::new(credentials)?;
+        let zone = client.get_zone(&request.zone_id).await?;
+        let snapshot = capture_snapshot(&client, &request.zone_id).await;
+
+        let info = DriftBaselineInfo {
+            zone_id: request.zone_id.clone(),
+            zone_name: Some(zone.name),
+            account_id: request.account_id.clone().or(zone.account.map(|a| a.id)),
+            webhook_url: request.webhook_url.clone().filter(|u| !u.is_empty()),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            last_checked_at: None,
+            snapshot,
+        };
+
+        // 其他 API Token 创建的基线不能被覆盖
+        let mut baselines = self.baselines.write().unwrap();
+        if baselines.get(&request.zone_id).is_some_and(|b| !owns(b, credentials)) {
+            return Err(format!("Zone {} already has a drift baseline created with different credentials", request.zone_id));
+        }
+        baselines.insert(request.zone_id.clone(), DriftBaseline {
+            credentials: credentials.clone(),
+            info: info.clone(),
+        });
+
+        log::info!("Drift baseline saved for zone {}", request.zone_id);
+
+        Ok(info)
+    }
+
+    // 列出当前 API Token 创建的基线
+    pub fn list_baselines(&self, credentials: &CloudflareCredentials) -> Vec<DriftBaselineInfo> {
+        self.baselines.read().unwrap()
+            .values()
+            .filter(|b| owns(b, credentials))
+            .map(|b| b.info.clone())
+            .collect()
+    }
+
+    pub fn remove_baseline(&self, credentials: &CloudflareCredentials, zone_id: &str) -> Result<String, String> {
+        let mut baselines = self.baselines.write().unwrap();
+        match baselines.get(zone_id) {
+            Some(b) if owns(b, credentials) => {
+                baselines.remove(zone_id);
+                self.reports.write().unwrap().remove(zone_id);
+                Ok(format!("Drift baseline for zone {} removed", zone_id))
+            }
+            _ => Err(format!("No drift baseline found for zone {}", zone_id)),
+        }
+    }
+
+    // 立即检测单个 Zone（需使用创建基线时的 API Token）
+    pub async fn check_zone_for(&self, credentials: &CloudflareCredentials, zone_id: &str) -> Result<DriftReport, String> {
+        let authorized = self.baselines.read().unwrap()
+            .get(zone_id)
+            .map(|b| owns(b, credentials))
+            .unwrap_or(false);
+
+        if !authorized {
+            return Err(format!("No drift baseline found for zone {}", zone_id));
+        }
+
+        self.check_zone(zone_id).await
+    }
+
+    // 重新读取 Zone 配置并与基线比较
+    async fn check_zone(&self, zone_id: &str) -> Result<DriftReport, String> {
+        let (credentials, info) = {
+            let baselines = self.baselines.read().unwrap();
+            let baseline = baselines.get(zone_id)
+                .ok_or_else(|| format!("No drift baseline found for zone {}", zone_id))?;
+            (baseline.credentials.clone(), baseline.info.clone())
+        };
+
+        let client = CloudflareClient::new(&credentials)?;
+        let current = capture_snapshot(&client, zone_id).await;
+        let mut changes = diff_snapshots(&info.snapshot, &current);
+        let mut errors = current.errors.clone();
+
+        // 每次都与原始基线比较，同一变更会在后续报告中重复出现：沿用上次报告中的变更者
+        let previous = self.reports.read().unwrap()
+            .get(zone_id)
+            .and_then(|history| history.front().cloned());
+        if let Some(previous) = &previous {
+            carry_attribution(&mut changes, &previous.changes);
+        }
+
+        // 审计日志从基线创建时起查询，覆盖上次检测之前发生的变更
+        if changes.iter().any(|c| c.changed_by.is_none()) {
+            if let Some(account_id) = &info.account_id {
+                match client.get_audit_logs(account_id, info.zone_name.as_deref(), &info.created_at).await {
+                    Ok(logs) => attribute_changes(&mut changes, &logs),
+                    Err(e) => errors.push(format!("audit_logs: {}", e)),
+                }
+            }
+        }
+
+        let checked_at = chrono::Utc::now().to_rfc3339();
+        let report = DriftReport {
+            id: uuid::Uuid::new_v4().to_string(),
+            zone_id: zone_id.to_string(),
+            zone_name: info.zone_name.clone(),
+            checked_at: checked_at.clone(),
+            drifted: !changes.is_empty(),
+            changes,
+            errors,
+        };
+
+        if let Some(baseline) = self.baselines.write().unwrap().get_mut(zone_id) {
+            baseline.info.last_checked_at = Some(checked_at);
+        }
+
+        {
+            let mut reports = self.reports.write().unwrap();
+            let history = reports.entry(zone_id.to_string()).or_default();
+            history.push_front(report.clone());
+            history.truncate(MAX_REPORTS_PER_ZONE);
+        }
+
+        if report.drifted {
+            log::warn!("Configuration drift detected for zone {}: {} change(s)", zone_id, report.changes.len());
+            // 只在变更集合与上次报告不同时通知，避免每个检测周期重复推送相同报告
+            let unchanged = previous.is_some_and(|p| same_changes(&p.changes, &report.changes));
+            if let (Some(webhook_url), false) = (&info.webhook_url, unchanged) {
+                self.notify_webhook(webhook_url, &report).await;
+            }
+        }
+
+        Ok(report)
+    }
+
+    // 查询检测报告（最新的在前）
+    pub fn reports(&self, credentials: &CloudflareCredentials, query: &GetDriftReportsRequest) -> Vec<DriftReport> {
+        let zone_ids: Vec<String> = self.baselines.read().unwrap()
+            .values()
+            .filter(|b| owns(b, credentials))
+            .filter(|b| query.zone_id.as_ref().map(|z| z == &b.info.zone_id).unwrap_or(true))
+            .map(|b| b.info.zone_id.clone())
+            .collect();
+
+        let reports = self.reports.read().unwrap();
+        let mut result: Vec<DriftReport> = zone_ids.iter()
+            .filter_map(|z| reports.get(z))
+            .flatten()
+            .filter(|r| !query.drifted_only || r.drifted)
+            .cloned()
+            .collect();
+
+        result.sort_by(|a, b| b.checked_at.cmp(&a.checked_at));
+        result.truncate(query.limit.unwrap_or(50));
+        result
+    }
+
+    async fn notify_webhook(&self, webhook_url: &str, report: &DriftReport) {
+        let payload = serde_json::json!({
+            "event": "zone_config_drift",
+            "report": report
+        });
+
+        match self.http.post(webhook_url).json(&payload).send().await {
+            Ok(response) if response.status().is_success() => {
+                log::info!("Drift report {} delivered to webhook", report.id);
+            }
+            Ok(response) => log::error!("Drift webhook returned HTTP {}", response.status()),
+            Err(e) => log::error!("Drift webhook request failed: {}", e),
+        }
+    }
+}
+
+fn owns(baseline: &DriftBaseline, credentials: &CloudflareCredentials) -> bool {
+    baseline.credentials.api_token.trim() == credentials.api_token.trim()
+}
+
+// 后台定时检测所有已设置基线的 Zone
+pub async fn run_scheduler(store: actix_web::web::Data<DriftStore>, interval: Duration) {
+    log::info!("Drift detection scheduler started (interval: {}s)", interval.as_secs());
+
+    let mut ticker = tokio::time::interval(interval);
+    // 第一次 tick 立即返回，跳过以免启动时检测
+    ticker.tick().await;
+
+    loop {
+        ticker.tick().await;
+
+        let zone_ids: Vec<String> = store.baselines.read().unwrap().keys().cloned().collect();
+        for zone_id in zone_ids {
+            if let Err(e) = store.check_zone(&zone_id).await {
+                log::error!("Drift check failed for zone {}: {}", zone_id, e);
+            }
+        }
+    }
+}
+
+// 读取 Zone 配置快照：设置、DNS、防火墙、页面规则、速率限制、SSL 模式与 Worker 路由
+pub async fn capture_snapshot(client: &CloudflareClient, zone_id: &str) -> ZoneConfigSnapshot {
+    let (settings, dns, firewall, page_rules, rate_limits, routes) = tokio::join!(
+        client.get_zone_settings(zone_id),
+        client.get_dns_records(zone_id),
+        client.get_firewall_rules(zone_id),
+        client.get_page_rules(zone_id),
+        client.get_rate_limits(zone_id),
+        client.get_worker_routes(zone_id),
+    );
+
+    let mut errors = Vec::new();
+
+    let ssl_mode = settings.as_ref().ok()
+        .and_then(|s| s.iter().find(|setting| setting.id == "ssl"))
+        .and_then(|setting| setting.value.as_str())
+        .map(|s| s.to_string());
+
+    let settings = collect_section("settings", settings, |s| Some(s.id.clone()), &mut errors);
+    let dns_records = collect_section("dns_records", dns, |r| {
+        Some(r.id.clone().unwrap_or_else(|| format!("{}:{}:{}", r.record_type, r.name, r.content)))
+    }, &mut errors);
+    let firewall_rules = collect_section("firewall_rules", firewall, |r| r.id.clone(), &mut errors);
+    let page_rules = collect_section("page_rules", page_rules, |r| r.id.clone(), &mut errors);
+    let rate_limits = collect_section("rate_limits", rate_limits, |r| Some(r.id.clone()), &mut errors);
+    let worker_routes = collect_section("worker_routes", routes, |r| Some(r.id.clone()), &mut errors);
+
+    ZoneConfigSnapshot {
+        captured_at: chrono::Utc::now().to_rfc3339(),
+        ssl_mode,
+        settings,
+        dns_records,
+        firewall_rules,
+        page_rules,
+        rate_limits,
+        worker_routes,
+        errors,
+    }
+}
+
+fn collect_section<T: serde::Serialize>(
+    section: &str,
+    result: Result<Vec<T>, String>,
+    key: impl Fn(&T) -> Option<String>,
+    errors: &mut Vec<String>,
+) -> Option<BTreeMap<String, serde_json::Value>> {
+    match result {
+        Ok(items) => Some(
+            items.iter()
+                .enumerate()
+                .map(|(i, item)| {
+                    let id = key(item).unwrap_or_else(|| format!("#{}", i));
+                    (id, serde_json::to_value(item).unwrap_or(serde_json::Value::Null))
+                })
+                .collect(),
+        ),
+        Err(e) => {
+            log::warn!("Drift snapshot skipped {}: {}", section, e);
+            errors.push(format!("{}: {}", section, e));
+            None
+        }
+    }
+}
+
+// 逐分区比较两个快照；任一侧读取失败的分区跳过
+pub fn diff_snapshots(baseline: &ZoneConfigSnapshot, current: &ZoneConfigSnapshot) -> Vec<DriftChange> {
+    let sections = [
+        ("settings", &baseline.settings, &current.settings),
+        ("dns_records", &baseline.dns_records, &current.dns_records),
+        ("firewall_rules", &baseline.firewall_rules, &current.firewall_rules),
+        ("page_rules", &baseline.page_rules, &current.page_rules),
+        ("rate_limits", &baseline.rate_limits, &current.rate_limits),
+        ("worker_routes", &baseline.worker_routes, &current.worker_routes),
+    ];
+
+    let mut changes = Vec::new();
+    for (section, before, after) in sections {
+        if let (Some(before), Some(after)) = (before, after) {
+            diff_section(section, before, after, &mut changes);
+        }
+    }
+
+    // SSL 模式单独报告，不再重复列出 settings 中的 ssl 项
+    if let (Some(before), Some(after)) = (&baseline.ssl_mode, &current.ssl_mode) {
+        changes.retain(|c| !(c.section == "settings" && c.resource_id == "ssl"));
+        if before != after {
+            changes.push(DriftChange {
+                section: "ssl_mode".to_string(),
+                resource_id: "ssl".to_string(),
+                kind: DriftKind::Modified,
+                field: Some("value".to_string()),
+                baseline: Some(serde_json::Value::String(before.clone())),
+                current: Some(serde_json::Value::String(after.clone())),
+                modified_on: current.settings.as_ref()
+                    .and_then(|s| s.get("ssl"))
+                    .and_then(|v| v["modified_on"].as_str())
+                    .map(|s| s.to_string()),
+                changed_by: None,
+            });
+        }
+    }
+    changes
+}
+
+fn diff_section(
+    section: &str,
+    before: &BTreeMap<String, serde_json::Value>,
+    after: &BTreeMap<String, serde_json::Value>,
+    changes: &mut Vec<DriftChange>,
+) {
+    for (id, old) in before {
+        let Some(new) = after.get(id) else {
+            changes.push(DriftChange {
+                section: section.to_string(),
+                resource_id: id.clone(),
+                kind: DriftKind::Removed,
+                field: None,
+                baseline: Some(old.clone()),
+                current: None,
+                modified_on: None,
+                changed_by: None,
+            });
+            continue;
+        };
+
+        let mut old_fields = BTreeMap::new();
+        let mut new_fields = BTreeMap::new();
+        flatten("", old, &mut old_fields);
+        flatten("", new, &mut new_fields);
+
+        let keys: BTreeSet<&String> = old_fields.keys().chain(new_fields.keys()).collect();
+        for key in keys {
+            let (a, b) = (old_fields.get(key), new_fields.get(key));
+            if a != b {
+                changes.push(DriftChange {
+                    section: section.to_string(),
+                    resource_id: id.clone(),
+                    kind: DriftKind::Modified,
+                    field: Some(key.clone()),
+                    baseline: a.cloned(),
+                    current: b.cloned(),
+                    modified_on: new["modified_on"].as_str().map(|s| s.to_string()),
+                    changed_by: None,
+                });
+            }
+        }
+    }
+
+    for (id, new) in after {
+        if !before.contains_key(id) {
+            changes.push(DriftChange {
+                section: section.to_string(),
+                resource_id: id.clone(),
+                kind: DriftKind::Added,
+                field: None,
+                baseline: None,
+                current: Some(new.clone()),
+                modified_on: new["modified_on"].as_str().map(|s| s.to_string()),
+                changed_by: None,
+            });
+        }
+    }
+}
+
+// 将嵌套对象展开为 "a.b.c" 形式的字段路径，数组整体比较
+fn flatten(prefix: &str, value: &serde_json::Value, out: &mut BTreeMap<String, serde_json::Value>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (k, v) in map {
+                if prefix.is_empty() && VOLATILE_FIELDS.contains(&k.as_str()) {
+                    continue;
+                }
+                let path = if prefix.is_empty() { k.clone() } else { format!("{}.{}", prefix, k) };
+                flatten(&path, v, out);
+            }
+        }
+        _ => {
+            out.insert(prefix.to_string(), value.clone());
+        }
+    }
+}
+
+fn change_key(change: &DriftChange) -> (&str, &str, Option<&str>, Option<&serde_json::Value>) {
+    (&change.section, &change.resource_id, change.field.as_deref(), change.current.as_ref())
+}
+
+// 同一资源字段且当前值未变时，沿用上次报告标注的变更者
+fn carry_attribution(changes: &mut [DriftChange], previous: &[DriftChange]) {
+    for change in changes.iter_mut().filter(|c| c.changed_by.is_none()) {
+        let key = change_key(change);
+        if let Some(by) = previous.iter().find(|p| change_key(p) == key).and_then(|p| p.changed_by.clone()) {
+            change.changed_by = Some(by);
+        }
+    }
+}
+
+fn same_changes(a: &[DriftChange], b: &[DriftChange]) -> bool {
+    let contains = |changes: &[DriftChange], change: &DriftChange| {
+        changes.iter().any(|c| c.kind == change.kind && change_key(c) == change_key(change))
+    };
+    a.len() == b.len() && a.iter().all(|c| contains(b, c)) && b.iter().all(|c| contains(a, c))
+}
+
+// 根据审计日志标注变更者：优先匹配资源 ID，日志按时间倒序，取最近一条
+fn attribute_changes(changes: &mut [DriftChange], logs: &[serde_json::Value]) {
+    for change in changes.iter_mut() {
+        let entry = logs.iter().find(|log| {
+            log["resource"]["id"].as_str() == Some(change.resource_id.as_str())
+                || log["newValueJson"]["id"].as_str() == Some(change.resource_id.as_str())
+                || log["oldValueJson"]["id"].as_str() == Some(change.resource_id.as_str())
+        });
+
+        if let Some(log) = entry {
+            change.changed_by = Some(describe_actor(log));
+            if change.modified_on.is_none() {
+                change.modified_on = log["when"].as_str().map(|s| s.to_string());
+            }
+        }
+    }
+}
+
+fn describe_actor(log: &serde_json::Value) -> String {
+    let actor = &log["actor"];
+    let who = actor["email"].as_str()
+        .or(actor["id"].as_str())
+        .unwrap_or("unknown");
+    let actor_type = actor["type"].as_str().unwrap_or("unknown");
+    let action = log["action"]["type"].as_str().unwrap_or("change");
+
+    let mut description = format!("{} ({}) {}", who, actor_type, action);
+    if let Some(interface) = log["interface"].as_str().filter(|s| !s.is_empty()) {
+        description.push_str(&format!(" via {}", interface));
+    }
+    if let Some(ip) = actor["ip"].as_str().filter(|s| !s.is_empty()) {
+        description.push_str(&format!(" from {}", ip));
+    }
+    description
+}
@@ -0,0 +1,1444 @@
+// Cloudflare 规则语言（wirefilter）的解析、类型校验与规范化输出
+use crate::models::{ExpressionError, ExpressionValidation};
+use std::fmt;
+use std::net::IpAddr;
+
+// ==================== 类型与字段 ====================
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    Bytes,
+    Int,
+    Bool,
+    Ip,
+    Array(Box<Type>),
+    Map(Box<Type>),
+    // 不在内置列表中的字段：不做类型检查，由 Cloudflare API 最终判定
+    Unknown,
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Bytes => write!(f, "String"),
+            Type::Int => write!(f, "Integer"),
+            Type::Bool => write!(f, "Boolean"),
+            Type::Ip => write!(f, "IP address"),
+            Type::Array(inner) => write!(f, "Array<{}>", inner),
+            Type::Map(inner) => write!(f, "Map<{}>", inner),
+            Type::Unknown => write!(f, "unknown type"),
+        }
+    }
+}
+
+fn bytes_array() -> Type {
+    Type::Array(Box::new(Type::Bytes))
+}
+
+fn bytes_map() -> Type {
+    Type::Map(Box::new(bytes_array()))
+}
+
+// 已知字段及其类型；其余字段按 Unknown 处理并给出警告
+pub fn field_type(name: &str) -> Option<Type> {
+    let t = match name {
+        "http.cookie"
+        | "http.host"
+        | "http.referer"
+        | "http.request.full_uri"
+        | "http.request.method"
+        | "http.request.uri"
+        | "http.request.uri.path"
+        | "http.request.uri.path.extension"
+        | "http.request.uri.query"
+        | "http.request.version"
+        | "http.request.body.raw"
+        | "http.request.body.mime"
+        | "http.user_agent"
+        | "http.x_forwarded_for"
+        | "raw.http.request.full_uri"
+        | "raw.http.request.uri"
+        | "raw.http.request.uri.path"
+        | "raw.http.request.uri.query"
+        | "ip.src.country"
+        | "ip.src.continent"
+        | "ip.src.city"
+        | "ip.src.region"
+        | "ip.src.region_code"
+        | "ip.src.postal_code"
+        | "ip.src.metro_code"
+        | "ip.src.timezone.name"
+        | "ip.src.subdivision_1_iso_code"
+        | "ip.src.subdivision_2_iso_code"
+        | "ip.geoip.country"
+        | "ip.geoip.continent"
+        | "ip.geoip.subdivision_1_iso_code"
+        | "ip.geoip.subdivision_2_iso_code"
+        | "cf.bot_management.ja3_hash"
+        | "cf.bot_management.ja4"
+        | "cf.hostname.metadata"
+        | "cf.random_seed"
+        | "cf.ray_id"
+        | "cf.tls_cipher"
+        | "cf.tls_version"
+        | "cf.verified_bot_category"
+        | "cf.worker.upstream_zone"
+        | "cf.zone.name"
+        | "cf.zone.plan" => Type::Bytes,
+        "ip.src.asnum"
+        | "ip.geoip.asnum"
+        | "cf.bot_management.score"
+        | "cf.edge.server_port"
+        | "cf.threat_score"
+        | "cf.waf.score"
+        | "cf.waf.score.rce"
+        | "cf.waf.score.sqli"
+        | "cf.waf.score.xss"
+        | "http.request.body.size"
+        | "http.request.timestamp.sec"
+        | "http.request.timestamp.msec" => Type::Int,
+        "ssl"
+        | "ip.src.is_in_european_union"
+        | "ip.geoip.is_in_european_union"
+        | "cf.bot_management.corporate_proxy"
+        | "cf.bot_management.js_detection.passed"
+        | "cf.bot_management.static_resource"
+        | "cf.bot_management.verified_bot"
+        | "cf.client.bot"
+        | "cf.tls_client_auth.cert_verified"
+        | "cf.tls_client_auth.cert_revoked"
+        | "http.request.body.truncated"
+        | "http.request.headers.truncated" => Type::Bool,
+        "ip.src" | "cf.edge.server_ip" => Type::Ip,
+        "http.request.accepted_languages"
+        | "http.request.headers.names"
+        | "http.request.headers.values"
+        | "http.request.uri.args.names"
+        | "http.request.uri.args.values"
+        | "http.request.body.form.names"
+        | "http.request.body.form.values"
+        | "http.request.body.multipart.names"
+        | "http.request.body.multipart.values" => bytes_array(),
+        "cf.bot_management.detection_ids" => Type::Array(Box::new(Type::Int)),
+        "http.request.cookies"
+        | "http.request.headers"
+        | "http.request.uri.args"
+        | "http.request.body.form" => bytes_map(),
+        _ => return None,
+    };
+    Some(t)
+}
+
+// ==================== 语法树 ====================
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Field(String),
+    Index(Box<Expr>, Index),
+    Call(String, Vec<Expr>),
+    Literal(Literal),
+    Compare(Box<Expr>, CompareOp, Literal),
+    Not(Box<Expr>),
+    Logical(LogicalOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Index {
+    Key(String),
+    Position(u32),
+    All,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    String(String),
+    Int(i64),
+    Ip(IpAddr),
+    Set(Vec<SetItem>),
+    List(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SetItem {
+    String(String),
+    Int(i64),
+    IntRange(i64, i64),
+    Ip(IpAddr),
+    Cidr(IpAddr, u8),
+    IpRange(IpAddr, IpAddr),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+    Matches,
+    Wildcard,
+    StrictWildcard,
+    In,
+}
+
+impl CompareOp {
+    fn keyword(self) -> &'static str {
+        match self {
+            CompareOp::Eq => "eq",
+            CompareOp::Ne => "ne",
+            CompareOp::Lt => "lt",
+            CompareOp::Le => "le",
+            CompareOp::Gt => "gt",
+            CompareOp::Ge => "ge",
+            CompareOp::Contains => "contains",
+            CompareOp::Matches => "matches",
+            CompareOp::Wildcard => "wildcard",
+            CompareOp::StrictWildcard => "strict wildcard",
+            CompareOp::In => "in",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogicalOp {
+    And,
+    Or,
+    Xor,
+}
+
+impl LogicalOp {
+    fn keyword(self) -> &'static str {
+        match self {
+            LogicalOp::And => "and",
+            LogicalOp::Or => "or",
+            LogicalOp::Xor => "xor",
+        }
+    }
+}
+
+// ==================== 对外接口 ====================
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (line {}, column {})", self.message, self.line, self.column)
+    }
+}
+
+// 解析并校验表达式
+pub fn parse(input: &str) -> Result<Expr, ExpressionError> {
+    parse_with_warnings(input).map(|(expr, _)| expr)
+}
+
+// 解析并校验表达式，同时返回警告（如未知字段）
+pub fn parse_with_warnings(input: &str) -> Result<(Expr, Vec<ExpressionError>), ExpressionError> {
+    let tokens = tokenize(input).map_err(|(message, at)| error_at(input, message, at))?;
+    let mut parser = Parser { tokens, pos: 0, warnings: Vec::new() };
+
+    let result = parser.parse_expression().and_then(|(expr, ty, start)| {
+        if let Some(token) = parser.peek_token().filter(|t| t.kind != Tok::Eof) {
+            return Err((format!("Unexpected {}", describe(&token.kind)), token.start));
+        }
+        require_bool(&ty, start)?;
+        Ok(expr)
+    });
+
+    let expr = result.map_err(|(message, at)| error_at(input, message, at))?;
+    let warnings = parser.warnings.into_iter().map(|(message, at)| error_at(input, message, at)).collect();
+    Ok((expr, warnings))
+}
+
+// 供各规则处理函数在调用 Cloudflare 之前使用
+pub fn validate(input: &str) -> Result<(), String> {
+    parse(input).map(|_| ()).map_err(|e| format!("Invalid expression: {}", e))
+}
+
+pub fn check(input: &str) -> ExpressionValidation {
+    match parse_with_warnings(input) {
+        Ok((expr, warnings)) => ExpressionValidation { valid: true, canonical: Some(format(&expr)), error: None, warnings },
+        Err(error) => ExpressionValidation { valid: false, canonical: None, error: Some(error), warnings: Vec::new() },
+    }
+}
+
+fn error_at(input: &str, message: String, byte_offset: usize) -> ExpressionError {
+    let before = &input[..byte_offset.min(input.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+    ExpressionError {
+        message,
+        offset: before.chars().count(),
+        line,
+        column,
+    }
+}
+
+// ==================== 词法分析 ====================
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Word(String),
+    Str(String),
+    List(String),
+    Sym(&'static str),
+    Eof,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: Tok,
+    start: usize,
+}
+
+const SYMBOLS: &[&str] = &[
+    "==", "!=", "<=", ">=", "&&", "||", "^^", "<", ">", "~", "!", "(", ")", "{", "}", "[", "]", ",", "*",
+];
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | ':' | '/')
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, (String, usize)> {
+    let mut tokens = Vec::new();
+    let chars: Vec<(usize, char)> = input.char_indices().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let (start, c) = chars[i];
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        // 原始字符串 r"..." / r#"..."#
+        if c == 'r' && matches!(chars.get(i + 1), Some((_, '"')) | Some((_, '#'))) {
+            let mut j = i + 1;
+            let mut hashes = 0;
+            while let Some((_, '#')) = chars.get(j) {
+                hashes += 1;
+                j += 1;
+            }
+            if !matches!(chars.get(j), Some((_, '"'))) {
+                return Err(("Expected '\"' after raw string prefix".to_string(), start));
+            }
+            j += 1;
+            let content_start = j;
+            loop {
+                match chars.get(j) {
+                    None => return Err(("Unterminated raw string".to_string(), start)),
+                    Some((_, '"')) if (1..=hashes).all(|k| matches!(chars.get(j + k), Some((_, '#')))) => break,
+                    _ => j += 1,
+                }
+            }
+            let value: String = chars[content_start..j].iter().map(|(_, c)| c).collect();
+            tokens.push(Token { kind: Tok::Str(value), start });
+            i = j + 1 + hashes;
+            continue;
+        }
+
+        if c == '"' {
+            let mut value: Vec<u8> = Vec::new();
+            let mut j = i + 1;
+            loop {
+                match chars.get(j) {
+                    None => return Err(("Unterminated string".to_string(), start)),
+                    Some((_, '"')) => break,
+                    Some((pos, '\\')) => match chars.get(j + 1) {
+                        Some((_, '"')) => { value.push(b'"'); j += 2; }
+                        Some((_, '\\')) => { value.push(b'\\'); j += 2; }
+                        Some((_, 'x')) => {
+                            let hex: String = chars.iter().skip(j + 2).take(2).map(|(_, c)| c).collect();
+                            let byte = Some(&hex)
+                                .filter(|h| h.len() == 2 && h.chars().all(|c| c.is_ascii_hexdigit()))
+                                .and_then(|h| u8::from_str_radix(h, 16).ok())
+                                .ok_or_else(|| ("Invalid \\x escape (expected two hex digits)".to_string(), *pos))?;
+                            value.push(byte);
+                            j += 4;
+                        }
+                        _ => return Err(("Invalid escape sequence (only \\\", \\\\ and \\xHH are allowed)".to_string(), *pos)),
+                    },
+                    Some((_, ch)) => {
+                        let mut buf = [0u8; 4];
+                        value.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                        j += 1;
+                    }
+                }
+            }
+            // \xHH 转义按原始字节收集，多字节 UTF-8 序列需整体解码
+            let value = String::from_utf8(value)
+                .map_err(|_| ("String contains \\x escapes that are not valid UTF-8".to_string(), start))?;
+            tokens.push(Token { kind: Tok::Str(value), start });
+            i = j + 1;
+            continue;
+        }
+
+        if c == '$' {
+            let mut j = i + 1;
+            while chars.get(j).map(|(_, c)| is_word_char(*c)).unwrap_or(false) {
+                j += 1;
+            }
+            if j == i + 1 {
+                return Err(("Expected a list name after '$'".to_string(), start));
+            }
+            let name: String = chars[i + 1..j].iter().map(|(_, c)| c).collect();
+            tokens.push(Token { kind: Tok::List(name), start });
+            i = j;
+            continue;
+        }
+
+        if is_word_char(c) {
+            let mut j = i;
+            while chars.get(j).map(|(_, c)| is_word_char(*c)).unwrap_or(false) {
+                j += 1;
+            }
+            let word: String = chars[i..j].iter().map(|(_, c)| c).collect();
+            tokens.push(Token { kind: Tok::Word(word), start });
+            i = j;
+            continue;
+        }
+
+        let rest = &input[start..];
+        match SYMBOLS.iter().find(|s| rest.starts_with(**s)) {
+            Some(symbol) => {
+                tokens.push(Token { kind: Tok::Sym(symbol), start });
+                i += symbol.chars().count();
+            }
+            None => return Err((format!("Unexpected character '{}'", c), start)),
+        }
+    }
+
+    tokens.push(Token { kind: Tok::Eof, start: input.len() });
+    Ok(tokens)
+}
+
+fn describe(tok: &Tok) -> String {
+    match tok {
+        Tok::Word(w) => format!("'{}'", w),
+        Tok::Str(_) => "string".to_string(),
+        Tok::List(name) => format!("list '${}'", name),
+        Tok::Sym(s) => format!("'{}'", s),
+        Tok::Eof => "end of expression".to_string(),
+    }
+}
+
+// ==================== 语法分析与类型检查 ====================
+
+// each 为 true 表示经过 [*] 展开的逐元素值
+#[derive(Debug, Clone, PartialEq)]
+struct Ty {
+    base: Type,
+    each: bool,
+}
+
+impl Ty {
+    fn scalar(base: Type) -> Self {
+        Ty { base, each: false }
+    }
+}
+
+type ParseResult<T> = Result<T, (String, usize)>;
+
+fn require_bool(ty: &Ty, at: usize) -> ParseResult<()> {
+    if !matches!(ty.base, Type::Bool | Type::Unknown) {
+        return Err((format!("Expected a boolean expression, found {}", ty.base), at));
+    }
+    if ty.each {
+        return Err(("Array comparisons must be wrapped in any() or all()".to_string(), at));
+    }
+    Ok(())
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    warnings: Vec<(String, usize)>,
+}
+
+impl Parser {
+    fn peek_token(&self) -> Option<Token> {
+        self.tokens.get(self.pos).cloned()
+    }
+
+    fn peek(&self) -> &Tok {
+        &self.tokens[self.pos.min(self.tokens.len() - 1)].kind
+    }
+
+    fn start(&self) -> usize {
+        self.tokens[self.pos.min(self.tokens.len() - 1)].start
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.pos.min(self.tokens.len() - 1)].clone();
+        if self.pos < self.tokens.len() - 1 {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn expect_sym(&mut self, symbol: &'static str) -> ParseResult<()> {
+        if *self.peek() == Tok::Sym(symbol) {
+            self.advance();
+            Ok(())
+        } else {
+            Err((format!("Expected '{}', found {}", symbol, describe(self.peek())), self.start()))
+        }
+    }
+
+    fn is_word(&self, word: &str) -> bool {
+        matches!(self.peek(), Tok::Word(w) if w == word)
+    }
+
+    fn parse_expression(&mut self) -> ParseResult<(Expr, Ty, usize)> {
+        let start = self.start();
+        let (expr, ty) = self.parse_or()?;
+        Ok((expr, ty, start))
+    }
+
+    fn logical_op(&self, op: LogicalOp) -> bool {
+        match op {
+            LogicalOp::Or => self.is_word("or") || *self.peek() == Tok::Sym("||"),
+            LogicalOp::Xor => self.is_word("xor") || *self.peek() == Tok::Sym("^^"),
+            LogicalOp::And => self.is_word("and") || *self.peek() == Tok::Sym("&&"),
+        }
+    }
+
+    // 优先级：not > and > xor > or
+    fn parse_or(&mut self) -> ParseResult<(Expr, Ty)> {
+        self.parse_logical(LogicalOp::Or)
+    }
+
+    fn parse_logical(&mut self, op: LogicalOp) -> ParseResult<(Expr, Ty)> {
+        let next = |parser: &mut Parser| match op {
+            LogicalOp::Or => parser.parse_logical(LogicalOp::Xor),
+            LogicalOp::Xor => parser.parse_logical(LogicalOp::And),
+            LogicalOp::And => parser.parse_not(),
+        };
+
+        let left_start = self.start();
+        let (mut left, left_ty) = next(self)?;
+        if !self.logical_op(op) {
+            return Ok((left, left_ty));
+        }
+        require_bool(&left_ty, left_start)?;
+
+        while self.logical_op(op) {
+            self.advance();
+            let right_start = self.start();
+            let (right, right_ty) = next(self)?;
+            require_bool(&right_ty, right_start)?;
+            left = Expr::Logical(op, Box::new(left), Box::new(right));
+        }
+        Ok((left, Ty::scalar(Type::Bool)))
+    }
+
+    fn parse_not(&mut self) -> ParseResult<(Expr, Ty)> {
+        if self.is_word("not") || *self.peek() == Tok::Sym("!") {
+            self.advance();
+            let start = self.start();
+            let (inner, ty) = self.parse_not()?;
+            require_bool(&ty, start)?;
+            return Ok((Expr::Not(Box::new(inner)), Ty::scalar(Type::Bool)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> ParseResult<(Expr, Ty)> {
+        if *self.peek() == Tok::Sym("(") {
+            self.advance();
+            let (expr, ty) = self.parse_or()?;
+            self.expect_sym(")")?;
+            return Ok((expr, ty));
+        }
+        self.parse_comparison()
+    }
+
+    fn comparison_op(&mut self) -> ParseResult<Option<CompareOp>> {
+        let op = match self.peek() {
+            Tok::Word(w) => match w.as_str() {
+                "eq" => CompareOp::Eq,
+                "ne" => CompareOp::Ne,
+                "lt" => CompareOp::Lt,
+                "le" => CompareOp::Le,
+                "gt" => CompareOp::Gt,
+                "ge" => CompareOp::Ge,
+                "contains" => CompareOp::Contains,
+                "matches" => CompareOp::Matches,
+                "wildcard" => CompareOp::Wildcard,
+                "in" => CompareOp::In,
+                "strict" => {
+                    self.advance();
+                    if !self.is_word("wildcard") {
+                        return Err((format!("Expected 'wildcard' after 'strict', found {}", describe(self.peek())), self.start()));
+                    }
+                    CompareOp::StrictWildcard
+                }
+                _ => return Ok(None),
+            },
+            Tok::Sym("==") => CompareOp::Eq,
+            Tok::Sym("!=") => CompareOp::Ne,
+            Tok::Sym("<") => CompareOp::Lt,
+            Tok::Sym("<=") => CompareOp::Le,
+            Tok::Sym(">") => CompareOp::Gt,
+            Tok::Sym(">=") => CompareOp::Ge,
+            Tok::Sym("~") => CompareOp::Matches,
+            _ => return Ok(None),
+        };
+        self.advance();
+        Ok(Some(op))
+    }
+
+    fn parse_comparison(&mut self) -> ParseResult<(Expr, Ty)> {
+        let lhs_start = self.start();
+        let (lhs, ty) = self.parse_value()?;
+
+        let op_start = self.start();
+        let Some(op) = self.comparison_op()? else {
+            return Ok((lhs, ty));
+        };
+
+        let supported = matches!(
+            (&ty.base, op),
+            (Type::Bytes | Type::Unknown, _)
+                | (Type::Int, CompareOp::Eq | CompareOp::Ne | CompareOp::Lt | CompareOp::Le | CompareOp::Gt | CompareOp::Ge | CompareOp::In)
+                | (Type::Ip, CompareOp::Eq | CompareOp::Ne | CompareOp::In)
+        );
+        if !supported {
+            let hint = match ty.base {
+                Type::Bool => " (boolean fields are used directly, e.g. `ssl` or `not ssl`)",
+                Type::Array(_) | Type::Map(_) => " (index it first, e.g. [0] or [*])",
+                _ => "",
+            };
+            return Err((
+                format!("Operator '{}' cannot be applied to {}{}", op.keyword(), ty.base, hint),
+                if matches!(ty.base, Type::Array(_) | Type::Map(_)) { lhs_start } else { op_start },
+            ));
+        }
+
+        let rhs = self.parse_rhs(op, &ty.base)?;
+        Ok((Expr::Compare(Box::new(lhs), op, rhs), Ty { base: Type::Bool, each: ty.each }))
+    }
+
+    fn parse_value(&mut self) -> ParseResult<(Expr, Ty)> {
+        let token = self.advance();
+        let name = match &token.kind {
+            Tok::Word(w) => w.clone(),
+            other => return Err((format!("Expected a field or function, found {}", describe(other)), token.start)),
+        };
+
+        let (mut expr, mut ty) = if *self.peek() == Tok::Sym("(") {
+            self.advance();
+            let mut args = Vec::new();
+            if *self.peek() != Tok::Sym(")") {
+                loop {
+                    let arg_start = self.start();
+                    let (arg, arg_ty) = self.parse_argument()?;
+                    args.push((arg, arg_ty, arg_start));
+                    if *self.peek() == Tok::Sym(",") {
+                        self.advance();
+                        continue;
+                    }
+                    break;
+                }
+            }
+            self.expect_sym(")")?;
+            let ty = check_call(&name, &args, token.start)?;
+            (Expr::Call(name, args.into_iter().map(|(e, _, _)| e).collect()), ty)
+        } else {
+            let base = field_type(&name).unwrap_or_else(|| {
+                self.warnings.push((format!("Unknown field '{}'; it is not type-checked locally", name), token.start));
+                Type::Unknown
+            });
+            (Expr::Field(name), Ty::scalar(base))
+        };
+
+        while *self.peek() == Tok::Sym("[") {
+            let bracket = self.advance();
+            let index_token = self.advance();
+            let index = match &index_token.kind {
+                Tok::Str(key) => Index::Key(key.clone()),
+                Tok::Sym("*") => Index::All,
+                Tok::Word(w) if w.chars().all(|c| c.is_ascii_digit()) => {
+                    Index::Position(w.parse().map_err(|_| ("Index out of range".to_string(), index_token.start))?)
+                }
+                other => return Err((format!("Expected a string key, integer index or '*', found {}", describe(other)), index_token.start)),
+            };
+            self.expect_sym("]")?;
+
+            let element = match (&ty.base, &index) {
+                (Type::Map(inner), Index::Key(_) | Index::All) => (**inner).clone(),
+                (Type::Array(inner), Index::Position(_) | Index::All) => (**inner).clone(),
+                (Type::Unknown, _) => Type::Unknown,
+                (Type::Map(_), Index::Position(_)) => {
+                    return Err(("Maps are indexed by string keys".to_string(), index_token.start));
+                }
+                (Type::Array(_), Index::Key(_)) => {
+                    return Err(("Arrays are indexed by integer positions".to_string(), index_token.start));
+                }
+                (other, _) => return Err((format!("{} cannot be indexed", other), bracket.start)),
+            };
+            ty = Ty { base: element, each: ty.each || index == Index::All };
+            expr = Expr::Index(Box::new(expr), index);
+        }
+
+        Ok((expr, ty))
+    }
+
+    fn parse_argument(&mut self) -> ParseResult<(Expr, Ty)> {
+        match self.peek().clone() {
+            Tok::Str(s) => {
+                self.advance();
+                Ok((Expr::Literal(Literal::String(s)), Ty::scalar(Type::Bytes)))
+            }
+            Tok::Word(w) if w.chars().all(|c| c.is_ascii_digit()) => {
+                let start = self.start();
+                self.advance();
+                let n = w.parse().map_err(|_| ("Integer out of range".to_string(), start))?;
+                Ok((Expr::Literal(Literal::Int(n)), Ty::scalar(Type::Int)))
+            }
+            _ => self.parse_or(),
+        }
+    }
+
+    fn parse_rhs(&mut self, op: CompareOp, lhs: &Type) -> ParseResult<Literal> {
+        let token = self.advance();
+
+        if op == CompareOp::In {
+            return match &token.kind {
+                Tok::Sym("{") => {
+                    let mut items = Vec::new();
+                    while *self.peek() != Tok::Sym("}") {
+                        if *self.peek() == Tok::Eof {
+                            return Err(("Unterminated set, expected '}'".to_string(), self.start()));
+                        }
+                        if *self.peek() == Tok::Sym(",") {
+                            self.advance();
+                            continue;
+                        }
+                        let item = self.advance();
+                        items.push(set_item(&item, lhs)?);
+                    }
+                    self.advance();
+                    if items.is_empty() {
+                        return Err(("Set must not be empty".to_string(), token.start));
+                    }
+                    Ok(Literal::Set(items))
+                }
+                Tok::List(name) if matches!(lhs, Type::Ip | Type::Int | Type::Bytes | Type::Unknown) => Ok(Literal::List(name.clone())),
+                other => Err((format!("Expected a set {{...}} or list $name after 'in', found {}", describe(other)), token.start)),
+            };
+        }
+
+        match (lhs, &token.kind) {
+            (Type::Bytes, Tok::Str(s)) => {
+                if op == CompareOp::Matches {
+                    regex::Regex::new(s).map_err(|e| {
+                        let detail = e.to_string();
+                        let last = detail.lines().last().unwrap_or("").trim().to_string();
+                        (format!("Invalid regular expression: {}", last), token.start)
+                    })?;
+                }
+                Ok(Literal::String(s.clone()))
+            }
+            (Type::Int, Tok::Word(w)) => w.parse::<i64>()
+                .map(Literal::Int)
+                .map_err(|_| (format!("Expected an integer, found '{}'", w), token.start)),
+            (Type::Unknown, Tok::Str(s)) => Ok(Literal::String(s.clone())),
+            (Type::Unknown, Tok::Word(w)) => match (w.parse::<i64>(), parse_ip(w)) {
+                (Ok(n), _) => Ok(Literal::Int(n)),
+                (_, Some(SetItem::Ip(ip))) => Ok(Literal::Ip(ip)),
+                _ => Err((format!("Expected a string, integer or IP address, found '{}'", w), token.start)),
+            },
+            (Type::Ip, Tok::Word(w)) => match parse_ip(w) {
+                Some(SetItem::Ip(ip)) => Ok(Literal::Ip(ip)),
+                Some(SetItem::Cidr(..)) => Err(("Use 'in {...}' to match a CIDR range".to_string(), token.start)),
+                _ => Err((format!("Expected an IP address, found '{}'", w), token.start)),
+            },
+            (expected, other) => Err((format!("Expected a {} value, found {}", expected, describe(other)), token.start)),
+        }
+    }
+}
+
+pub(crate) fn parse_ip(word: &str) -> Option<SetItem> {
+    match word.split_once('/') {
+        Some((addr, prefix)) => {
+            let ip: IpAddr = addr.parse().ok()?;
+            let prefix: u8 = prefix.parse().ok()?;
+            let max = if ip.is_ipv4() { 32 } else { 128 };
+            (prefix <= max).then_some(SetItem::Cidr(ip, prefix))
+        }
+        None => word.parse().ok().map(SetItem::Ip),
+    }
+}
+
+fn set_item(token: &Token, lhs: &Type) -> ParseResult<SetItem> {
+    let invalid = |what: &str| Err((format!("Expected {} in set, found {}", what, describe(&token.kind)), token.start));
+
+    match (lhs, &token.kind) {
+        (Type::Bytes, Tok::Str(s)) => Ok(SetItem::String(s.clone())),
+        (Type::Bytes, _) => invalid("a string"),
+        (Type::Int, Tok::Word(w)) => match w.split_once("..") {
+            Some((a, b)) => match (a.parse::<i64>(), b.parse::<i64>()) {
+                (Ok(a), Ok(b)) if a <= b => Ok(SetItem::IntRange(a, b)),
+                (Ok(_), Ok(_)) => Err(("Range start must not exceed range end".to_string(), token.start)),
+                _ => invalid("an integer or range"),
+            },
+            None => w.parse().map(SetItem::Int).or_else(|_| invalid("an integer")),
+        },
+        (Type::Int, _) => invalid("an integer"),
+        (Type::Ip, Tok::Word(w)) => match w.split_once("..") {
+            Some((a, b)) => match (a.parse::<IpAddr>(), b.parse::<IpAddr>()) {
+                (Ok(a), Ok(b)) if a.is_ipv4() == b.is_ipv4() => Ok(SetItem::IpRange(a, b)),
+                _ => invalid("an IP range"),
+            },
+            None => parse_ip(w).map(Ok).unwrap_or_else(|| invalid("an IP address or CIDR")),
+        },
+        (Type::Ip, _) => invalid("an IP address or CIDR"),
+        // 类型未知时按字面量本身推断
+        (Type::Unknown, Tok::Str(_)) => set_item(token, &Type::Bytes),
+        (Type::Unknown, Tok::Word(w)) => {
+            let ip_like = w.contains([':', '/']) || w.split("..").next().is_some_and(|a| a.parse::<IpAddr>().is_ok());
+            let inferred = if ip_like { Type::Ip } else { Type::Int };
+            set_item(token, &inferred)
+        }
+        (Type::Unknown, _) => invalid("a string, integer or IP address"),
+        (other, _) => Err((format!("{} values cannot be used with 'in'", other), token.start)),
+    }
+}
+
+// 函数签名检查，返回结果类型
+fn check_call(name: &str, args: &[(Expr, Ty, usize)], at: usize) -> ParseResult<Ty> {
+    let each = args.iter().any(|(_, ty, _)| ty.each);
+    let arity = |min: usize, max: usize| -> ParseResult<()> {
+        if args.len() < min || args.len() > max {
+            let expected = if min == max { min.to_string() } else if max == usize::MAX { format!("at least {}", min) } else { format!("{} to {}", min, max) };
+            return Err((format!("{}() expects {} argument(s), got {}", name, expected, args.len()), at));
+        }
+        Ok(())
+    };
+    let expect = |index: usize, expected: &[Type]| -> ParseResult<()> {
+        let (_, ty, start) = &args[index];
+        if ty.base != Type::Unknown && !expected.contains(&ty.base) {
+            let names: Vec<String> = expected.iter().map(|t| t.to_string()).collect();
+            return Err((format!("Argument {} of {}() must be {}, found {}", index + 1, name, names.join(" or "), ty.base), *start));
+        }
+        Ok(())
+    };
+    // 对 [*] 展开值调用函数得到数组，需再次使用 [*] 才能逐元素比较
+    let result = |base: Type| Ok(if each { Ty::scalar(Type::Array(Box::new(base))) } else { Ty::scalar(base) });
+
+    match name {
+        "lower" | "upper" | "uuidv4" | "decode_base64" => {
+            arity(1, 1)?;
+            expect(0, &[Type::Bytes])?;
+            result(Type::Bytes)
+        }
+        "url_decode" | "encode_base64" => {
+            arity(1, 2)?;
+            expect(0, &[Type::Bytes])?;
+            if args.len() == 2 {
+                expect(1, &[Type::Bytes])?;
+            }
+            result(Type::Bytes)
+        }
+        "remove_bytes" => {
+            arity(2, 2)?;
+            expect(0, &[Type::Bytes])?;
+            expect(1, &[Type::Bytes])?;
+            result(Type::Bytes)
+        }
+        "starts_with" | "ends_with" => {
+            arity(2, 2)?;
+            expect(0, &[Type::Bytes])?;
+            expect(1, &[Type::Bytes])?;
+            result(Type::Bool)
+        }
+        "len" => {
+            arity(1, 1)?;
+            match &args[0].1.base {
+                Type::Bytes | Type::Array(_) | Type::Unknown => result(Type::Int),
+                other => Err((format!("Argument 1 of len() must be String or Array, found {}", other), args[0].2)),
+            }
+        }
+        "any" | "all" => {
+            arity(1, 1)?;
+            let (_, ty, start) = &args[0];
+            let boolean_array = ty.base == Type::Array(Box::new(Type::Bool));
+            if !(boolean_array || ty.base == Type::Unknown || (ty.each && ty.base == Type::Bool)) {
+                return Err((format!("{}() expects an array of booleans, e.g. {}(field[*] eq \"value\")", name, name), *start));
+            }
+            Ok(Ty::scalar(Type::Bool))
+        }
+        "concat" => {
+            arity(2, usize::MAX)?;
+            let first = args[0].1.base.clone();
+            if !matches!(first, Type::Bytes | Type::Array(_) | Type::Unknown) {
+                return Err((format!("Argument 1 of concat() must be String or Array, found {}", first), args[0].2));
+            }
+            if first != Type::Unknown {
+                for index in 1..args.len() {
+                    expect(index, std::slice::from_ref(&first))?;
+                }
+            }
+            result(first)
+        }
+        "to_string" => {
+            arity(1, 1)?;
+            expect(0, &[Type::Int, Type::Bool, Type::Ip])?;
+            result(Type::Bytes)
+        }
+        "lookup_json_string" | "lookup_json_integer" => {
+            arity(2, usize::MAX)?;
+            expect(0, &[Type::Bytes])?;
+            for index in 1..args.len() {
+                expect(index, &[Type::Bytes, Type::Int])?;
+            }
+            result(if name == "lookup_json_string" { Type::Bytes } else { Type::Int })
+        }
+        "regex_replace" => {
+            arity(3, 3)?;
+            expect(0, &[Type::Bytes])?;
+            expect(1, &[Type::Bytes])?;
+            expect(2, &[Type::Bytes])?;
+            result(Type::Bytes)
+        }
+        "wildcard_replace" => {
+            arity(3, 4)?;
+            for index in 0..args.len() {
+                expect(index, &[Type::Bytes])?;
+            }
+            result(Type::Bytes)
+        }
+        "substring" => {
+            arity(2, 3)?;
+            expect(0, &[Type::Bytes])?;
+            expect(1, &[Type::Int])?;
+            if args.len() == 3 {
+                expect(2, &[Type::Int])?;
+            }
+            result(Type::Bytes)
+        }
+        "join" => {
+            arity(2, 2)?;
+            expect(0, &[bytes_array()])?;
+            expect(1, &[Type::Bytes])?;
+            result(Type::Bytes)
+        }
+        "has_key" => {
+            arity(2, 2)?;
+            if !matches!(args[0].1.base, Type::Map(_) | Type::Unknown) {
+                return Err(("Argument 1 of has_key() must be a Map".to_string(), args[0].2));
+            }
+            expect(1, &[Type::Bytes])?;
+            result(Type::Bool)
+        }
+        "has_value" => {
+            arity(2, 2)?;
+            if !matches!(args[0].1.base, Type::Map(_) | Type::Array(_) | Type::Unknown) {
+                return Err(("Argument 1 of has_value() must be a Map or Array".to_string(), args[0].2));
+            }
+            expect(1, &[Type::Bytes, Type::Int])?;
+            result(Type::Bool)
+        }
+        "bit_slice" => {
+            arity(3, 3)?;
+            expect(0, &[Type::Bytes])?;
+            expect(1, &[Type::Int])?;
+            expect(2, &[Type::Int])?;
+            result(Type::Int)
+        }
+        "cidr" => {
+            arity(3, 3)?;
+            expect(0, &[Type::Ip])?;
+            expect(1, &[Type::Int])?;
+            expect(2, &[Type::Int])?;
+            result(Type::Ip)
+        }
+        "cidr6" => {
+            arity(2, 2)?;
+            expect(0, &[Type::Ip])?;
+            expect(1, &[Type::Int])?;
+            result(Type::Ip)
+        }
+        "is_timed_hmac_valid_v0" => {
+            arity(4, 6)?;
+            expect(0, &[Type::Bytes])?;
+            expect(1, &[Type::Bytes])?;
+            expect(2, &[Type::Int])?;
+            expect(3, &[Type::Int])?;
+            result(Type::Bool)
+        }
+        _ => Err((format!("Unknown function '{}'", name), at)),
+    }
+}
+
+// ==================== 规范化输出 ====================
+
+pub(crate) fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() && (c as u32) < 0x80 => out.push_str(&format!("\\x{:02x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn format_set_item(item: &SetItem) -> String {
+    match item {
+        SetItem::String(s) => quote(s),
+        SetItem::Int(n) => n.to_string(),
+        SetItem::IntRange(a, b) => format!("{}..{}", a, b),
+        SetItem::Ip(ip) => ip.to_string(),
+        SetItem::Cidr(ip, prefix) => format!("{}/{}", ip, prefix),
+        SetItem::IpRange(a, b) => format!("{}..{}", a, b),
+    }
+}
+
+fn format_literal(literal: &Literal) -> String {
+    match literal {
+        Literal::String(s) => quote(s),
+        Literal::Int(n) => n.to_string(),
+        Literal::Ip(ip) => ip.to_string(),
+        Literal::Set(items) => format!("{{{}}}", items.iter().map(format_set_item).collect::<Vec<_>>().join(" ")),
+        Literal::List(name) => format!("${}", name),
+    }
+}
+
+// 规范形式：英文关键字运算符、单个空格分隔；不同逻辑运算符嵌套时总是加括号
+pub fn format(expr: &Expr) -> String {
+    match expr {
+        Expr::Field(name) => name.clone(),
+        Expr::Index(base, index) => {
+            let index = match index {
+                Index::Key(key) => quote(key),
+                Index::Position(n) => n.to_string(),
+                Index::All => "*".to_string(),
+            };
+            format!("{}[{}]", format(base), index)
+        }
+        Expr::Call(name, args) => format!("{}({})", name, args.iter().map(format).collect::<Vec<_>>().join(", ")),
+        Expr::Literal(literal) => format_literal(literal),
+        Expr::Compare(lhs, op, rhs) => format!("{} {} {}", format(lhs), op.keyword(), format_literal(rhs)),
+        Expr::Not(inner) => match inner.as_ref() {
+            Expr::Logical(..) | Expr::Compare(..) => format!("not ({})", format(inner)),
+            _ => format!("not {}", format(inner)),
+        },
+        Expr::Logical(op, left, right) => {
+            let side = |e: &Expr| match e {
+                Expr::Logical(child, ..) if child != op => format!("({})", format(e)),
+                _ => format(e),
+            };
+            format!("{} {} {}", side(left), op.keyword(), side(right))
+        }
+    }
+}
+
+// ==================== 本地求值 ====================
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Val {
+    Bytes(String),
+    Int(i64),
+    Bool(bool),
+    Ip(IpAddr),
+    Array(Vec<Val>),
+    Map(Vec<(String, Val)>),
+    // 不存在的 Map 键或数组下标，参与比较时结果为 false
+    Missing,
+}
+
+impl Val {
+    fn default_for(t: &Type) -> Val {
+        match t {
+            Type::Bytes => Val::Bytes(String::new()),
+            Type::Int => Val::Int(0),
+            Type::Bool => Val::Bool(false),
+            Type::Ip => Val::Ip(IpAddr::from([0, 0, 0, 0])),
+            Type::Array(_) => Val::Array(Vec::new()),
+            Type::Map(_) => Val::Map(Vec::new()),
+            Type::Unknown => Val::Missing,
+        }
+    }
+
+    fn elements(self) -> Vec<Val> {
+        match self {
+            Val::Array(items) => items,
+            Val::Map(entries) => entries.into_iter().map(|(_, v)| v).collect(),
+            Val::Missing => Vec::new(),
+            other => vec![other],
+        }
+    }
+}
+
+// 求值上下文：字段值、命名列表内容，以及求值过程中使用了默认值的字段
+#[derive(Default)]
+pub struct EvalContext {
+    pub fields: std::collections::HashMap<String, Val>,
+    pub lists: std::collections::HashMap<String, Vec<String>>,
+    defaulted: std::cell::RefCell<std::collections::BTreeSet<String>>,
+}
+
+impl EvalContext {
+    pub fn new(lists: std::collections::HashMap<String, Vec<String>>) -> Self {
+        EvalContext { lists, ..Default::default() }
+    }
+
+    pub fn defaulted_fields(&self) -> Vec<String> {
+        self.defaulted.borrow().iter().cloned().collect()
+    }
+
+    fn field(&self, name: &str) -> Val {
+        if let Some(value) = self.fields.get(name) {
+            return value.clone();
+        }
+        self.defaulted.borrow_mut().insert(name.to_string());
+        field_type(name).map(|t| Val::default_for(&t)).unwrap_or(Val::Missing)
+    }
+}
+
+// 对已解析的表达式求值
+pub fn evaluate(expr: &Expr, ctx: &EvalContext) -> Result<bool, String> {
+    match eval(expr, ctx)? {
+        Val::Bool(b) => Ok(b),
+        // 未提供的未知字段直接作为条件使用时视为 false
+        Val::Missing => Ok(false),
+        other => Err(format!("Expression evaluated to a non-boolean value: {:?}", other)),
+    }
+}
+
+// 表达式是否为 [*] 展开后的逐元素值（与类型检查中的 each 规则一致）
+fn is_each(expr: &Expr) -> bool {
+    match expr {
+        Expr::Index(_, Index::All) => true,
+        Expr::Index(base, _) => is_each(base),
+        Expr::Compare(lhs, _, _) => is_each(lhs),
+        _ => false,
+    }
+}
+
+fn eval(expr: &Expr, ctx: &EvalContext) -> Result<Val, String> {
+    match expr {
+        Expr::Field(name) => Ok(ctx.field(name)),
+        Expr::Literal(Literal::String(s)) => Ok(Val::Bytes(s.clone())),
+        Expr::Literal(Literal::Int(n)) => Ok(Val::Int(*n)),
+        Expr::Literal(Literal::Ip(ip)) => Ok(Val::Ip(*ip)),
+        Expr::Literal(_) => Err("Sets and lists can only appear after 'in'".to_string()),
+        Expr::Index(base, index) => {
+            let value = eval(base, ctx)?;
+            if is_each(base) {
+                let items = value.elements().into_iter().map(|v| apply_index(v, index));
+                return Ok(Val::Array(match index {
+                    Index::All => items.flat_map(Val::elements).collect(),
+                    _ => items.collect(),
+                }));
+            }
+            match index {
+                Index::All => Ok(Val::Array(value.elements())),
+                _ => Ok(apply_index(value, index)),
+            }
+        }
+        Expr::Call(name, args) => {
+            let each_arg = args.iter().position(is_each);
+            let values = args.iter().map(|a| eval(a, ctx)).collect::<Result<Vec<_>, _>>()?;
+            match each_arg {
+                Some(position) if name != "any" && name != "all" => {
+                    let items = values[position].clone().elements();
+                    let mapped = items.into_iter()
+                        .map(|item| {
+                            let mut args = values.clone();
+                            args[position] = item;
+                            call(name, args)
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    Ok(Val::Array(mapped))
+                }
+                _ => call(name, values),
+            }
+        }
+        Expr::Compare(lhs, op, rhs) => {
+            let value = eval(lhs, ctx)?;
+            if is_each(lhs) {
+                let results = value.elements().into_iter()
+                    .map(|v| compare(&v, *op, rhs, ctx).map(Val::Bool))
+                    .collect::<Result<Vec<_>, _>>()?;
+                return Ok(Val::Array(results));
+            }
+            compare(&value, *op, rhs, ctx).map(Val::Bool)
+        }
+        Expr::Not(inner) => Ok(Val::Bool(!evaluate(inner, ctx)?)),
+        Expr::Logical(op, left, right) => {
+            let left = evaluate(left, ctx)?;
+            let result = match op {
+                LogicalOp::And => left && evaluate(right, ctx)?,
+                LogicalOp::Or => left || evaluate(right, ctx)?,
+                LogicalOp::Xor => left ^ evaluate(right, ctx)?,
+            };
+            Ok(Val::Bool(result))
+        }
+    }
+}
+
+fn apply_index(value: Val, index: &Index) -> Val {
+    match (value, index) {
+        (Val::Map(entries), Index::Key(key)) => entries.into_iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+            .unwrap_or(Val::Missing),
+        (Val::Array(items), Index::Position(n)) => items.into_iter().nth(*n as usize).unwrap_or(Val::Missing),
+        (value, Index::All) => Val::Array(value.elements()),
+        _ => Val::Missing,
+    }
+}
+
+fn wildcard_regex(pattern: &str, case_sensitive: bool) -> Result<regex::Regex, String> {
+    let mut source = String::from(if case_sensitive { "(?s)^" } else { "(?is)^" });
+    let mut literal = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => literal.push(chars.next().unwrap_or('\\')),
+            '*' => {
+                source.push_str(&regex::escape(&literal));
+                literal.clear();
+                source.push_str(".*");
+            }
+            c => literal.push(c),
+        }
+    }
+    source.push_str(&regex::escape(&literal));
+    source.push('$');
+    regex::Regex::new(&source).map_err(|e| e.to_string())
+}
+
+pub(crate) fn ip_in(ip: &IpAddr, item: &SetItem) -> bool {
+    match item {
+        SetItem::Ip(other) => ip == other,
+        SetItem::Cidr(network, prefix) => match (ip, network) {
+            (IpAddr::V4(a), IpAddr::V4(n)) => {
+                let mask = if *prefix == 0 { 0 } else { u32::MAX << (32 - *prefix as u32) };
+                u32::from(*a) & mask == u32::from(*n) & mask
+            }
+            (IpAddr::V6(a), IpAddr::V6(n)) => {
+                let mask = if *prefix == 0 { 0 } else { u128::MAX << (128 - *prefix as u32) };
+                u128::from(*a) & mask == u128::from(*n) & mask
+            }
+            _ => false,
+        },
+        SetItem::IpRange(start, end) => match (ip, start, end) {
+            (IpAddr::V4(a), IpAddr::V4(s), IpAddr::V4(e)) => (u32::from(*s)..=u32::from(*e)).contains(&u32::from(*a)),
+            (IpAddr::V6(a), IpAddr::V6(s), IpAddr::V6(e)) => (u128::from(*s)..=u128::from(*e)).contains(&u128::from(*a)),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn compare(value: &Val, op: CompareOp, rhs: &Literal, ctx: &EvalContext) -> Result<bool, String> {
+    if op == CompareOp::In {
+        let items: Vec<SetItem> = match rhs {
+            Literal::Set(items) => items.clone(),
+            Literal::List(name) => {
+                let entries = ctx.lists.get(name)
+                    .ok_or_else(|| format!("List ${} cannot be evaluated locally; supply its items in `lists`", name))?;
+                entries.iter()
+                    .map(|entry| match value {
+                        Val::Ip(_) => parse_ip(entry.trim()).unwrap_or(SetItem::String(entry.clone())),
+                        Val::Int(_) => entry.trim().parse().map(SetItem::Int).unwrap_or(SetItem::String(entry.clone())),
+                        _ => SetItem::String(entry.clone()),
+                    })
+                    .collect()
+            }
+            _ => return Err("Expected a set or list after 'in'".to_string()),
+        };
+        return Ok(items.iter().any(|item| match (value, item) {
+            (Val::Bytes(s), SetItem::String(t)) => s == t,
+            (Val::Int(n), SetItem::Int(m)) => n == m,
+            (Val::Int(n), SetItem::IntRange(a, b)) => (*a..=*b).contains(n),
+            (Val::Ip(ip), item) => ip_in(ip, item),
+            _ => false,
+        }));
+    }
+
+    let result = match (value, rhs) {
+        (Val::Missing, _) => false,
+        (Val::Bytes(s), Literal::String(t)) => match op {
+            CompareOp::Eq => s == t,
+            CompareOp::Ne => s != t,
+            CompareOp::Lt => s < t,
+            CompareOp::Le => s <= t,
+            CompareOp::Gt => s > t,
+            CompareOp::Ge => s >= t,
+            CompareOp::Contains => s.contains(t.as_str()),
+            CompareOp::Matches => regex::Regex::new(t).map_err(|e| e.to_string())?.is_match(s),
+            CompareOp::Wildcard => wildcard_regex(t, false)?.is_match(s),
+            CompareOp::StrictWildcard => wildcard_regex(t, true)?.is_match(s),
+            CompareOp::In => unreachable!(),
+        },
+        (Val::Int(n), Literal::Int(m)) => match op {
+            CompareOp::Eq => n == m,
+            CompareOp::Ne => n != m,
+            CompareOp::Lt => n < m,
+            CompareOp::Le => n <= m,
+            CompareOp::Gt => n > m,
+            CompareOp::Ge => n >= m,
+            _ => false,
+        },
+        (Val::Ip(a), Literal::Ip(b)) => match op {
+            CompareOp::Eq => a == b,
+            CompareOp::Ne => a != b,
+            _ => false,
+        },
+        (value, rhs) => return Err(format!("Cannot compare {:?} with {}", value, format_literal(rhs))),
+    };
+    Ok(result)
+}
+
+fn text(value: &Val) -> Option<&str> {
+    match value {
+        Val::Bytes(s) => Some(s),
+        _ => None,
+    }
+}
+
+fn call(name: &str, args: Vec<Val>) -> Result<Val, String> {
+    if args.contains(&Val::Missing) && name != "has_key" {
+        return Ok(Val::Missing);
+    }
+    let first = args.first().cloned().unwrap_or(Val::Missing);
+    let string_arg = |i: usize| args.get(i).and_then(text).unwrap_or("").to_string();
+    let int_arg = |i: usize| match args.get(i) {
+        Some(Val::Int(n)) => Some(*n),
+        _ => None,
+    };
+
+    let value = match name {
+        "lower" => Val::Bytes(string_arg(0).to_lowercase()),
+        "upper" => Val::Bytes(string_arg(0).to_uppercase()),
+        "url_decode" => Val::Bytes(urlencoding::decode(&string_arg(0).replace('+', " ")).map(|s| s.into_owned()).unwrap_or_else(|_| string_arg(0))),
+        "remove_bytes" => {
+            let remove = string_arg(1);
+            Val::Bytes(string_arg(0).chars().filter(|c| !remove.contains(*c)).collect())
+        }
+        "starts_with" => Val::Bool(string_arg(0).starts_with(&string_arg(1))),
+        "ends_with" => Val::Bool(string_arg(0).ends_with(&string_arg(1))),
+        "len" => match &first {
+            Val::Bytes(s) => Val::Int(s.len() as i64),
+            other => Val::Int(other.clone().elements().len() as i64),
+        },
+        "any" | "all" => {
+            let items = first.elements();
+            let bools = items.iter().map(|v| matches!(v, Val::Bool(true)));
+            Val::Bool(if name == "any" { bools.into_iter().any(|b| b) } else { !items.is_empty() && bools.into_iter().all(|b| b) })
+        }
+        "concat" => match &first {
+            Val::Bytes(_) => Val::Bytes(args.iter().filter_map(text).collect()),
+            _ => Val::Array(args.into_iter().flat_map(Val::elements).collect()),
+        },
+        "to_string" => Val::Bytes(match &first {
+            Val::Int(n) => n.to_string(),
+            Val::Bool(b) => b.to_string(),
+            Val::Ip(ip) => ip.to_string(),
+            other => format!("{:?}", other),
+        }),
+        "substring" => {
+            let chars: Vec<char> = string_arg(0).chars().collect();
+            let len = chars.len() as i64;
+            let resolve = |i: i64| if i < 0 { (len + i).max(0) } else { i.min(len) };
+            let start = resolve(int_arg(1).unwrap_or(0));
+            let end = int_arg(2).map(resolve).unwrap_or(len);
+            Val::Bytes(if start < end { chars[start as usize..end as usize].iter().collect() } else { String::new() })
+        }
+        "join" => Val::Bytes(first.elements().iter().filter_map(text).collect::<Vec<_>>().join(&string_arg(1))),
+        "has_key" => match &first {
+            Val::Map(entries) => Val::Bool(entries.iter().any(|(k, _)| *k == string_arg(1))),
+            _ => Val::Bool(false),
+        },
+        "has_value" => {
+            let needle = args.get(1).cloned().unwrap_or(Val::Missing);
+            let found = first.elements().into_iter().any(|v| v == needle || v.elements().contains(&needle));
+            Val::Bool(found)
+        }
+        "regex_replace" => {
+            let re = regex::Regex::new(&string_arg(1)).map_err(|e| e.to_string())?;
+            Val::Bytes(re.replacen(&string_arg(0), 1, string_arg(2).as_str()).into_owned())
+        }
+        "lookup_json_string" | "lookup_json_integer" => {
+            let mut current: serde_json::Value = match serde_json::from_str(&string_arg(0)) {
+                Ok(v) => v,
+                Err(_) => return Ok(Val::Missing),
+            };
+            for key in &args[1..] {
+                current = match key {
+                    Val::Bytes(k) => current.get(k.as_str()).cloned().unwrap_or(serde_json::Value::Null),
+                    Val::Int(i) => current.get(*i as usize).cloned().unwrap_or(serde_json::Value::Null),
+                    _ => serde_json::Value::Null,
+                };
+            }
+            match (name, current) {
+                ("lookup_json_string", serde_json::Value::String(s)) => Val::Bytes(s),
+                ("lookup_json_integer", serde_json::Value::Number(n)) => n.as_i64().map(Val::Int).unwrap_or(Val::Missing),
+                _ => Val::Missing,
+            }
+        }
+        other => return Err(format!("Function {}() is not supported by the local evaluator", other)),
+    };
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string_literal(input: &str) -> Result<String, String> {
+        match parse(&format!("http.host eq {}", input)).map_err(|e| e.message)? {
+            Expr::Compare(_, _, Literal::String(value)) => Ok(value),
+            other => panic!("unexpected expression {:?}", other),
+        }
+    }
+
+    #[test]
+    fn decodes_string_escapes() {
+        assert_eq!(string_literal(r#""a\"b\\c""#).unwrap(), "a\"b\\c");
+        assert_eq!(string_literal(r#""\x41\x62""#).unwrap(), "Ab");
+        assert_eq!(string_literal("\"é\"").unwrap(), "é");
+    }
+
+    #[test]
+    fn hex_escapes_are_utf8_bytes() {
+        assert_eq!(string_literal(r#""caf\xc3\xa9""#).unwrap(), "café");
+        assert_eq!(string_literal(r#""\xe2\x82\xac""#).unwrap(), "€");
+        assert!(string_literal(r#""\xff""#).unwrap_err().contains("not valid UTF-8"));
+        assert!(string_literal(r#""\xc3""#).unwrap_err().contains("not valid UTF-8"));
+    }
+
+    #[test]
+    fn rejects_invalid_escapes() {
+        assert!(string_literal(r#""\x4""#).is_err());
+        assert!(string_literal(r#""\x+1""#).unwrap_err().contains("two hex digits"));
+        assert!(string_literal(r#""\n""#).unwrap_err().contains("Invalid escape"));
+        assert!(string_literal(r#""abc"#).unwrap_err().contains("Unterminated"));
+    }
+
+    #[test]
+    fn canonical_form_round_trips_escapes() {
+        let expr = parse(r#"http.host eq "a\x01\"b""#).unwrap();
+        let canonical = format(&expr);
+        assert_eq!(parse(&canonical).unwrap(), expr);
+    }
+
+    #[test]
+    fn unknown_fields_are_warnings() {
+        let (_, warnings) = parse_with_warnings(r#"cf.some_new_field eq "x" and http.host eq "a""#).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].message.contains("cf.some_new_field"));
+
+        let (_, warnings) = parse_with_warnings(r#"http.host eq "a""#).unwrap();
+        assert!(warnings.is_empty());
+    }
+}
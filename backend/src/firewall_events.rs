@@ -0,0 +1,226 @@
+use crate::cloudflare::CloudflareClient;
+use crate::custom_rules;
+use crate::expression;
+use crate::models::*;
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use serde_json::{json, Map, Value};
+
+const DEFAULT_LIMIT: u32 = 100;
+const MAX_LIMIT: u32 = 10_000;
+const DEFAULT_TOP: u32 = 10;
+const MAX_TOP: u32 = 100;
+
+const EVENTS_QUERY: &str = r#"
+query FirewallEvents($zoneTag: string, $filter: FirewallEventsAdaptiveFilter_InputObject, $limit: uint64!, $top: uint64!) {
+    viewer {
+        zones(filter: { zoneTag: $zoneTag }) {
+            events: firewallEventsAdaptive(filter: $filter, limit: $limit, orderBy: [datetime_DESC]) {
+                datetime
+                action
+                source
+                ruleId
+                rayName
+                clientIP
+                clientAsn
+                clientCountryName
+                clientRequestHTTPHost
+                clientRequestHTTPMethodName
+                clientRequestPath
+                clientRequestQuery
+                userAgent
+            }
+            topIps: firewallEventsAdaptiveGroups(filter: $filter, limit: $top, orderBy: [count_DESC]) {
+                count
+                dimensions { clientIP }
+            }
+            topRules: firewallEventsAdaptiveGroups(filter: $filter, limit: $top, orderBy: [count_DESC]) {
+                count
+                dimensions { ruleId source }
+            }
+            topPaths: firewallEventsAdaptiveGroups(filter: $filter, limit: $top, orderBy: [count_DESC]) {
+                count
+                dimensions { clientRequestPath }
+            }
+        }
+    }
+}
+"#;
+
+fn parse_time(value: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|e| format!("Invalid time '{}': {}", value, e))
+}
+
+fn time_window(request: &FirewallEventsRequest) -> Result<(DateTime<Utc>, DateTime<Utc>), String> {
+    let until = match &request.until {
+        Some(until) => parse_time(until)?,
+        None => Utc::now(),
+    };
+    let since = match &request.since {
+        Some(since) => parse_time(since)?,
+        None => {
+            let range = match request.time_range.as_deref().unwrap_or("24h") {
+                "1h" => Duration::hours(1),
+                "6h" => Duration::hours(6),
+                "24h" => Duration::hours(24),
+                "7d" => Duration::days(7),
+                other => return Err(format!("Unsupported time range '{}' (expected 1h, 6h, 24h or 7d)", other)),
+            };
+            until - range
+        }
+    };
+    if since >= until {
+        return Err("since must be earlier than until".to_string());
+    }
+    Ok((since, until))
+}
+
+fn build_filter(request: &FirewallEventsRequest, since: &DateTime<Utc>, until: &DateTime<Utc>) -> Value {
+    let mut filter = Map::new();
+    filter.insert("datetime_geq".to_string(), json!(since.to_rfc3339_opts(SecondsFormat::Secs, true)));
+    filter.insert("datetime_leq".to_string(), json!(until.to_rfc3339_opts(SecondsFormat::Secs, true)));
+
+    let optional = [
+        ("action", &request.action),
+        ("ruleId", &request.rule_id),
+        ("clientIP", &request.ip),
+        ("clientRequestHTTPHost", &request.host),
+    ];
+    for (key, value) in optional {
+        if let Some(value) = value.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+            filter.insert(key.to_string(), json!(value));
+        }
+    }
+
+    if let Some(country) = request.country.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+        filter.insert("clientCountryName".to_string(), json!(country.to_ascii_uppercase()));
+    }
+    if let Some(path) = request.path.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+        let key = if path.contains('%') { "clientRequestPath_like" } else { "clientRequestPath" };
+        filter.insert(key.to_string(), json!(path));
+    }
+
+    Value::Object(filter)
+}
+
+fn counts(groups: &Value, dimension: &str) -> Vec<EventCount> {
+    groups.as_array()
+        .map(|groups| {
+            groups.iter()
+                .map(|g| EventCount {
+                    value: g["dimensions"][dimension].as_str().unwrap_or_default().to_string(),
+                    count: g["count"].as_u64().unwrap_or(0),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// 查询防火墙事件及排行（Top IP / 规则 / 路径）
+pub async fn query_events(client: &CloudflareClient, request: &FirewallEventsRequest) -> Result<FirewallEventsResult, String> {
+    let (since, until) = time_window(request)?;
+    let variables = json!({
+        "zoneTag": request.zone_id,
+        "filter": build_filter(request, &since, &until),
+        "limit": request.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT),
+        "top": request.top.unwrap_or(DEFAULT_TOP).clamp(1, MAX_TOP),
+    });
+
+    let zone = client.query_zone_analytics(EVENTS_QUERY, variables).await?;
+
+    let events: Vec<FirewallEvent> = serde_json::from_value(zone["events"].clone())
+        .map_err(|e| format!("Failed to parse firewall events: {}", e))?;
+
+    let top_rules = zone["topRules"].as_array()
+        .map(|groups| {
+            groups.iter()
+                .map(|g| EventRuleCount {
+                    rule_id: g["dimensions"]["ruleId"].as_str().unwrap_or_default().to_string(),
+                    source: g["dimensions"]["source"].as_str().unwrap_or_default().to_string(),
+                    count: g["count"].as_u64().unwrap_or(0),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(FirewallEventsResult {
+        since: since.to_rfc3339_opts(SecondsFormat::Secs, true),
+        until: until.to_rfc3339_opts(SecondsFormat::Secs, true),
+        events,
+        top_ips: counts(&zone["topIps"], "clientIP"),
+        top_rules,
+        top_paths: counts(&zone["topPaths"], "clientRequestPath"),
+    })
+}
+
+// 由事件字段拼接匹配表达式
+fn event_expression(event: &FirewallEvent, match_on: &[String]) -> Result<String, String> {
+    let default_fields = ["ip".to_string()];
+    let fields = if match_on.is_empty() { &default_fields[..] } else { match_on };
+
+    let mut clauses = Vec::new();
+    for field in fields {
+        let (name, value) = match field.as_str() {
+            "ip" => ("ip.src", event.client_ip.as_str()),
+            "asn" => ("ip.src.asnum", event.client_asn.as_str()),
+            "country" => ("ip.src.country", event.country.as_str()),
+            "host" => ("http.host", event.host.as_str()),
+            "path" => ("http.request.uri.path", event.path.as_str()),
+            "method" => ("http.request.method", event.method.as_str()),
+            "user_agent" => ("http.user_agent", event.user_agent.as_str()),
+            other => return Err(format!("Unsupported event field '{}'", other)),
+        };
+        if value.is_empty() {
+            return Err(format!("Event has no {} to match on", field));
+        }
+
+        let literal = match field.as_str() {
+            "ip" | "asn" => value.to_string(),
+            _ => expression::quote(value),
+        };
+        clauses.push(format!("{} eq {}", name, literal));
+    }
+
+    let expression = clauses.join(" and ");
+    expression::validate(&expression)?;
+    Ok(expression)
+}
+
+// 根据事件生成自定义规则草稿；apply 为 true 时直接创建
+pub async fn rule_from_event(client: &CloudflareClient, request: &RuleFromEventRequest) -> Result<RuleFromEvent, String> {
+    let expression = event_expression(&request.event, &request.match_on)?;
+
+    let (action, action_parameters) = match request.action.as_str() {
+        "allow" => ("skip".to_string(), Some(json!({ "ruleset": "current" }))),
+        other => (other.to_string(), None),
+    };
+
+    let subject = if request.event.ray_name.is_empty() {
+        request.event.client_ip.clone()
+    } else {
+        format!("ray {}", request.event.ray_name)
+    };
+
+    let rule = RulesetRule {
+        id: None,
+        expression,
+        action,
+        description: Some(format!("{} from firewall event ({})", request.action, subject)),
+        enabled: true,
+        action_parameters,
+        ratelimit: None,
+        logging: None,
+        rule_ref: None,
+        position: None,
+    };
+    custom_rules::validate(&rule)?;
+
+    let created = if request.apply {
+        Some(custom_rules::create_rule(client, &request.zone_id, &rule).await?)
+    } else {
+        None
+    };
+
+    Ok(RuleFromEvent { rule, created })
+}
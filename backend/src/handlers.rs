@@ -1,6 +1,29 @@
 use actix_web::{web, HttpResponse, Responder, HttpRequest};
+use crate::access_rules;
+use crate::audit;
+use crate::backup;
+use crate::bulk::{self, BulkJobStore};
 use crate::cloudflare::CloudflareClient;
+use crate::custom_rules;
+use crate::drift::DriftStore;
+use crate::expression;
+use crate::firewall_events;
+use crate::lists;
+use crate::managed_rules;
 use crate::models::*;
+use crate::rate_limit_rules;
+use crate::rate_limit_simulator;
+use crate::rule_templates;
+use crate::rule_tester;
+use crate::settings_matrix;
+use crate::terraform;
+use crate::ua_rules;
+use crate::worker_deploy;
+use crate::worker_schedules;
+use crate::worker_scripts;
+use crate::worker_tail;
+use crate::worker_versions;
+use crate::zone_lockdowns;
 
 // 健康检查
 pub async fn health_check() -> impl Responder {
@@ -170,104 +193,817 @@ pub async fn get_firewall_rules(req: web::Json<CloudflareRequest<GetFirewallRule
 
 // 创建防火墙规则
 pub async fn create_firewall_rule(req: web::Json<CloudflareRequest<CreateFirewallRuleRequest>>) -> impl Responder {
+    if let Err(e) = expression::validate(&req.data.rule.filter.expression) {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e));
+    }
+
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match client.create_firewall_rule(&req.data.zone_id, &req.data.rule).await {
+        Ok(rule) => HttpResponse::Ok().json(ApiResponse::success(rule)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 更新防火墙规则
+pub async fn update_firewall_rule(req: web::Json<CloudflareRequest<UpdateFirewallRuleRequest>>) -> impl Responder {
+    if let Err(e) = expression::validate(&req.data.rule.filter.expression) {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e));
+    }
+
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match client.update_firewall_rule(&req.data.zone_id, &req.data.rule_id, &req.data.rule).await {
+        Ok(rule) => HttpResponse::Ok().json(ApiResponse::success(rule)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 删除防火墙规则
+pub async fn delete_firewall_rule(req: web::Json<CloudflareRequest<DeleteFirewallRuleRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match client.delete_firewall_rule(&req.data.zone_id, &req.data.rule_id).await {
+        Ok(id) => HttpResponse::Ok().json(ApiResponse::success(id)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 校验规则表达式并返回规范化形式
+pub async fn validate_expression(req: web::Json<ValidateExpressionRequest>) -> impl Responder {
+    HttpResponse::Ok().json(ApiResponse::success(expression::check(&req.expression)))
+}
+
+// 用示例请求在本地测试 Zone 的规则
+pub async fn test_rules(req: web::Json<CloudflareRequest<TestRulesRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match rule_tester::test_rules(&client, &req.data).await {
+        Ok(result) => HttpResponse::Ok().json(ApiResponse::success(result)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 获取 WAF 自定义规则（Rulesets）
+pub async fn get_custom_rules(req: web::Json<CloudflareRequest<GetCustomRulesRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match custom_rules::list_rules(&client, &req.data.zone_id).await {
+        Ok(rules) => HttpResponse::Ok().json(ApiResponse::success(rules)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 创建 WAF 自定义规则
+pub async fn create_custom_rule(req: web::Json<CloudflareRequest<CreateCustomRuleRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match custom_rules::create_rule(&client, &req.data.zone_id, &req.data.rule).await {
+        Ok(rules) => HttpResponse::Ok().json(ApiResponse::success(rules)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 更新 WAF 自定义规则
+pub async fn update_custom_rule(req: web::Json<CloudflareRequest<UpdateCustomRuleRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match custom_rules::update_rule(&client, &req.data.zone_id, &req.data.rule_id, &req.data.rule).await {
+        Ok(rules) => HttpResponse::Ok().json(ApiResponse::success(rules)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 删除 WAF 自定义规则
+pub async fn delete_custom_rule(req: web::Json<CloudflareRequest<DeleteCustomRuleRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match custom_rules::delete_rule(&client, &req.data.zone_id, &req.data.rule_id).await {
+        Ok(rules) => HttpResponse::Ok().json(ApiResponse::success(rules)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 启用/禁用 WAF 自定义规则
+pub async fn set_custom_rule_enabled(req: web::Json<CloudflareRequest<SetCustomRuleEnabledRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match custom_rules::set_enabled(&client, &req.data.zone_id, &req.data.rule_id, req.data.enabled).await {
+        Ok(rules) => HttpResponse::Ok().json(ApiResponse::success(rules)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 调整 WAF 自定义规则顺序
+pub async fn reorder_custom_rules(req: web::Json<CloudflareRequest<ReorderCustomRulesRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match custom_rules::reorder(&client, &req.data.zone_id, &req.data.rule_ids).await {
+        Ok(rules) => HttpResponse::Ok().json(ApiResponse::success(rules)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 将旧版防火墙规则迁移为 WAF 自定义规则
+pub async fn migrate_firewall_rules(req: web::Json<CloudflareRequest<MigrateLegacyRulesRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match custom_rules::migrate(&client, &req.data).await {
+        Ok(report) => HttpResponse::Ok().json(ApiResponse::success(report)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 查询防火墙事件（GraphQL）
+pub async fn get_firewall_events(req: web::Json<CloudflareRequest<FirewallEventsRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match firewall_events::query_events(&client, &req.data).await {
+        Ok(events) => HttpResponse::Ok().json(ApiResponse::success(events)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 根据防火墙事件生成放行/阻止规则
+pub async fn create_rule_from_event(req: web::Json<CloudflareRequest<RuleFromEventRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match firewall_events::rule_from_event(&client, &req.data).await {
+        Ok(result) => HttpResponse::Ok().json(ApiResponse::success(result)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 获取安全规则模板目录
+pub async fn get_rule_templates() -> impl Responder {
+    HttpResponse::Ok().json(ApiResponse::success(rule_templates::catalogue()))
+}
+
+// 用参数渲染模板（不创建规则）
+pub async fn render_rule_template(req: web::Json<RenderTemplateRequest>) -> impl Responder {
+    match rule_templates::render(&req) {
+        Ok(rendered) => HttpResponse::Ok().json(ApiResponse::success(rendered)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 将模板应用到单个 Zone
+pub async fn apply_rule_template(req: web::Json<CloudflareRequest<ApplyTemplateRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match rule_templates::apply(&client, &req.data).await {
+        Ok(applied) => HttpResponse::Ok().json(ApiResponse::success(applied)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 将模板批量应用到多个 Zone（后台任务）
+pub async fn apply_rule_template_bulk(
+    store: web::Data<BulkJobStore>,
+    req: web::Json<CloudflareRequest<ApplyTemplateBulkRequest>>,
+) -> impl Responder {
+    match rule_templates::apply_bulk(&store, &req.credentials, &req.data).await {
+        Ok(job) => HttpResponse::Ok().json(ApiResponse::success(job)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 列出 Zone 中由模板创建的规则及版本状态
+pub async fn get_rule_template_usage(req: web::Json<CloudflareRequest<TemplateUsageRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match rule_templates::zone_usage(&client, &req.data.zone_id).await {
+        Ok(usage) => HttpResponse::Ok().json(ApiResponse::success(usage)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 获取 IP 访问规则（Zone 或账户级别）
+pub async fn get_access_rules(req: web::Json<CloudflareRequest<ListAccessRulesRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match access_rules::list_rules(&client, &req.data).await {
+        Ok(rules) => HttpResponse::Ok().json(ApiResponse::success(rules)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 创建 IP 访问规则
+pub async fn create_access_rule(req: web::Json<CloudflareRequest<CreateAccessRuleRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match access_rules::create_rule(&client, &req.data).await {
+        Ok(rule) => HttpResponse::Ok().json(ApiResponse::success(rule)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 更新 IP 访问规则
+pub async fn update_access_rule(req: web::Json<CloudflareRequest<UpdateAccessRuleRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match access_rules::update_rule(&client, &req.data).await {
+        Ok(rule) => HttpResponse::Ok().json(ApiResponse::success(rule)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 删除 IP 访问规则
+pub async fn delete_access_rule(req: web::Json<CloudflareRequest<DeleteAccessRuleRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match access_rules::delete_rule(&client, &req.data).await {
+        Ok(rule_id) => HttpResponse::Ok().json(ApiResponse::success(rule_id)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 批量导入 IP 访问规则
+pub async fn import_access_rules(req: web::Json<CloudflareRequest<ImportAccessRulesRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match access_rules::import(&client, &req.data).await {
+        Ok(report) => HttpResponse::Ok().json(ApiResponse::success(report)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 按 IP 或文本查找 IP 访问规则
+pub async fn search_access_rules(req: web::Json<CloudflareRequest<SearchAccessRulesRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match access_rules::search(&client, &req.data).await {
+        Ok(matches) => HttpResponse::Ok().json(ApiResponse::success(matches)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 获取 Zone Lockdown 规则
+pub async fn get_zone_lockdowns(req: web::Json<CloudflareRequest<GetZoneLockdownsRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match zone_lockdowns::list_lockdowns(&client, &req.data.zone_id).await {
+        Ok(lockdowns) => HttpResponse::Ok().json(ApiResponse::success(lockdowns)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 创建 Zone Lockdown 规则
+pub async fn create_zone_lockdown(req: web::Json<CloudflareRequest<CreateZoneLockdownRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match zone_lockdowns::create_lockdown(&client, &req.data.zone_id, &req.data.lockdown).await {
+        Ok(lockdown) => HttpResponse::Ok().json(ApiResponse::success(lockdown)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 更新 Zone Lockdown 规则
+pub async fn update_zone_lockdown(req: web::Json<CloudflareRequest<UpdateZoneLockdownRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match zone_lockdowns::update_lockdown(&client, &req.data.zone_id, &req.data.lockdown_id, &req.data.lockdown).await {
+        Ok(lockdown) => HttpResponse::Ok().json(ApiResponse::success(lockdown)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 删除 Zone Lockdown 规则
+pub async fn delete_zone_lockdown(req: web::Json<CloudflareRequest<DeleteZoneLockdownRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match zone_lockdowns::delete_lockdown(&client, &req.data.zone_id, &req.data.lockdown_id).await {
+        Ok(id) => HttpResponse::Ok().json(ApiResponse::success(id)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 将 Zone Lockdown 规则迁移为 WAF 自定义规则
+pub async fn migrate_zone_lockdowns(req: web::Json<CloudflareRequest<MigrateLegacyRulesRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match zone_lockdowns::migrate(&client, &req.data).await {
+        Ok(report) => HttpResponse::Ok().json(ApiResponse::success(report)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 获取 User Agent 拦截规则
+pub async fn get_ua_rules(req: web::Json<CloudflareRequest<GetUserAgentRulesRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match ua_rules::list_rules(&client, &req.data.zone_id).await {
+        Ok(rules) => HttpResponse::Ok().json(ApiResponse::success(rules)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 创建 User Agent 拦截规则
+pub async fn create_ua_rule(req: web::Json<CloudflareRequest<CreateUserAgentRuleRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match ua_rules::create_rule(&client, &req.data.zone_id, &req.data.rule).await {
+        Ok(rule) => HttpResponse::Ok().json(ApiResponse::success(rule)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 更新 User Agent 拦截规则
+pub async fn update_ua_rule(req: web::Json<CloudflareRequest<UpdateUserAgentRuleRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match ua_rules::update_rule(&client, &req.data.zone_id, &req.data.rule_id, &req.data.rule).await {
+        Ok(rule) => HttpResponse::Ok().json(ApiResponse::success(rule)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 删除 User Agent 拦截规则
+pub async fn delete_ua_rule(req: web::Json<CloudflareRequest<DeleteUserAgentRuleRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match ua_rules::delete_rule(&client, &req.data.zone_id, &req.data.rule_id).await {
+        Ok(id) => HttpResponse::Ok().json(ApiResponse::success(id)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 将 User Agent 拦截规则迁移为 WAF 自定义规则
+pub async fn migrate_ua_rules(req: web::Json<CloudflareRequest<MigrateLegacyRulesRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match ua_rules::migrate(&client, &req.data).await {
+        Ok(report) => HttpResponse::Ok().json(ApiResponse::success(report)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 获取账户 Lists
+pub async fn get_rules_lists(req: web::Json<CloudflareRequest<ListRulesListsRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match lists::list_lists(&client, &req.data.account_id).await {
+        Ok(lists) => HttpResponse::Ok().json(ApiResponse::success(lists)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 创建 List
+pub async fn create_rules_list(req: web::Json<CloudflareRequest<CreateRulesListRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match lists::create_list(&client, &req.data).await {
+        Ok(list) => HttpResponse::Ok().json(ApiResponse::success(list)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 更新 List
+pub async fn update_rules_list(req: web::Json<CloudflareRequest<UpdateRulesListRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match lists::update_list(&client, &req.data).await {
+        Ok(list) => HttpResponse::Ok().json(ApiResponse::success(list)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 删除 List
+pub async fn delete_rules_list(req: web::Json<CloudflareRequest<RulesListRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match lists::delete_list(&client, &req.data).await {
+        Ok(list_id) => HttpResponse::Ok().json(ApiResponse::success(list_id)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 获取 List 条目
+pub async fn get_rules_list_items(req: web::Json<CloudflareRequest<RulesListRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match lists::get_items(&client, &req.data).await {
+        Ok(items) => HttpResponse::Ok().json(ApiResponse::success(items)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 添加 List 条目
+pub async fn add_rules_list_items(req: web::Json<CloudflareRequest<AddListItemsRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match lists::add_items(&client, &req.data).await {
+        Ok(operation) => HttpResponse::Ok().json(ApiResponse::success(operation)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 删除 List 条目
+pub async fn delete_rules_list_items(req: web::Json<CloudflareRequest<DeleteListItemsRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match lists::delete_items(&client, &req.data).await {
+        Ok(operation) => HttpResponse::Ok().json(ApiResponse::success(operation)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 查询 List 批量操作的当前状态
+pub async fn get_rules_list_operation(req: web::Json<CloudflareRequest<GetListOperationRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match lists::get_operation(&client, &req.data.account_id, &req.data.operation_id).await {
+        Ok(operation) => HttpResponse::Ok().json(ApiResponse::success(operation)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 比较本地来源与 List
+pub async fn diff_rules_list(req: web::Json<CloudflareRequest<SyncListRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match lists::diff(&client, &req.data).await {
+        Ok(diff) => HttpResponse::Ok().json(ApiResponse::success(diff)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 将本地来源同步到 List
+pub async fn sync_rules_list(req: web::Json<CloudflareRequest<SyncListRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match lists::sync(&client, &req.data).await {
+        Ok(diff) => HttpResponse::Ok().json(ApiResponse::success(diff)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 部署 Worker
+pub async fn deploy_worker(req: web::Json<CloudflareRequest<DeployWorkerRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match worker_deploy::deploy(&client, &req.data).await {
+        Ok(report) => HttpResponse::Ok().json(ApiResponse::success(report)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 列出 Workers
+pub async fn list_workers(req: web::Json<CloudflareRequest<ListWorkersRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match client.list_workers(&req.data.account_id).await {
+        Ok(workers) => HttpResponse::Ok().json(ApiResponse::success(workers)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 获取单个 Worker
+pub async fn get_worker(req: web::Json<CloudflareRequest<GetWorkerRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    if req.data.archive {
+        let archive = client.get_worker(&req.data.account_id, &req.data.script_name)
+            .await
+            .and_then(|content| worker_scripts::archive(&content));
+        return match archive {
+            Ok(bytes) => HttpResponse::Ok()
+                .content_type("application/zip")
+                .insert_header((
+                    actix_web::http::header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}.zip\"", req.data.script_name),
+                ))
+                .body(bytes),
+            Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+        };
+    }
+
+    match worker_scripts::get(&client, &req.data.account_id, &req.data.script_name).await {
+        Ok(details) => HttpResponse::Ok().json(ApiResponse::success(details)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 删除 Worker
+pub async fn delete_worker(req: web::Json<CloudflareRequest<DeleteWorkerRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match client.delete_worker(&req.data.account_id, &req.data.script_name).await {
+        Ok(message) => HttpResponse::Ok().json(ApiResponse::success(message)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 上传/更新 Worker
+pub async fn upload_worker(req: web::Json<CloudflareRequest<UploadWorkerRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match worker_scripts::upload(&client, &req.data, Vec::new()).await {
+        Ok(result) => HttpResponse::Ok().json(ApiResponse::success(result)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 以 multipart 上传 Worker 模块包（credentials、metadata 字段与模块文件或归档）
+pub async fn upload_worker_bundle(payload: web::Bytes, req: HttpRequest) -> impl Responder {
+    let content_type = req.headers()
+        .get(actix_web::http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+
+    let (credentials, request, files) = match worker_scripts::parse_bundle(content_type, &payload) {
+        Ok(bundle) => bundle,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    let client = match CloudflareClient::new(&credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match worker_scripts::upload(&client, &request, files).await {
+        Ok(result) => HttpResponse::Ok().json(ApiResponse::success(result)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 下载 Worker 的全部模块
+pub async fn download_worker(req: web::Json<CloudflareRequest<DownloadWorkerRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match worker_scripts::download(&client, &req.data.account_id, &req.data.script_name).await {
+        Ok(bundle) => HttpResponse::Ok().json(ApiResponse::success(bundle)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 列出 Worker 的全部版本
+pub async fn list_worker_versions(req: web::Json<CloudflareRequest<ListWorkerVersionsRequest>>) -> impl Responder {
     let client = match CloudflareClient::new(&req.credentials) {
         Ok(c) => c,
         Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
     };
 
-    match client.create_firewall_rule(&req.data.zone_id, &req.data.rule).await {
-        Ok(rule) => HttpResponse::Ok().json(ApiResponse::success(rule)),
+    match worker_versions::list_versions(&client, &req.data.account_id, &req.data.script_name).await {
+        Ok(versions) => HttpResponse::Ok().json(ApiResponse::success(versions)),
         Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
     }
 }
 
-// 更新防火墙规则
-pub async fn update_firewall_rule(req: web::Json<CloudflareRequest<UpdateFirewallRuleRequest>>) -> impl Responder {
+// 上传 Worker 新版本（不部署）
+pub async fn upload_worker_version(req: web::Json<CloudflareRequest<UploadWorkerVersionRequest>>) -> impl Responder {
     let client = match CloudflareClient::new(&req.credentials) {
         Ok(c) => c,
         Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
     };
 
-    match client.update_firewall_rule(&req.data.zone_id, &req.data.rule_id, &req.data.rule).await {
-        Ok(rule) => HttpResponse::Ok().json(ApiResponse::success(rule)),
+    match worker_versions::upload_version(&client, &req.data).await {
+        Ok(version) => HttpResponse::Ok().json(ApiResponse::success(version)),
         Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
     }
 }
 
-// 删除防火墙规则
-pub async fn delete_firewall_rule(req: web::Json<CloudflareRequest<DeleteFirewallRuleRequest>>) -> impl Responder {
+// 获取 Worker 部署历史
+pub async fn get_worker_deployments(req: web::Json<CloudflareRequest<GetWorkerDeploymentsRequest>>) -> impl Responder {
     let client = match CloudflareClient::new(&req.credentials) {
         Ok(c) => c,
         Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
     };
 
-    match client.delete_firewall_rule(&req.data.zone_id, &req.data.rule_id).await {
-        Ok(id) => HttpResponse::Ok().json(ApiResponse::success(id)),
+    match worker_versions::history(&client, &req.data.account_id, &req.data.script_name).await {
+        Ok(history) => HttpResponse::Ok().json(ApiResponse::success(history)),
         Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
     }
 }
 
-// 部署 Worker
-pub async fn deploy_worker(req: web::Json<CloudflareRequest<DeployWorkerRequest>>) -> impl Responder {
+// 按百分比部署 Worker 版本（渐进式发布）
+pub async fn create_worker_deployment(req: web::Json<CloudflareRequest<CreateWorkerDeploymentRequest>>) -> impl Responder {
     let client = match CloudflareClient::new(&req.credentials) {
         Ok(c) => c,
         Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
     };
 
-    match client.deploy_worker(&req.data).await {
-        Ok(message) => HttpResponse::Ok().json(ApiResponse::success(message)),
+    match worker_versions::deploy(&client, &req.data).await {
+        Ok(deployment) => HttpResponse::Ok().json(ApiResponse::success(deployment)),
         Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
     }
 }
 
-// 列出 Workers
-pub async fn list_workers(req: web::Json<CloudflareRequest<ListWorkersRequest>>) -> impl Responder {
+// 回滚 Worker 到之前的版本
+pub async fn rollback_worker(req: web::Json<CloudflareRequest<RollbackWorkerRequest>>) -> impl Responder {
     let client = match CloudflareClient::new(&req.credentials) {
         Ok(c) => c,
         Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
     };
 
-    match client.list_workers(&req.data.account_id).await {
-        Ok(workers) => HttpResponse::Ok().json(ApiResponse::success(workers)),
+    match worker_versions::rollback(&client, &req.data).await {
+        Ok(deployment) => HttpResponse::Ok().json(ApiResponse::success(deployment)),
         Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
     }
 }
 
-// 获取单个 Worker
-pub async fn get_worker(req: web::Json<CloudflareRequest<GetWorkerRequest>>) -> impl Responder {
+// 实时查看 Worker 日志（SSE 事件流，浏览器断开后自动清理 tail 会话）
+pub async fn tail_worker(req: web::Json<CloudflareRequest<TailWorkerRequest>>) -> impl Responder {
     let client = match CloudflareClient::new(&req.credentials) {
         Ok(c) => c,
         Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
     };
 
-    match client.get_worker(&req.data.account_id, &req.data.script_name).await {
-        Ok(script) => HttpResponse::Ok().json(ApiResponse::success(script)),
+    match worker_tail::start(client, &req.data).await {
+        Ok(events) => HttpResponse::Ok()
+            .content_type("text/event-stream")
+            .insert_header((actix_web::http::header::CACHE_CONTROL, "no-cache"))
+            // 关闭 nginx 的响应缓冲，事件才能实时到达浏览器
+            .insert_header(("X-Accel-Buffering", "no"))
+            .streaming(events),
         Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
     }
 }
 
-// 删除 Worker
-pub async fn delete_worker(req: web::Json<CloudflareRequest<DeleteWorkerRequest>>) -> impl Responder {
+// 获取 Worker 的 Cron Triggers 及接下来的触发时间
+pub async fn get_worker_schedules(req: web::Json<CloudflareRequest<GetWorkerSchedulesRequest>>) -> impl Responder {
     let client = match CloudflareClient::new(&req.credentials) {
         Ok(c) => c,
         Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
     };
 
-    match client.delete_worker(&req.data.account_id, &req.data.script_name).await {
-        Ok(message) => HttpResponse::Ok().json(ApiResponse::success(message)),
+    match worker_schedules::get(&client, &req.data.account_id, &req.data.script_name).await {
+        Ok(schedules) => HttpResponse::Ok().json(ApiResponse::success(schedules)),
         Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
     }
 }
 
-// 上传/更新 Worker
-pub async fn upload_worker(req: web::Json<CloudflareRequest<UploadWorkerRequest>>) -> impl Responder {
+// 设置 Worker 的 Cron Triggers（先校验表达式）
+pub async fn update_worker_schedules(req: web::Json<CloudflareRequest<UpdateWorkerSchedulesRequest>>) -> impl Responder {
     let client = match CloudflareClient::new(&req.credentials) {
         Ok(c) => c,
         Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
     };
 
-    match client.upload_worker(&req.data.account_id, &req.data.script_name, &req.data.script_content).await {
-        Ok(result) => HttpResponse::Ok().json(ApiResponse::success(result)),
+    match worker_schedules::update(&client, &req.data).await {
+        Ok(schedules) => HttpResponse::Ok().json(ApiResponse::success(schedules)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 预览 cron 表达式接下来的触发时间（UTC，不调用 Cloudflare API）
+pub async fn preview_cron(req: web::Json<PreviewCronRequest>) -> impl Responder {
+    match worker_schedules::preview(&req) {
+        Ok(preview) => HttpResponse::Ok().json(ApiResponse::success(preview)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 获取 Worker 脚本设置（当前绑定等）
+pub async fn get_worker_settings(req: web::Json<CloudflareRequest<GetWorkerSettingsRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match worker_scripts::settings(&client, &req.data.account_id, &req.data.script_name).await {
+        Ok(settings) => HttpResponse::Ok().json(ApiResponse::success(settings)),
         Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
     }
 }
@@ -603,6 +1339,84 @@ pub async fn update_waf_package(req: web::Json<CloudflareRequest<UpdateWafPackag
     }
 }
 
+// 获取 WAF 托管规则集部署状态
+pub async fn get_managed_rules(req: web::Json<CloudflareRequest<ManagedRulesRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match managed_rules::status(&client, &req.data.zone_id).await {
+        Ok(status) => HttpResponse::Ok().json(ApiResponse::success(status)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 部署或更新 WAF 托管规则集
+pub async fn deploy_managed_ruleset(req: web::Json<CloudflareRequest<DeployManagedRulesetRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match managed_rules::deploy(&client, &req.data).await {
+        Ok(status) => HttpResponse::Ok().json(ApiResponse::success(status)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 移除 WAF 托管规则集
+pub async fn remove_managed_ruleset(req: web::Json<CloudflareRequest<RemoveManagedRulesetRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match managed_rules::remove(&client, &req.data).await {
+        Ok(status) => HttpResponse::Ok().json(ApiResponse::success(status)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 添加 WAF 托管规则例外
+pub async fn create_managed_exception(req: web::Json<CloudflareRequest<CreateManagedExceptionRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match managed_rules::add_exception(&client, &req.data).await {
+        Ok(status) => HttpResponse::Ok().json(ApiResponse::success(status)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 删除 WAF 托管规则例外
+pub async fn delete_managed_exception(req: web::Json<CloudflareRequest<DeleteManagedRuleRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match managed_rules::delete_exception(&client, &req.data).await {
+        Ok(status) => HttpResponse::Ok().json(ApiResponse::success(status)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 获取托管规则集的分类与规则
+pub async fn get_managed_catalog(req: web::Json<CloudflareRequest<ManagedCatalogRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match managed_rules::catalog(&client, &req.data).await {
+        Ok(catalog) => HttpResponse::Ok().json(ApiResponse::success(catalog)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
 // ===== Rate Limiting =====
 
 // 获取速率限制规则列表
@@ -657,6 +1471,84 @@ pub async fn delete_rate_limit(req: web::Json<CloudflareRequest<DeleteRateLimitR
     }
 }
 
+// 获取速率限制规则（http_ratelimit 阶段）
+pub async fn get_rate_limit_rules(req: web::Json<CloudflareRequest<GetRateLimitsRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match rate_limit_rules::list_rules(&client, &req.data.zone_id).await {
+        Ok(rules) => HttpResponse::Ok().json(ApiResponse::success(rules)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 创建速率限制规则
+pub async fn create_rate_limit_rule(req: web::Json<CloudflareRequest<CreateRateLimitRuleRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match rate_limit_rules::create_rule(&client, &req.data.zone_id, &req.data.rule).await {
+        Ok(rules) => HttpResponse::Ok().json(ApiResponse::success(rules)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 更新速率限制规则
+pub async fn update_rate_limit_rule(req: web::Json<CloudflareRequest<UpdateRateLimitRuleRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match rate_limit_rules::update_rule(&client, &req.data.zone_id, &req.data.rule_id, &req.data.rule).await {
+        Ok(rules) => HttpResponse::Ok().json(ApiResponse::success(rules)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 删除速率限制规则
+pub async fn delete_rate_limit_rule(req: web::Json<CloudflareRequest<DeleteRateLimitRuleRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match rate_limit_rules::delete_rule(&client, &req.data.zone_id, &req.data.rule_id).await {
+        Ok(rules) => HttpResponse::Ok().json(ApiResponse::success(rules)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 将旧版速率限制迁移为速率限制规则
+pub async fn migrate_rate_limits(req: web::Json<CloudflareRequest<MigrateLegacyRulesRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match rate_limit_rules::migrate(&client, &req.data).await {
+        Ok(report) => HttpResponse::Ok().json(ApiResponse::success(report)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 按历史流量模拟速率限制阈值
+pub async fn simulate_rate_limit(req: web::Json<CloudflareRequest<SimulateRateLimitRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match rate_limit_simulator::simulate(&client, &req.data).await {
+        Ok(simulation) => HttpResponse::Ok().json(ApiResponse::success(simulation)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
 // ==================== Workers KV ====================
 
 // 列出 KV Namespaces
@@ -821,3 +1713,197 @@ pub async fn execute_d1_query(req: web::Json<CloudflareRequest<ExecuteD1QueryReq
         }
     }
 }
+
+// ==================== 配置漂移检测 ====================
+
+// 将 Zone 当前配置设为基线
+pub async fn set_drift_baseline(
+    store: web::Data<DriftStore>,
+    req: web::Json<CloudflareRequest<SetDriftBaselineRequest>>,
+) -> impl Responder {
+    match store.set_baseline(&req.credentials, &req.data).await {
+        Ok(baseline) => HttpResponse::Ok().json(ApiResponse::success(baseline)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 列出已设置的基线
+pub async fn list_drift_baselines(
+    store: web::Data<DriftStore>,
+    req: web::Json<CloudflareRequest<serde_json::Value>>,
+) -> impl Responder {
+    HttpResponse::Ok().json(ApiResponse::success(store.list_baselines(&req.credentials)))
+}
+
+// 删除基线
+pub async fn delete_drift_baseline(
+    store: web::Data<DriftStore>,
+    req: web::Json<CloudflareRequest<DriftZoneRequest>>,
+) -> impl Responder {
+    match store.remove_baseline(&req.credentials, &req.data.zone_id) {
+        Ok(message) => HttpResponse::Ok().json(ApiResponse::success(message)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 立即执行漂移检测
+pub async fn check_drift(
+    store: web::Data<DriftStore>,
+    req: web::Json<CloudflareRequest<DriftZoneRequest>>,
+) -> impl Responder {
+    match store.check_zone_for(&req.credentials, &req.data.zone_id).await {
+        Ok(report) => HttpResponse::Ok().json(ApiResponse::success(report)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 查询漂移检测报告
+pub async fn get_drift_reports(
+    store: web::Data<DriftStore>,
+    req: web::Json<CloudflareRequest<GetDriftReportsRequest>>,
+) -> impl Responder {
+    HttpResponse::Ok().json(ApiResponse::success(store.reports(&req.credentials, &req.data)))
+}
+
+// ==================== 多 Zone 设置对比 ====================
+
+// 对比多个 Zone 的设置
+pub async fn compare_zone_settings(req: web::Json<CloudflareRequest<CompareZoneSettingsRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match settings_matrix::compare_zone_settings(&client, &req.data).await {
+        Ok(matrix) => HttpResponse::Ok().json(ApiResponse::success(matrix)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 将离群 Zone 的设置对齐到统一值
+pub async fn align_zone_setting(req: web::Json<CloudflareRequest<AlignZoneSettingRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match settings_matrix::align_zone_setting(&client, &req.data).await {
+        Ok(result) => HttpResponse::Ok().json(ApiResponse::success(result)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// ==================== 安全审计评分 ====================
+
+// 审计单个 Zone
+pub async fn audit_zone(req: web::Json<CloudflareRequest<AuditZoneRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match audit::audit_zone(&client, &req.data.zone_id).await {
+        Ok(report) if req.data.format == AuditFormat::Sarif => {
+            // SARIF 需要原始文档，不包在 ApiResponse 中
+            HttpResponse::Ok()
+                .content_type("application/sarif+json")
+                .body(audit::to_sarif(&[report]).to_string())
+        }
+        Ok(report) => HttpResponse::Ok().json(ApiResponse::success(report)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 审计多个 Zone（默认全部）
+pub async fn audit_zones(req: web::Json<CloudflareRequest<AuditZonesRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match audit::audit_zones(&client, &req.data).await {
+        Ok(reports) if req.data.format == AuditFormat::Sarif => {
+            // SARIF 需要原始文档，不包在 ApiResponse 中
+            HttpResponse::Ok()
+                .content_type("application/sarif+json")
+                .body(audit::to_sarif(&reports).to_string())
+        }
+        Ok(reports) => HttpResponse::Ok().json(ApiResponse::success(reports)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// ==================== 批量 Zone 操作 ====================
+
+// 创建批量操作任务（后台执行，立即返回任务 ID）
+pub async fn create_bulk_job(
+    store: web::Data<BulkJobStore>,
+    req: web::Json<CloudflareRequest<CreateBulkJobRequest>>,
+) -> impl Responder {
+    match bulk::create_job(&store, &req.credentials, &req.data).await {
+        Ok(job) => HttpResponse::Ok().json(ApiResponse::success(job)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 查询批量任务进度
+pub async fn get_bulk_job(
+    store: web::Data<BulkJobStore>,
+    req: web::Json<CloudflareRequest<GetBulkJobRequest>>,
+) -> impl Responder {
+    match store.get(&req.credentials, &req.data.job_id) {
+        Ok(job) => HttpResponse::Ok().json(ApiResponse::success(job)),
+        Err(e) => HttpResponse::NotFound().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 列出批量任务
+pub async fn list_bulk_jobs(
+    store: web::Data<BulkJobStore>,
+    req: web::Json<CloudflareRequest<serde_json::Value>>,
+) -> impl Responder {
+    HttpResponse::Ok().json(ApiResponse::success(store.list(&req.credentials)))
+}
+
+// ==================== 备份与恢复 ====================
+
+// 导出 Zone / 账户配置归档
+pub async fn export_backup(req: web::Json<CloudflareRequest<BackupExportRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match backup::export(&client, &req.data).await {
+        Ok(archive) => HttpResponse::Ok().json(ApiResponse::success(archive)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// 从归档恢复到相同或其他 Zone / 账户
+pub async fn restore_backup(req: web::Json<CloudflareRequest<BackupRestoreRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match backup::restore(&client, &req.data).await {
+        Ok(report) => HttpResponse::Ok().json(ApiResponse::success(report)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// ==================== Terraform 导出 ====================
+
+// 将 Zone 现有资源导出为 Terraform 配置和 import 块
+pub async fn export_terraform(req: web::Json<CloudflareRequest<TerraformExportRequest>>) -> impl Responder {
+    let client = match CloudflareClient::new(&req.credentials) {
+        Ok(c) => c,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    };
+
+    match terraform::export_zone(&client, &req.data).await {
+        Ok(export) => HttpResponse::Ok().json(ApiResponse::success(export)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
@@ -0,0 +1,317 @@
+use crate::access_rules;
+use crate::cloudflare::CloudflareClient;
+use crate::expression::{self, SetItem};
+use crate::models::*;
+use serde_json::json;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+// 支持的 List 类型
+const KINDS: &[&str] = &["ip", "hostname", "asn", "redirect"];
+
+// 批量操作轮询间隔与最大次数
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_POLLS: u32 = 120;
+
+const REDIRECT_STATUS_CODES: &[u64] = &[301, 302, 307, 308];
+
+// CSV 表头中可能出现的首列名称
+const HEADER_NAMES: &[&str] = &["ip", "hostname", "url_hostname", "asn", "source_url", "source", "value"];
+
+fn validate_list(list: &RulesList) -> Result<(), String> {
+    if !KINDS.contains(&list.kind.as_str()) {
+        return Err(format!("Unsupported list kind '{}' (expected one of: {})", list.kind, KINDS.join(", ")));
+    }
+    let valid_name = !list.name.is_empty()
+        && list.name.len() <= 50
+        && list.name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if !valid_name {
+        return Err("List name must be 1-50 lowercase letters, digits or underscores".to_string());
+    }
+    Ok(())
+}
+
+pub async fn list_lists(client: &CloudflareClient, account_id: &str) -> Result<Vec<RulesList>, String> {
+    client.list_rules_lists(account_id).await
+}
+
+pub async fn create_list(client: &CloudflareClient, request: &CreateRulesListRequest) -> Result<RulesList, String> {
+    validate_list(&request.list)?;
+    client.create_rules_list(&request.account_id, &request.list).await
+}
+
+pub async fn update_list(client: &CloudflareClient, request: &UpdateRulesListRequest) -> Result<RulesList, String> {
+    client.update_rules_list(&request.account_id, &request.list_id, &request.description).await
+}
+
+pub async fn delete_list(client: &CloudflareClient, request: &RulesListRequest) -> Result<String, String> {
+    client.delete_rules_list(&request.account_id, &request.list_id).await
+}
+
+pub async fn get_items(client: &CloudflareClient, request: &RulesListRequest) -> Result<Vec<ListItem>, String> {
+    client.list_rules_list_items(&request.account_id, &request.list_id).await
+}
+
+pub async fn get_operation(client: &CloudflareClient, account_id: &str, operation_id: &str) -> Result<ListOperation, String> {
+    client.get_rules_list_operation(account_id, operation_id).await
+}
+
+// 轮询批量操作直到完成；失败时返回操作错误信息
+async fn wait_for_operation(client: &CloudflareClient, account_id: &str, operation_id: &str) -> Result<ListOperation, String> {
+    for _ in 0..MAX_POLLS {
+        let operation = client.get_rules_list_operation(account_id, operation_id).await?;
+        match operation.status.as_str() {
+            "completed" => return Ok(operation),
+            "failed" => {
+                return Err(format!(
+                    "Bulk operation {} failed: {}",
+                    operation_id,
+                    operation.error.as_deref().unwrap_or("unknown error")
+                ))
+            }
+            _ => tokio::time::sleep(POLL_INTERVAL).await,
+        }
+    }
+    Err(format!("Timed out waiting for bulk operation {}", operation_id))
+}
+
+async fn finish(client: &CloudflareClient, account_id: &str, operation_id: &str, wait: bool) -> Result<ListOperation, String> {
+    if wait {
+        wait_for_operation(client, account_id, operation_id).await
+    } else {
+        client.get_rules_list_operation(account_id, operation_id).await
+    }
+}
+
+pub async fn add_items(client: &CloudflareClient, request: &AddListItemsRequest) -> Result<ListOperation, String> {
+    let list = client.get_rules_list(&request.account_id, &request.list_id).await?;
+
+    let mut items = Vec::with_capacity(request.items.len());
+    for item in &request.items {
+        let key = item_key(&list.kind, item)
+            .ok_or_else(|| format!("Item does not match list kind '{}': {}", list.kind, json!(item)))?;
+        let mut item = item.clone();
+        item.id = None;
+        if list.kind == "ip" {
+            item.ip = Some(key);
+        }
+        items.push(item);
+    }
+
+    let operation_id = client.add_rules_list_items(&request.account_id, &request.list_id, &items).await?;
+    finish(client, &request.account_id, &operation_id, request.wait).await
+}
+
+pub async fn delete_items(client: &CloudflareClient, request: &DeleteListItemsRequest) -> Result<ListOperation, String> {
+    let operation_id = client.delete_rules_list_items(&request.account_id, &request.list_id, &request.item_ids).await?;
+    finish(client, &request.account_id, &operation_id, request.wait).await
+}
+
+// 规范化 IP 条目：单个地址或网络地址形式的 CIDR
+fn normalize_ip(value: &str) -> Option<String> {
+    match expression::parse_ip(value.trim())? {
+        SetItem::Ip(ip) => Some(ip.to_string()),
+        SetItem::Cidr(ip, prefix) => match (ip.is_ipv4(), prefix) {
+            (true, 32) | (false, 128) => Some(ip.to_string()),
+            (true, 8..=31) | (false, 12..=64) => Some(format!("{}/{}", access_rules::network(ip, prefix), prefix)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// 条目比较键；与 List 类型不符时返回 None
+fn item_key(kind: &str, item: &ListItem) -> Option<String> {
+    match kind {
+        "ip" => normalize_ip(item.ip.as_deref()?),
+        "hostname" => item.hostname.as_ref()?["url_hostname"].as_str().map(|h| h.trim().to_ascii_lowercase()),
+        "asn" => item.asn.map(|asn| asn.to_string()),
+        "redirect" => {
+            let redirect = item.redirect.as_ref()?;
+            Some(format!(
+                "{} -> {} ({})",
+                redirect["source_url"].as_str()?.to_ascii_lowercase(),
+                redirect["target_url"].as_str()?,
+                redirect["status_code"].as_u64().unwrap_or(301)
+            ))
+        }
+        _ => None,
+    }
+}
+
+fn field(value: &str) -> String {
+    let value = value.trim();
+    value.strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .map(|v| v.replace("\"\"", "\""))
+        .unwrap_or_else(|| value.to_string())
+}
+
+fn parse_line(kind: &str, fields: &[String]) -> Result<ListItem, String> {
+    let value = fields[0].as_str();
+    let comment_at = if kind == "redirect" { 3 } else { 1 };
+    let comment = fields.get(comment_at).filter(|c| !c.is_empty()).cloned();
+    let mut item = ListItem { comment, ..Default::default() };
+
+    match kind {
+        "ip" => {
+            item.ip = Some(normalize_ip(value).ok_or_else(|| format!("{}: expected an IP address or CIDR (IPv4 /8-/32, IPv6 /12-/64)", value))?);
+        }
+        "hostname" => {
+            let valid = !value.is_empty()
+                && value.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '*' | '_'));
+            if !valid {
+                return Err(format!("{}: invalid hostname", value));
+            }
+            item.hostname = Some(json!({ "url_hostname": value.to_ascii_lowercase() }));
+        }
+        "asn" => {
+            let number = value.strip_prefix("AS").or_else(|| value.strip_prefix("as")).unwrap_or(value);
+            item.asn = Some(number.parse().map_err(|_| format!("{}: invalid ASN", value))?);
+        }
+        "redirect" => {
+            let target = fields.get(1).filter(|t| !t.is_empty())
+                .ok_or_else(|| format!("{}: missing target URL", value))?;
+            let status_code = match fields.get(2).filter(|s| !s.is_empty()) {
+                Some(code) => code.parse::<u64>().ok()
+                    .filter(|c| REDIRECT_STATUS_CODES.contains(c))
+                    .ok_or_else(|| format!("{}: status code must be 301, 302, 307 or 308", value))?,
+                None => 301,
+            };
+            item.redirect = Some(json!({ "source_url": value, "target_url": target, "status_code": status_code }));
+        }
+        other => return Err(format!("Unsupported list kind '{}'", other)),
+    }
+
+    Ok(item)
+}
+
+// 按逗号或制表符最多拆分为 count 列，最后一列（备注）保留其中的分隔符
+fn split_fields(line: &str, count: usize) -> Vec<String> {
+    let mut fields = Vec::with_capacity(count);
+    let mut rest = line;
+    while fields.len() + 1 < count {
+        let Some((value, remainder)) = rest.split_once([',', '\t']) else { break };
+        fields.push(field(value));
+        rest = remainder;
+    }
+    fields.push(field(rest));
+    fields
+}
+
+// 解析来源内容：每行一个条目，允许 # 注释与 CSV 表头
+fn parse_source(kind: &str, content: &str, default_comment: Option<&str>) -> (Vec<ListItem>, Vec<String>) {
+    let mut items = Vec::new();
+    let mut errors = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields = split_fields(line, if kind == "redirect" { 4 } else { 2 });
+        if items.is_empty() && errors.is_empty() && HEADER_NAMES.contains(&fields[0].to_ascii_lowercase().as_str()) {
+            continue;
+        }
+
+        match parse_line(kind, &fields) {
+            Ok(mut item) => {
+                if item.comment.is_none() {
+                    item.comment = default_comment.map(str::to_string);
+                }
+                items.push(item);
+            }
+            Err(e) => errors.push(format!("Line {}: {}", index + 1, e)),
+        }
+    }
+
+    (items, errors)
+}
+
+// 计算来源与远端 List 的差异
+async fn compute_diff(client: &CloudflareClient, request: &SyncListRequest) -> Result<(ListDiff, Vec<ListItem>), String> {
+    let list = client.get_rules_list(&request.account_id, &request.list_id).await?;
+    let existing = client.list_rules_list_items(&request.account_id, &request.list_id).await?;
+    let (source, errors) = parse_source(&list.kind, &request.content, request.comment.as_deref());
+
+    let mut wanted: HashMap<String, ListItem> = HashMap::new();
+    let mut order = Vec::new();
+    for item in source {
+        if let Some(key) = item_key(&list.kind, &item) {
+            if let Entry::Vacant(entry) = wanted.entry(key.clone()) {
+                order.push(key);
+                entry.insert(item);
+            }
+        }
+    }
+
+    let mut present = HashSet::new();
+    let mut kept = Vec::new();
+    let mut removed = Vec::new();
+    for item in existing {
+        match item_key(&list.kind, &item) {
+            Some(key) if wanted.contains_key(&key) => {
+                present.insert(key);
+                kept.push(item);
+            }
+            _ => removed.push(item),
+        }
+    }
+
+    let added: Vec<ListItem> = order.into_iter()
+        .filter(|key| !present.contains(key))
+        .filter_map(|key| wanted.remove(&key))
+        .collect();
+
+    let diff = ListDiff {
+        list_id: request.list_id.clone(),
+        kind: list.kind,
+        added,
+        removed: if request.remove_missing { removed } else { Vec::new() },
+        unchanged: kept.len(),
+        errors,
+        applied: false,
+        operation: None,
+    };
+    Ok((diff, kept))
+}
+
+pub async fn diff(client: &CloudflareClient, request: &SyncListRequest) -> Result<ListDiff, String> {
+    compute_diff(client, request).await.map(|(diff, _)| diff)
+}
+
+// 将来源同步到 List：需要删除条目时整体替换（单次原子操作），否则仅追加新增条目。
+// 默认提交后即返回，operation 中为操作 ID 与当前状态
+pub async fn sync(client: &CloudflareClient, request: &SyncListRequest) -> Result<ListDiff, String> {
+    let (mut diff, kept) = compute_diff(client, request).await?;
+
+    if !diff.errors.is_empty() {
+        return Err(format!("Source has {} invalid line(s): {}", diff.errors.len(), diff.errors.join("; ")));
+    }
+
+    let operation_id = if !diff.removed.is_empty() {
+        let items: Vec<ListItem> = kept.into_iter()
+            .map(|item| ListItem { id: None, created_on: None, modified_on: None, ..item })
+            .chain(diff.added.iter().cloned())
+            .collect();
+        client.replace_rules_list_items(&request.account_id, &request.list_id, &items).await?
+    } else if !diff.added.is_empty() {
+        client.add_rules_list_items(&request.account_id, &request.list_id, &diff.added).await?
+    } else {
+        return Ok(diff);
+    };
+
+    diff.operation = Some(finish(client, &request.account_id, &operation_id, request.wait).await?);
+    diff.applied = true;
+
+    log::info!(
+        "Synced list {}: {} added, {} removed",
+        request.list_id,
+        diff.added.len(),
+        diff.removed.len()
+    );
+
+    Ok(diff)
+}
@@ -1,10 +1,34 @@
 use actix_cors::Cors;
 use actix_web::{middleware::Logger, web, App, HttpServer, error};
 use std::env;
+use std::time::Duration;
 
+mod access_rules;
+mod audit;
+mod backup;
+mod bulk;
 mod cloudflare;
+mod custom_rules;
+mod drift;
+mod expression;
+mod firewall_events;
 mod handlers;
+mod lists;
+mod managed_rules;
 mod models;
+mod rate_limit_rules;
+mod rate_limit_simulator;
+mod rule_templates;
+mod rule_tester;
+mod settings_matrix;
+mod terraform;
+mod ua_rules;
+mod worker_deploy;
+mod worker_schedules;
+mod worker_scripts;
+mod worker_tail;
+mod worker_versions;
+mod zone_lockdowns;
 
 // 自定义 JSON 错误处理器
 fn json_error_handler(err: error::JsonPayloadError, _req: &actix_web::HttpRequest) -> error::Error {
@@ -42,7 +66,19 @@ async fn main() -> std::io::Result<()> {
     log::info!("🚀 Starting Cloudflare Manager API Server");
     log::info!("📡 Listening on: http://{}", bind_addr);
 
-    HttpServer::new(|| {
+    // 配置漂移检测 - 定时任务间隔（秒）
+    let drift_interval = env::var("DRIFT_CHECK_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(3600);
+
+    let drift_store = web::Data::new(drift::DriftStore::default());
+    actix_web::rt::spawn(drift::run_scheduler(drift_store.clone(), Duration::from_secs(drift_interval)));
+
+    let bulk_store = web::Data::new(bulk::BulkJobStore::default());
+
+    HttpServer::new(move || {
         // CORS 配置
         let cors = Cors::default()
             .allow_any_origin()
@@ -62,6 +98,8 @@ async fn main() -> std::io::Result<()> {
         App::new()
             .app_data(json_cfg)
             .app_data(payload_cfg)
+            .app_data(drift_store.clone())
+            .app_data(bulk_store.clone())
             .wrap(cors)
             .wrap(Logger::default())
             // 健康检查
@@ -79,16 +117,116 @@ async fn main() -> std::io::Result<()> {
                     .route("/firewall/rules/create", web::post().to(handlers::create_firewall_rule))
                     .route("/firewall/rules/update", web::post().to(handlers::update_firewall_rule))
                     .route("/firewall/rules/delete", web::post().to(handlers::delete_firewall_rule))
+                    .route("/firewall/expressions/validate", web::post().to(handlers::validate_expression))
+                    .route("/firewall/rules/test", web::post().to(handlers::test_rules))
+                    .route("/firewall/rules/migrate", web::post().to(handlers::migrate_firewall_rules))
+                    // WAF 自定义规则 (Rulesets) routes
+                    .route("/firewall/custom-rules", web::post().to(handlers::get_custom_rules))
+                    .route("/firewall/custom-rules/create", web::post().to(handlers::create_custom_rule))
+                    .route("/firewall/custom-rules/update", web::post().to(handlers::update_custom_rule))
+                    .route("/firewall/custom-rules/delete", web::post().to(handlers::delete_custom_rule))
+                    .route("/firewall/custom-rules/enable", web::post().to(handlers::set_custom_rule_enabled))
+                    .route("/firewall/custom-rules/reorder", web::post().to(handlers::reorder_custom_rules))
+                    // 防火墙事件 routes
+                    .route("/firewall/events", web::post().to(handlers::get_firewall_events))
+                    .route("/firewall/events/rule", web::post().to(handlers::create_rule_from_event))
+                    // 安全规则模板 routes
+                    .route("/firewall/templates", web::post().to(handlers::get_rule_templates))
+                    .route("/firewall/templates/render", web::post().to(handlers::render_rule_template))
+                    .route("/firewall/templates/apply", web::post().to(handlers::apply_rule_template))
+                    .route("/firewall/templates/apply-bulk", web::post().to(handlers::apply_rule_template_bulk))
+                    .route("/firewall/templates/usage", web::post().to(handlers::get_rule_template_usage))
+                    // IP 访问规则 routes
+                    .route("/firewall/access-rules", web::post().to(handlers::get_access_rules))
+                    .route("/firewall/access-rules/create", web::post().to(handlers::create_access_rule))
+                    .route("/firewall/access-rules/update", web::post().to(handlers::update_access_rule))
+                    .route("/firewall/access-rules/delete", web::post().to(handlers::delete_access_rule))
+                    .route("/firewall/access-rules/import", web::post().to(handlers::import_access_rules))
+                    .route("/firewall/access-rules/search", web::post().to(handlers::search_access_rules))
+                    // Zone Lockdown 与 User Agent 拦截 routes
+                    .route("/firewall/lockdowns", web::post().to(handlers::get_zone_lockdowns))
+                    .route("/firewall/lockdowns/create", web::post().to(handlers::create_zone_lockdown))
+                    .route("/firewall/lockdowns/update", web::post().to(handlers::update_zone_lockdown))
+                    .route("/firewall/lockdowns/delete", web::post().to(handlers::delete_zone_lockdown))
+                    .route("/firewall/lockdowns/migrate", web::post().to(handlers::migrate_zone_lockdowns))
+                    .route("/firewall/ua-rules", web::post().to(handlers::get_ua_rules))
+                    .route("/firewall/ua-rules/create", web::post().to(handlers::create_ua_rule))
+                    .route("/firewall/ua-rules/update", web::post().to(handlers::update_ua_rule))
+                    .route("/firewall/ua-rules/delete", web::post().to(handlers::delete_ua_rule))
+                    .route("/firewall/ua-rules/migrate", web::post().to(handlers::migrate_ua_rules))
+                    // 账户 Lists routes
+                    .route("/lists", web::post().to(handlers::get_rules_lists))
+                    .route("/lists/create", web::post().to(handlers::create_rules_list))
+                    .route("/lists/update", web::post().to(handlers::update_rules_list))
+                    .route("/lists/delete", web::post().to(handlers::delete_rules_list))
+                    .route("/lists/items", web::post().to(handlers::get_rules_list_items))
+                    .route("/lists/items/add", web::post().to(handlers::add_rules_list_items))
+                    .route("/lists/items/delete", web::post().to(handlers::delete_rules_list_items))
+                    .route("/lists/operations", web::post().to(handlers::get_rules_list_operation))
+                    // 威胁情报来源可能较大，比较与同步单独放宽 JSON 限制
+                    .service(
+                        web::resource("/lists/diff")
+                            .app_data(
+                                web::JsonConfig::default()
+                                    .limit(16 * 1024 * 1024)  // 16MB
+                                    .error_handler(json_error_handler),
+                            )
+                            .route(web::post().to(handlers::diff_rules_list)),
+                    )
+                    .service(
+                        web::resource("/lists/sync")
+                            .app_data(
+                                web::JsonConfig::default()
+                                    .limit(16 * 1024 * 1024)  // 16MB
+                                    .error_handler(json_error_handler),
+                            )
+                            .route(web::post().to(handlers::sync_rules_list)),
+                    )
                     .route("/workers/deploy", web::post().to(handlers::deploy_worker))
                     .route("/workers/list", web::post().to(handlers::list_workers))
                     .route("/workers/get", web::post().to(handlers::get_worker))
                     .route("/workers/delete", web::post().to(handlers::delete_worker))
-                    .route("/workers/upload", web::post().to(handlers::upload_worker))
+                    // 模块包可能较大，上传接口单独放宽大小限制
+                    .service(
+                        web::resource("/workers/upload")
+                            .app_data(
+                                web::JsonConfig::default()
+                                    .limit(32 * 1024 * 1024)  // 32MB
+                                    .error_handler(json_error_handler),
+                            )
+                            .route(web::post().to(handlers::upload_worker)),
+                    )
+                    .service(
+                        web::resource("/workers/upload/bundle")
+                            .app_data(web::PayloadConfig::default().limit(32 * 1024 * 1024))  // 32MB
+                            .route(web::post().to(handlers::upload_worker_bundle)),
+                    )
+                    .route("/workers/download", web::post().to(handlers::download_worker))
+                    .route("/workers/versions", web::post().to(handlers::list_worker_versions))
+                    .service(
+                        web::resource("/workers/versions/upload")
+                            .app_data(
+                                web::JsonConfig::default()
+                                    .limit(32 * 1024 * 1024)  // 32MB
+                                    .error_handler(json_error_handler),
+                            )
+                            .route(web::post().to(handlers::upload_worker_version)),
+                    )
+                    .route("/workers/deployments", web::post().to(handlers::get_worker_deployments))
+                    .route("/workers/deployments/create", web::post().to(handlers::create_worker_deployment))
+                    .route("/workers/rollback", web::post().to(handlers::rollback_worker))
+                    .route("/workers/tail", web::post().to(handlers::tail_worker))
+                    .route("/workers/schedules", web::post().to(handlers::get_worker_schedules))
+                    .route("/workers/schedules/update", web::post().to(handlers::update_worker_schedules))
+                    .route("/workers/schedules/preview", web::post().to(handlers::preview_cron))
+                    .route("/workers/settings", web::post().to(handlers::get_worker_settings))
                     .route("/workers/routes", web::post().to(handlers::get_worker_routes))
                     .route("/workers/routes/create", web::post().to(handlers::create_worker_route))
                     .route("/workers/routes/delete", web::post().to(handlers::delete_worker_route))
                     .route("/zone/settings", web::post().to(handlers::get_zone_settings))
                     .route("/zone/settings/update", web::post().to(handlers::update_zone_settings))
+                    .route("/zone/settings/compare", web::post().to(handlers::compare_zone_settings))
+                    .route("/zone/settings/align", web::post().to(handlers::align_zone_setting))
                     .route("/zone/optimize", web::post().to(handlers::optimize_zone))
                     .route("/analytics", web::post().to(handlers::get_analytics))
                     .route("/cache/purge", web::post().to(handlers::purge_cache))
@@ -104,10 +242,24 @@ async fn main() -> std::io::Result<()> {
                     .route("/waf/rules", web::post().to(handlers::get_waf_rules))
                     .route("/waf/rules/update", web::post().to(handlers::update_waf_rule))
                     .route("/waf/packages/update", web::post().to(handlers::update_waf_package))
+                    // WAF 托管规则集 routes
+                    .route("/waf/managed", web::post().to(handlers::get_managed_rules))
+                    .route("/waf/managed/catalog", web::post().to(handlers::get_managed_catalog))
+                    .route("/waf/managed/deploy", web::post().to(handlers::deploy_managed_ruleset))
+                    .route("/waf/managed/remove", web::post().to(handlers::remove_managed_ruleset))
+                    .route("/waf/managed/exceptions/create", web::post().to(handlers::create_managed_exception))
+                    .route("/waf/managed/exceptions/delete", web::post().to(handlers::delete_managed_exception))
                     .route("/ratelimits", web::post().to(handlers::get_rate_limits))
                     .route("/ratelimits/create", web::post().to(handlers::create_rate_limit))
                     .route("/ratelimits/update", web::post().to(handlers::update_rate_limit))
                     .route("/ratelimits/delete", web::post().to(handlers::delete_rate_limit))
+                    // 速率限制规则 (http_ratelimit) routes
+                    .route("/ratelimits/rules", web::post().to(handlers::get_rate_limit_rules))
+                    .route("/ratelimits/rules/create", web::post().to(handlers::create_rate_limit_rule))
+                    .route("/ratelimits/rules/update", web::post().to(handlers::update_rate_limit_rule))
+                    .route("/ratelimits/rules/delete", web::post().to(handlers::delete_rate_limit_rule))
+                    .route("/ratelimits/migrate", web::post().to(handlers::migrate_rate_limits))
+                    .route("/ratelimits/simulate", web::post().to(handlers::simulate_rate_limit))
                     // Workers KV routes
                     .route("/kv/namespaces", web::post().to(handlers::list_kv_namespaces))
                     .route("/kv/namespaces/create", web::post().to(handlers::create_kv_namespace))
@@ -121,6 +273,32 @@ async fn main() -> std::io::Result<()> {
                     .route("/d1/databases/create", web::post().to(handlers::create_d1_database))
                     .route("/d1/databases/delete", web::post().to(handlers::delete_d1_database))
                     .route("/d1/query", web::post().to(handlers::execute_d1_query))
+                    // 安全审计 routes
+                    .route("/audit/zone", web::post().to(handlers::audit_zone))
+                    .route("/audit/zones", web::post().to(handlers::audit_zones))
+                    // 批量操作 routes
+                    .route("/bulk/jobs", web::post().to(handlers::list_bulk_jobs))
+                    .route("/bulk/jobs/create", web::post().to(handlers::create_bulk_job))
+                    .route("/bulk/jobs/get", web::post().to(handlers::get_bulk_job))
+                    // 配置漂移检测 routes
+                    .route("/drift/baselines", web::post().to(handlers::list_drift_baselines))
+                    .route("/drift/baselines/set", web::post().to(handlers::set_drift_baseline))
+                    .route("/drift/baselines/delete", web::post().to(handlers::delete_drift_baseline))
+                    .route("/drift/check", web::post().to(handlers::check_drift))
+                    .route("/drift/reports", web::post().to(handlers::get_drift_reports))
+                    // Terraform 导出 routes
+                    .route("/terraform/export", web::post().to(handlers::export_terraform))
+                    // 备份与恢复 routes（归档可能较大，单独放宽 JSON 限制）
+                    .route("/backup/export", web::post().to(handlers::export_backup))
+                    .service(
+                        web::resource("/backup/restore")
+                            .app_data(
+                                web::JsonConfig::default()
+                                    .limit(64 * 1024 * 1024)  // 64MB
+                                    .error_handler(json_error_handler),
+                            )
+                            .route(web::post().to(handlers::restore_backup)),
+                    )
             )
     })
     .bind(&bind_addr)?
@@ -0,0 +1,301 @@
+use crate::cloudflare::CloudflareClient;
+use crate::expression;
+use crate::models::*;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeSet;
+
+// WAF 托管规则集所在的阶段
+pub const PHASE: &str = "http_request_firewall_managed";
+
+pub const CLOUDFLARE_MANAGED_ID: &str = "efb7b8c949ac4650a09736fc376e9aee";
+pub const OWASP_CORE_ID: &str = "4814384a9e5d4991b9815dcfc25d2f1f";
+
+// OWASP 规则集中判断异常分数是否超限的规则（949110）
+const OWASP_SCORE_RULE_ID: &str = "6179ae15870a4bb7b2d480d4843b323c";
+
+const OVERRIDE_ACTIONS: &[&str] = &["block", "challenge", "js_challenge", "managed_challenge", "log"];
+
+// 将 cloudflare / owasp 别名解析为托管规则集 ID
+pub fn resolve_ruleset(name: &str) -> &str {
+    match name {
+        "cloudflare" => CLOUDFLARE_MANAGED_ID,
+        "owasp" => OWASP_CORE_ID,
+        id => id,
+    }
+}
+
+fn ruleset_name(id: &str) -> String {
+    match id {
+        CLOUDFLARE_MANAGED_ID => "Cloudflare Managed Ruleset".to_string(),
+        OWASP_CORE_ID => "Cloudflare OWASP Core Ruleset".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn check_action(action: &Option<String>) -> Result<(), String> {
+    match action {
+        Some(action) if !OVERRIDE_ACTIONS.contains(&action.as_str()) => Err(format!(
+            "Unsupported override action '{}' (expected one of: {})",
+            action,
+            OVERRIDE_ACTIONS.join(", ")
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn executed_ruleset(rule: &RulesetRule) -> Option<&str> {
+    if rule.action != "execute" {
+        return None;
+    }
+    rule.action_parameters.as_ref()?["id"].as_str()
+}
+
+pub async fn status(client: &CloudflareClient, zone_id: &str) -> Result<ManagedRulesStatus, String> {
+    let Some(ruleset) = client.get_phase_entrypoint(zone_id, PHASE).await? else {
+        return Ok(ManagedRulesStatus { ruleset_id: None, deployments: Vec::new(), exceptions: Vec::new() });
+    };
+
+    let mut deployments = Vec::new();
+    let mut exceptions = Vec::new();
+    for rule in ruleset.rules {
+        match executed_ruleset(&rule) {
+            Some(id) => deployments.push(ManagedDeployment {
+                rule_id: rule.id.clone(),
+                ruleset_id: id.to_string(),
+                name: ruleset_name(id),
+                enabled: rule.enabled,
+                expression: rule.expression.clone(),
+                overrides: rule.action_parameters.as_ref().and_then(|p| p.get("overrides")).cloned(),
+            }),
+            None => exceptions.push(rule),
+        }
+    }
+
+    Ok(ManagedRulesStatus { ruleset_id: Some(ruleset.id), deployments, exceptions })
+}
+
+// 根据请求构建 overrides：OWASP 的偏执级别与灵敏度转换为分类与规则覆盖
+fn build_overrides(ruleset_id: &str, request: &DeployManagedRulesetRequest) -> Result<Option<Value>, String> {
+    let is_owasp = ruleset_id == OWASP_CORE_ID;
+    if !is_owasp && (request.paranoia_level.is_some() || request.sensitivity.is_some()) {
+        return Err("Paranoia level and sensitivity only apply to the OWASP Core Ruleset".to_string());
+    }
+
+    check_action(&request.action)?;
+    let mut overrides = Map::new();
+    let mut categories: Vec<CategoryOverride> = Vec::new();
+    let mut rules: Vec<ManagedRuleOverride> = Vec::new();
+
+    if is_owasp {
+        if let Some(level) = request.paranoia_level {
+            if !(1..=4).contains(&level) {
+                return Err("Paranoia level must be between 1 and 4".to_string());
+            }
+            for pl in 2..=4u8 {
+                categories.push(CategoryOverride {
+                    category: format!("paranoia-level-{}", pl),
+                    action: None,
+                    enabled: Some(pl <= level),
+                });
+            }
+        }
+
+        let score_threshold = match request.sensitivity.as_deref() {
+            Some("low") => Some(60),
+            Some("medium") => Some(40),
+            Some("high") => Some(25),
+            Some(other) => return Err(format!("Unsupported sensitivity '{}' (expected low, medium or high)", other)),
+            None => None,
+        };
+        if score_threshold.is_some() || request.action.is_some() {
+            rules.push(ManagedRuleOverride {
+                id: OWASP_SCORE_RULE_ID.to_string(),
+                action: request.action.clone(),
+                enabled: None,
+                score_threshold,
+            });
+        }
+    } else if let Some(action) = &request.action {
+        overrides.insert("action".to_string(), json!(action));
+    }
+
+    // 显式给出的分类与规则覆盖优先于上面推导出的覆盖
+    for category in &request.category_overrides {
+        check_action(&category.action)?;
+        categories.retain(|c| c.category != category.category);
+        categories.push(category.clone());
+    }
+    for rule in &request.rule_overrides {
+        check_action(&rule.action)?;
+        if rule.score_threshold.is_some() && !is_owasp {
+            return Err("score_threshold overrides only apply to the OWASP Core Ruleset".to_string());
+        }
+        rules.retain(|r| r.id != rule.id);
+        rules.push(rule.clone());
+    }
+
+    if !categories.is_empty() {
+        overrides.insert("categories".to_string(), json!(categories));
+    }
+    if !rules.is_empty() {
+        overrides.insert("rules".to_string(), json!(rules));
+    }
+
+    Ok((!overrides.is_empty()).then_some(Value::Object(overrides)))
+}
+
+// 部署托管规则集；已部署时原位更新
+pub async fn deploy(client: &CloudflareClient, request: &DeployManagedRulesetRequest) -> Result<ManagedRulesStatus, String> {
+    let ruleset_id = resolve_ruleset(&request.ruleset);
+    let expression = request.expression.clone().unwrap_or_else(|| "true".to_string());
+    if expression != "true" {
+        expression::validate(&expression)?;
+    }
+
+    let mut action_parameters = json!({ "id": ruleset_id });
+    if let Some(overrides) = build_overrides(ruleset_id, request)? {
+        action_parameters["overrides"] = overrides;
+    }
+
+    let rule = RulesetRule {
+        id: None,
+        expression,
+        action: "execute".to_string(),
+        description: Some(ruleset_name(ruleset_id)),
+        enabled: request.enabled,
+        action_parameters: Some(action_parameters),
+        ratelimit: None,
+        logging: None,
+        rule_ref: None,
+        position: None,
+    };
+
+    let zone_id = &request.zone_id;
+    match client.get_phase_entrypoint(zone_id, PHASE).await? {
+        Some(entrypoint) => {
+            let existing = entrypoint.rules.iter()
+                .find(|r| executed_ruleset(r) == Some(ruleset_id))
+                .and_then(|r| r.id.clone());
+            match existing {
+                Some(rule_id) => client.update_ruleset_rule(zone_id, &entrypoint.id, &rule_id, &rule).await?,
+                None => client.create_ruleset_rule(zone_id, &entrypoint.id, &rule).await?,
+            };
+        }
+        None => {
+            client.update_phase_entrypoint(zone_id, PHASE, &[rule]).await?;
+        }
+    }
+
+    log::info!("Deployed managed ruleset {} on zone {}", ruleset_id, zone_id);
+    status(client, zone_id).await
+}
+
+pub async fn remove(client: &CloudflareClient, request: &RemoveManagedRulesetRequest) -> Result<ManagedRulesStatus, String> {
+    let ruleset_id = resolve_ruleset(&request.ruleset);
+    let entrypoint = client.get_phase_entrypoint(&request.zone_id, PHASE).await?
+        .ok_or_else(|| "No managed rulesets are deployed on this zone".to_string())?;
+
+    let rule_id = entrypoint.rules.iter()
+        .find(|r| executed_ruleset(r) == Some(ruleset_id))
+        .and_then(|r| r.id.clone())
+        .ok_or_else(|| format!("{} is not deployed on this zone", ruleset_name(ruleset_id)))?;
+
+    client.delete_ruleset_rule(&request.zone_id, &entrypoint.id, &rule_id).await?;
+    status(client, &request.zone_id).await
+}
+
+// 添加例外；插入到第一条执行托管规则集的规则之前
+pub async fn add_exception(client: &CloudflareClient, request: &CreateManagedExceptionRequest) -> Result<ManagedRulesStatus, String> {
+    expression::validate(&request.expression)?;
+
+    let action_parameters = if !request.rules.is_empty() {
+        let rules: Map<String, Value> = request.rules.iter()
+            .map(|(ruleset, ids)| (resolve_ruleset(ruleset).to_string(), json!(ids)))
+            .collect();
+        json!({ "rules": rules })
+    } else if !request.rulesets.is_empty() {
+        let rulesets: Vec<&str> = request.rulesets.iter().map(|r| resolve_ruleset(r)).collect();
+        json!({ "rulesets": rulesets })
+    } else {
+        json!({ "ruleset": "current" })
+    };
+
+    let mut rule = RulesetRule {
+        id: None,
+        expression: request.expression.clone(),
+        action: "skip".to_string(),
+        description: request.description.clone(),
+        enabled: true,
+        action_parameters: Some(action_parameters),
+        ratelimit: None,
+        logging: None,
+        rule_ref: None,
+        position: None,
+    };
+
+    let zone_id = &request.zone_id;
+    match client.get_phase_entrypoint(zone_id, PHASE).await? {
+        Some(entrypoint) => {
+            let first_execute = entrypoint.rules.iter()
+                .find(|r| r.action == "execute")
+                .and_then(|r| r.id.clone());
+            rule.position = first_execute.map(|before| RulePosition { before: Some(before), after: None, index: None });
+            client.create_ruleset_rule(zone_id, &entrypoint.id, &rule).await?;
+        }
+        None => {
+            client.update_phase_entrypoint(zone_id, PHASE, &[rule]).await?;
+        }
+    }
+
+    status(client, zone_id).await
+}
+
+pub async fn delete_exception(client: &CloudflareClient, request: &DeleteManagedRuleRequest) -> Result<ManagedRulesStatus, String> {
+    let entrypoint = client.get_phase_entrypoint(&request.zone_id, PHASE).await?
+        .ok_or_else(|| "No managed rules are configured for this zone".to_string())?;
+
+    let rule = entrypoint.rules.iter()
+        .find(|r| r.id.as_deref() == Some(request.rule_id.as_str()))
+        .ok_or_else(|| format!("Rule {} not found", request.rule_id))?;
+    if rule.action == "execute" {
+        return Err("Rule deploys a managed ruleset; remove the ruleset instead".to_string());
+    }
+
+    client.delete_ruleset_rule(&request.zone_id, &entrypoint.id, &request.rule_id).await?;
+    status(client, &request.zone_id).await
+}
+
+// 获取托管规则集的分类与规则列表
+pub async fn catalog(client: &CloudflareClient, request: &ManagedCatalogRequest) -> Result<ManagedRulesetCatalog, String> {
+    let ruleset_id = resolve_ruleset(&request.ruleset);
+    let ruleset = client.get_ruleset(&request.zone_id, ruleset_id).await?;
+
+    let mut categories = BTreeSet::new();
+    let rules = ruleset["rules"].as_array()
+        .map(|rules| {
+            rules.iter()
+                .map(|rule| {
+                    let rule_categories: Vec<String> = rule["categories"].as_array()
+                        .map(|c| c.iter().filter_map(|c| c.as_str().map(str::to_string)).collect())
+                        .unwrap_or_default();
+                    categories.extend(rule_categories.iter().cloned());
+                    ManagedRuleInfo {
+                        id: rule["id"].as_str().unwrap_or_default().to_string(),
+                        description: rule["description"].as_str().unwrap_or_default().to_string(),
+                        action: rule["action"].as_str().unwrap_or_default().to_string(),
+                        enabled: rule["enabled"].as_bool().unwrap_or(true),
+                        categories: rule_categories,
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(ManagedRulesetCatalog {
+        ruleset_id: ruleset_id.to_string(),
+        name: ruleset["name"].as_str().map(str::to_string).unwrap_or_else(|| ruleset_name(ruleset_id)),
+        version: ruleset["version"].as_str().map(str::to_string),
+        categories: categories.into_iter().collect(),
+        rules,
+    })
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 // Cloudflare API 凭证（仅使用 API Token，更安全）
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -40,10 +41,19 @@ pub struct Zone {
     pub name: String,
     pub status: String,
     pub name_servers: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account: Option<ZoneAccount>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ZoneAccount {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
 }
 
 // DNS 记录
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DnsRecord {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<String>,
@@ -82,7 +92,7 @@ pub struct DeleteRecordRequest {
 }
 
 // 防火墙规则
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FirewallRule {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<String>,
@@ -92,19 +102,25 @@ pub struct FirewallRule {
     pub description: Option<String>,
     #[serde(default = "default_false")]
     pub paused: bool,
+    // 数值越小越先评估；未设置的规则排在最后
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i64>,
 }
 
 fn default_false() -> bool {
     false
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FirewallFilter {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<String>,
     pub expression: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    // 更新 filter 时原样带回，避免误改暂停状态
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paused: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -137,282 +153,1476 @@ pub struct UpdateFirewallRuleRequest {
     pub rule: FirewallRule,
 }
 
-// Worker 部署
+// 规则表达式校验
 #[derive(Debug, Deserialize)]
-pub struct DeployWorkerRequest {
-    #[serde(alias = "zoneId")]
-    pub zone_id: String,
-    #[serde(alias = "scriptName")]
-    pub script_name: String,
-    #[serde(alias = "targetUrl")]
-    pub target_url: String,
-    #[serde(alias = "accessDomain")]
-    pub access_domain: String,
-    #[serde(alias = "cacheTtl")]
-    pub cache_ttl: u32,
-    #[serde(alias = "cdnNode")]
-    pub cdn_node: String,
+pub struct ValidateExpressionRequest {
+    pub expression: String,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct ListWorkersRequest {
-    #[serde(alias = "accountId")]
-    pub account_id: String,
+#[derive(Debug, Serialize, Clone)]
+pub struct ExpressionError {
+    pub message: String,
+    // 字符偏移（从 0 开始）
+    pub offset: usize,
+    // 行号与列号（从 1 开始）
+    pub line: usize,
+    pub column: usize,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct Worker {
-    pub id: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub etag: Option<String>,
+#[derive(Debug, Serialize)]
+pub struct ExpressionValidation {
+    pub valid: bool,
+    // 规范化后的表达式
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub created_on: Option<String>,
+    pub canonical: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub modified_on: Option<String>,
+    pub error: Option<ExpressionError>,
+    // 未知字段等不影响有效性的提示，最终以 Cloudflare API 的校验为准
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<ExpressionError>,
 }
 
+// 规则本地测试
 #[derive(Debug, Deserialize)]
-pub struct GetWorkerRequest {
-    #[serde(alias = "accountId")]
-    pub account_id: String,
-    #[serde(alias = "scriptName")]
-    pub script_name: String,
+pub struct TestRulesRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    pub request: SampleRequest,
+    // 尚未部署的规则，排在现有自定义规则之后参与求值
+    #[serde(default, alias = "draftRules")]
+    pub draft_rules: Vec<RulesetRule>,
+    // 是否同时评估旧版防火墙规则
+    #[serde(default = "default_true", alias = "includeLegacy")]
+    pub include_legacy: bool,
+    // 命名列表（$name）的内容
+    #[serde(default)]
+    pub lists: std::collections::HashMap<String, Vec<String>>,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct DeleteWorkerRequest {
-    #[serde(alias = "accountId")]
-    pub account_id: String,
-    #[serde(alias = "scriptName")]
-    pub script_name: String,
+pub struct SampleRequest {
+    #[serde(default = "default_method")]
+    pub method: String,
+    // 完整 URL 或路径（路径时使用 Host 头或 Zone 域名）
+    pub uri: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    pub ip: String,
+    pub country: Option<String>,
+    pub asn: Option<i64>,
+    #[serde(alias = "userAgent")]
+    pub user_agent: Option<String>,
+    // 其他字段的取值，如 cf.threat_score、cf.bot_management.score
+    #[serde(default)]
+    pub fields: std::collections::HashMap<String, serde_json::Value>,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct UploadWorkerRequest {
-    #[serde(alias = "accountId")]
-    pub account_id: String,
-    #[serde(alias = "scriptName")]
-    pub script_name: String,
-    #[serde(alias = "scriptContent")]
-    pub script_content: String,
+fn default_method() -> String {
+    "GET".to_string()
 }
 
-#[derive(Debug, Deserialize)]
-pub struct GetWorkerRoutesRequest {
-    #[serde(alias = "zoneId")]
-    pub zone_id: String,
+#[derive(Debug, Serialize)]
+pub struct RuleEvaluation {
+    // custom / draft / legacy
+    pub source: String,
+    pub position: usize,
+    pub rule_id: Option<String>,
+    pub description: Option<String>,
+    pub action: String,
+    pub enabled: bool,
+    pub matched: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RuleTestResult {
+    // 第一个命中的终止性规则
+    pub matched_rule: Option<RuleEvaluation>,
+    // 最终结果动作，未命中时为 "none"
+    pub action: String,
+    pub evaluations: Vec<RuleEvaluation>,
+    // 示例请求中未提供、按默认值求值的字段
+    pub defaulted_fields: Vec<String>,
 }
 
+// Rulesets（WAF 自定义规则等阶段入口规则集）
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct WorkerRoute {
+pub struct Ruleset {
     pub id: String,
-    pub pattern: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub kind: String,
+    #[serde(default)]
+    pub phase: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub script: Option<String>,
+    pub version: Option<String>,
+    #[serde(default)]
+    pub rules: Vec<RulesetRule>,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct CreateWorkerRouteRequest {
-    #[serde(alias = "zoneId")]
-    pub zone_id: String,
-    pub pattern: String,
-    #[serde(alias = "scriptName")]
-    pub script_name: String,
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RulesetRule {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub expression: String,
+    pub action: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action_parameters: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ratelimit: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logging: Option<serde_json::Value>,
+    #[serde(default, rename = "ref", skip_serializing_if = "Option::is_none")]
+    pub rule_ref: Option<String>,
+    // 仅在创建/更新时使用，指定规则在规则集中的位置
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<RulePosition>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RulePosition {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PhaseRuleList {
+    // 入口规则集尚未创建时为 None
+    pub ruleset_id: Option<String>,
+    pub rules: Vec<RulesetRule>,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct DeleteWorkerRouteRequest {
+pub struct GetCustomRulesRequest {
     #[serde(alias = "zoneId")]
     pub zone_id: String,
-    #[serde(alias = "routeId")]
-    pub route_id: String,
 }
 
-// Zone 设置
 #[derive(Debug, Deserialize)]
-pub struct GetZoneSettingsRequest {
+pub struct CreateCustomRuleRequest {
     #[serde(alias = "zoneId")]
     pub zone_id: String,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-pub struct ZoneSetting {
-    pub id: String,
-    pub value: serde_json::Value,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    #[serde(alias = "modifiedOn")]
-    pub modified_on: Option<String>,
+    pub rule: RulesetRule,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct UpdateZoneSettingsRequest {
+pub struct UpdateCustomRuleRequest {
     #[serde(alias = "zoneId")]
     pub zone_id: String,
-    pub settings: Vec<UpdateSetting>,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-pub struct UpdateSetting {
-    pub id: String,
-    pub value: serde_json::Value,
+    #[serde(alias = "ruleId")]
+    pub rule_id: String,
+    pub rule: RulesetRule,
 }
 
-// 自动优化
 #[derive(Debug, Deserialize)]
-pub struct OptimizeZoneRequest {
+pub struct DeleteCustomRuleRequest {
     #[serde(alias = "zoneId")]
     pub zone_id: String,
-    pub mode: OptimizeMode,
+    #[serde(alias = "ruleId")]
+    pub rule_id: String,
 }
 
 #[derive(Debug, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum OptimizeMode {
-    Security,
-    Performance,
+pub struct SetCustomRuleEnabledRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    #[serde(alias = "ruleId")]
+    pub rule_id: String,
+    pub enabled: bool,
 }
 
-// Analytics 相关
 #[derive(Debug, Deserialize)]
-pub struct GetAnalyticsRequest {
+pub struct ReorderCustomRulesRequest {
     #[serde(alias = "zoneId")]
     pub zone_id: String,
-    #[serde(alias = "timeRange")]
-    pub time_range: String, // "24h", "7d", "30d"
-}
-
-#[derive(Debug, Serialize)]
-pub struct AnalyticsData {
-    pub stats: AnalyticsStats,
-    pub timeseries: Vec<TimeseriesPoint>,
-    #[serde(rename = "statusCodes")]
-    pub status_codes: Vec<StatusCodeStat>,
-    pub countries: Vec<CountryStat>,
-    pub content: Vec<ContentStat>,
-}
-
-#[derive(Debug, Serialize)]
-pub struct AnalyticsStats {
-    #[serde(rename = "totalRequests")]
-    pub total_requests: u64,
-    #[serde(rename = "cacheHitRate")]
-    pub cache_hit_rate: f64,
-    pub bandwidth: f64,
-    pub threats: u64,
-}
-
-#[derive(Debug, Serialize)]
-pub struct TimeseriesPoint {
-    pub timestamp: String,
-    pub requests: u64,
-    pub cached: u64,
-    pub uncached: u64,
-}
-
-#[derive(Debug, Serialize)]
-pub struct StatusCodeStat {
-    pub code: String,
-    pub description: String,
-    pub count: u64,
-    pub percentage: f64,
-}
-
-#[derive(Debug, Serialize)]
-pub struct CountryStat {
-    pub rank: u32,
-    pub country: String,
-    pub requests: u64,
-    pub percentage: f64,
-}
-
-#[derive(Debug, Serialize)]
-pub struct ContentStat {
-    pub rank: u32,
-    pub url: String,
-    pub requests: u64,
-    pub bandwidth: String,
+    // 新的规则顺序；未列出的规则保持原有相对顺序排在末尾
+    #[serde(alias = "ruleIds")]
+    pub rule_ids: Vec<String>,
 }
 
-// 缓存清除相关
 #[derive(Debug, Deserialize)]
-pub struct PurgeCacheRequest {
+pub struct MigrateLegacyRulesRequest {
     #[serde(alias = "zoneId")]
     pub zone_id: String,
-    #[serde(alias = "purgeEverything")]
-    pub purge_everything: Option<bool>,
-    pub files: Option<Vec<String>>,
-    pub tags: Option<Vec<String>>,
+    // 默认只预览转换结果
+    #[serde(default = "default_true", alias = "dryRun")]
+    pub dry_run: bool,
+    // 迁移成功后删除旧版规则（防火墙规则或速率限制）
+    #[serde(default, alias = "deleteLegacy")]
+    pub delete_legacy: bool,
 }
 
 #[derive(Debug, Serialize)]
-pub struct PurgeCacheResponse {
-    pub id: String,
+pub struct MigratedRule {
+    pub legacy_id: Option<String>,
+    pub rule: RulesetRule,
+    pub notes: Vec<String>,
 }
 
-// 页面规则相关
-#[derive(Debug, Deserialize)]
-pub struct GetPageRulesRequest {
-    #[serde(alias = "zoneId")]
-    pub zone_id: String,
+#[derive(Debug, Serialize)]
+pub struct RuleMigrationReport {
+    pub converted: Vec<MigratedRule>,
+    pub applied: bool,
+    pub ruleset_id: Option<String>,
+    pub legacy_deleted: Vec<String>,
+    pub errors: Vec<String>,
 }
 
+// IP 访问规则（Zone 或账户级别）
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct PageRule {
+pub struct AccessRule {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<String>,
-    pub targets: Vec<PageRuleTarget>,
-    pub actions: Vec<PageRuleAction>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub priority: Option<u32>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub status: Option<String>,
+    // block / challenge / js_challenge / managed_challenge / whitelist
+    pub mode: String,
+    pub configuration: AccessRuleConfiguration,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<AccessRuleScope>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_on: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_on: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct PageRuleTarget {
-    pub target: String,  // "url"
-    pub constraint: PageRuleConstraint,
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AccessRuleConfiguration {
+    // ip / ip6 / ip_range / asn / country
+    pub target: String,
+    pub value: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct PageRuleConstraint {
-    pub operator: String,  // "matches"
-    pub value: String,  // URL pattern
+pub struct AccessRuleScope {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(rename = "type")]
+    pub scope_type: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct PageRuleAction {
-    pub id: String,
-    pub value: serde_json::Value,
+// zone_id 与 account_id 二选一
+#[derive(Debug, Deserialize)]
+pub struct ListAccessRulesRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: Option<String>,
+    #[serde(alias = "accountId")]
+    pub account_id: Option<String>,
+    pub mode: Option<String>,
+    pub target: Option<String>,
+    pub notes: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct CreatePageRuleRequest {
+pub struct CreateAccessRuleRequest {
     #[serde(alias = "zoneId")]
-    pub zone_id: String,
-    pub rule: PageRule,
+    pub zone_id: Option<String>,
+    #[serde(alias = "accountId")]
+    pub account_id: Option<String>,
+    pub rule: AccessRule,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct UpdatePageRuleRequest {
+pub struct UpdateAccessRuleRequest {
     #[serde(alias = "zoneId")]
-    pub zone_id: String,
+    pub zone_id: Option<String>,
+    #[serde(alias = "accountId")]
+    pub account_id: Option<String>,
     #[serde(alias = "ruleId")]
     pub rule_id: String,
-    pub rule: PageRule,
+    pub mode: String,
+    #[serde(default)]
+    pub notes: String,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct DeletePageRuleRequest {
+pub struct DeleteAccessRuleRequest {
     #[serde(alias = "zoneId")]
-    pub zone_id: String,
+    pub zone_id: Option<String>,
+    #[serde(alias = "accountId")]
+    pub account_id: Option<String>,
     #[serde(alias = "ruleId")]
     pub rule_id: String,
 }
 
-// SSL 证书相关
+// 批量导入：每行一个 IP / CIDR / ASN / 国家代码，或 CSV（首列为值，第二列为备注）
 #[derive(Debug, Deserialize)]
-pub struct GetSslCertificatesRequest {
+pub struct ImportAccessRulesRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: Option<String>,
+    #[serde(alias = "accountId")]
+    pub account_id: Option<String>,
+    pub mode: String,
+    pub content: String,
+    // 行内未提供备注时使用
+    pub notes: Option<String>,
+    #[serde(default, alias = "dryRun")]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccessRuleImportReport {
+    pub parsed: Vec<AccessRule>,
+    pub created: Vec<AccessRule>,
+    // 已存在相同目标的规则
+    pub skipped: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchAccessRulesRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: Option<String>,
+    #[serde(alias = "accountId")]
+    pub account_id: Option<String>,
+    // 查找覆盖该 IP 的规则（精确匹配或 CIDR 包含）
+    pub ip: Option<String>,
+    // 按值或备注模糊匹配
+    pub query: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccessRuleMatch {
+    pub rule: AccessRule,
+    // exact / cidr / text
+    pub match_type: String,
+}
+
+// Zone Lockdown：仅允许指定 IP 访问匹配的 URL
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ZoneLockdown {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    // 如 example.com/admin*，不含协议
+    pub urls: Vec<String>,
+    // target 为 ip 或 ip_range
+    pub configurations: Vec<AccessRuleConfiguration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub paused: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_on: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_on: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetZoneLockdownsRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateZoneLockdownRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    pub lockdown: ZoneLockdown,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateZoneLockdownRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    #[serde(alias = "lockdownId")]
+    pub lockdown_id: String,
+    pub lockdown: ZoneLockdown,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteZoneLockdownRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    #[serde(alias = "lockdownId")]
+    pub lockdown_id: String,
+}
+
+// User Agent 拦截规则（完整匹配 User-Agent）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UserAgentRule {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    // block / challenge / js_challenge / managed_challenge
+    pub mode: String,
+    // target 固定为 ua
+    pub configuration: AccessRuleConfiguration,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub paused: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetUserAgentRulesRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateUserAgentRuleRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    pub rule: UserAgentRule,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateUserAgentRuleRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    #[serde(alias = "ruleId")]
+    pub rule_id: String,
+    pub rule: UserAgentRule,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteUserAgentRuleRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    #[serde(alias = "ruleId")]
+    pub rule_id: String,
+}
+
+// 账户级 List（可在规则表达式中以 $name 引用）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RulesList {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub name: String,
+    // ip / hostname / asn / redirect
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_items: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_referencing_filters: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_on: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_on: Option<String>,
+}
+
+// List 条目：按 List 类型只设置 ip / hostname / asn / redirect 之一
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ListItem {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip: Option<String>,
+    // {"url_hostname": "..."}
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asn: Option<u64>,
+    // {"source_url", "target_url", "status_code", ...}
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirect: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_on: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_on: Option<String>,
+}
+
+// List 批量操作状态：pending / running / completed / failed
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ListOperation {
+    pub id: String,
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListRulesListsRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateRulesListRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: String,
+    pub list: RulesList,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateRulesListRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: String,
+    #[serde(alias = "listId")]
+    pub list_id: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RulesListRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: String,
+    #[serde(alias = "listId")]
+    pub list_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddListItemsRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: String,
+    #[serde(alias = "listId")]
+    pub list_id: String,
+    pub items: Vec<ListItem>,
+    // 等待批量操作完成后再返回；默认立即返回操作 ID，由客户端轮询 /lists/operations
+    #[serde(default)]
+    pub wait: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteListItemsRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: String,
+    #[serde(alias = "listId")]
+    pub list_id: String,
+    #[serde(alias = "itemIds")]
+    pub item_ids: Vec<String>,
+    #[serde(default)]
+    pub wait: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetListOperationRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: String,
+    #[serde(alias = "operationId")]
+    pub operation_id: String,
+}
+
+// 以本地来源（文件内容）为准比较或同步 List
+// 纯文本每行一个值；CSV 的 ip/hostname/asn 为 值,备注，redirect 为 源URL,目标URL[,状态码][,备注]
+#[derive(Debug, Deserialize)]
+pub struct SyncListRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: String,
+    #[serde(alias = "listId")]
+    pub list_id: String,
+    pub content: String,
+    // 行内未提供备注时使用
+    pub comment: Option<String>,
+    // 删除来源中不存在的条目；为 false 时仅导入新增条目
+    #[serde(default = "default_true", alias = "removeMissing")]
+    pub remove_missing: bool,
+    // 等待批量操作完成后再返回
+    #[serde(default)]
+    pub wait: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListDiff {
+    pub list_id: String,
+    pub kind: String,
+    pub added: Vec<ListItem>,
+    pub removed: Vec<ListItem>,
+    pub unchanged: usize,
+    // 无法解析的来源行
+    pub errors: Vec<String>,
+    pub applied: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation: Option<ListOperation>,
+}
+
+// 防火墙事件查询（GraphQL firewallEventsAdaptive）
+#[derive(Debug, Deserialize)]
+pub struct FirewallEventsRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    // RFC 3339 时间；未指定时按 time_range 计算
+    pub since: Option<String>,
+    pub until: Option<String>,
+    // 1h / 6h / 24h / 7d，默认 24h
+    #[serde(alias = "timeRange")]
+    pub time_range: Option<String>,
+    pub action: Option<String>,
+    #[serde(alias = "ruleId")]
+    pub rule_id: Option<String>,
+    pub ip: Option<String>,
+    // 两位国家代码
+    pub country: Option<String>,
+    // 包含 % 时按 LIKE 匹配
+    pub path: Option<String>,
+    pub host: Option<String>,
+    pub limit: Option<u32>,
+    // 每个排行返回的条数
+    pub top: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct FirewallEvent {
+    #[serde(default)]
+    pub datetime: String,
+    #[serde(default)]
+    pub action: String,
+    #[serde(default)]
+    pub source: String,
+    #[serde(default, alias = "ruleId")]
+    pub rule_id: String,
+    #[serde(default, alias = "rayName")]
+    pub ray_name: String,
+    #[serde(default, alias = "clientIP")]
+    pub client_ip: String,
+    #[serde(default, alias = "clientAsn")]
+    pub client_asn: String,
+    #[serde(default, alias = "clientCountryName")]
+    pub country: String,
+    #[serde(default, alias = "clientRequestHTTPHost")]
+    pub host: String,
+    #[serde(default, alias = "clientRequestHTTPMethodName")]
+    pub method: String,
+    #[serde(default, alias = "clientRequestPath")]
+    pub path: String,
+    #[serde(default, alias = "clientRequestQuery")]
+    pub query: String,
+    #[serde(default, alias = "userAgent")]
+    pub user_agent: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EventCount {
+    pub value: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EventRuleCount {
+    pub rule_id: String,
+    pub source: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FirewallEventsResult {
+    pub since: String,
+    pub until: String,
+    pub events: Vec<FirewallEvent>,
+    pub top_ips: Vec<EventCount>,
+    pub top_rules: Vec<EventRuleCount>,
+    pub top_paths: Vec<EventCount>,
+}
+
+// 根据事件生成放行/阻止规则
+#[derive(Debug, Deserialize)]
+pub struct RuleFromEventRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    pub event: FirewallEvent,
+    // allow（转换为 skip 剩余自定义规则）/ block / managed_challenge 等
+    pub action: String,
+    // 参与匹配的事件字段：ip / asn / country / host / path / method / user_agent，默认仅 ip
+    #[serde(default, alias = "matchOn")]
+    pub match_on: Vec<String>,
+    // 为 false 时只返回规则草稿
+    #[serde(default)]
+    pub apply: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RuleFromEvent {
+    pub rule: RulesetRule,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<PhaseRuleList>,
+}
+
+// 安全规则模板目录
+#[derive(Debug, Serialize)]
+pub struct RuleTemplateInfo {
+    pub id: String,
+    pub version: u32,
+    pub name: String,
+    pub description: String,
+    pub category: String,
+    pub default_action: String,
+    pub parameters: Vec<TemplateParameterInfo>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TemplateParameterInfo {
+    pub name: String,
+    // ip_list / country_list / path_list / method_list / string_list / path / bool
+    pub kind: String,
+    pub required: bool,
+    pub description: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RenderTemplateRequest {
+    #[serde(alias = "templateId")]
+    pub template_id: String,
+    // 列表参数可以是字符串数组，也可以是以逗号、空白或换行分隔的字符串
+    #[serde(default)]
+    pub parameters: std::collections::HashMap<String, serde_json::Value>,
+    // 覆盖模板的默认动作
+    pub action: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RenderedTemplate {
+    pub template_id: String,
+    pub version: u32,
+    pub expression: String,
+    pub action: String,
+    // 带有 [template:id@vN] 标记，用于追踪规则来源
+    pub description: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApplyTemplateRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    #[serde(flatten)]
+    pub template: RenderTemplateRequest,
+    // 使用旧版防火墙规则而不是 WAF 自定义规则
+    #[serde(default)]
+    pub legacy: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AppliedTemplate {
+    pub rendered: RenderedTemplate,
+    pub rule_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApplyTemplateBulkRequest {
+    // 为空时作用于当前凭证下的所有 Zone
+    #[serde(default)]
+    pub targets: Vec<BulkTarget>,
+    #[serde(flatten)]
+    pub template: RenderTemplateRequest,
+    #[serde(default)]
+    pub legacy: bool,
+    pub concurrency: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TemplateUsageRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TemplateUsage {
+    pub rule_id: Option<String>,
+    // custom_rules / firewall_rules
+    pub source: String,
+    pub description: String,
+    pub template_id: String,
+    pub version: u32,
+    pub current_version: Option<u32>,
+    pub outdated: bool,
+}
+
+// Worker 部署
+#[derive(Debug, Deserialize)]
+pub struct DeployWorkerRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    #[serde(alias = "scriptName")]
+    pub script_name: String,
+    #[serde(alias = "targetUrl")]
+    pub target_url: String,
+    #[serde(alias = "accessDomain")]
+    pub access_domain: String,
+    #[serde(alias = "cacheTtl")]
+    pub cache_ttl: u32,
+    // 可选：回源时将目标主机解析到该 Zone 内的主机名（cf.resolveOverride）
+    #[serde(default, alias = "cdnNode")]
+    pub cdn_node: String,
+}
+
+// 部署流程中单个步骤的结果
+#[derive(Debug, Serialize)]
+pub struct WorkerDeployStep {
+    // upload / route / dns
+    pub step: String,
+    pub status: String, // ok, skipped, failed
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkerDeployReport {
+    pub script_name: String,
+    pub account_id: String,
+    pub route_pattern: String,
+    pub success: bool,
+    pub steps: Vec<WorkerDeployStep>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListWorkersRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Worker {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_on: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified_on: Option<String>,
+    // 脚本导出的事件处理器（fetch、scheduled 等）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub handlers: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compatibility_date: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub compatibility_flags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage_model: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetWorkerRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: String,
+    #[serde(alias = "scriptName")]
+    pub script_name: String,
+    // 为 true 时以 zip 归档下载全部模块
+    #[serde(default)]
+    pub archive: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkerDetails {
+    #[serde(flatten)]
+    pub script: Worker,
+    pub main_module: Option<String>,
+    pub modules: Vec<WorkerModule>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteWorkerRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: String,
+    #[serde(alias = "scriptName")]
+    pub script_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UploadWorkerRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: String,
+    #[serde(alias = "scriptName")]
+    pub script_name: String,
+    // 单模块脚本内容；与 modules、archive 可同时使用
+    #[serde(default, alias = "scriptContent")]
+    pub script_content: String,
+    // 多模块时必填；单模块时默认为该模块
+    #[serde(alias = "mainModule")]
+    pub main_module: Option<String>,
+    #[serde(default)]
+    pub modules: Vec<WorkerModule>,
+    // base64 编码的 tar、tar.gz 或 zip 归档，文件路径即模块名
+    pub archive: Option<String>,
+    // 纯文本环境变量（plain_text 绑定）
+    #[serde(default)]
+    pub vars: BTreeMap<String, String>,
+    // 加密环境变量（secret_text 绑定）
+    #[serde(default)]
+    pub secrets: BTreeMap<String, String>,
+    #[serde(default)]
+    pub bindings: Vec<WorkerBinding>,
+    #[serde(alias = "compatibilityDate")]
+    pub compatibility_date: Option<String>,
+    #[serde(default, alias = "compatibilityFlags")]
+    pub compatibility_flags: Vec<String>,
+    // standard / bundled / unbound
+    #[serde(alias = "usageModel")]
+    pub usage_model: Option<String>,
+    // smart 启用智能放置
+    pub placement: Option<String>,
+    // 保留脚本上已有但本次未提供的 secret
+    #[serde(default = "default_true", alias = "keepSecrets")]
+    pub keep_secrets: bool,
+}
+
+// Worker 模块（ES 模块、CommonJS、Python、wasm、文本、二进制数据或 source map）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WorkerModule {
+    pub name: String,
+    // 省略时按扩展名推断
+    #[serde(default, alias = "contentType", skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    pub content: String,
+    // base64 表示 content 为 base64 编码（wasm 等二进制模块）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DownloadWorkerRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: String,
+    #[serde(alias = "scriptName")]
+    pub script_name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkerBundle {
+    // Service Worker 格式的脚本没有主模块
+    pub main_module: Option<String>,
+    pub modules: Vec<WorkerModule>,
+}
+
+// Worker 绑定，格式与上传元数据中的 bindings 一致
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WorkerBinding {
+    PlainText { name: String, text: String },
+    SecretText { name: String, text: String },
+    KvNamespace { name: String, namespace_id: String },
+    D1 { name: String, id: String },
+    R2Bucket { name: String, bucket_name: String },
+    Queue { name: String, queue_name: String },
+    Service {
+        name: String,
+        service: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        environment: Option<String>,
+    },
+    DurableObjectNamespace {
+        name: String,
+        class_name: String,
+        // 类定义在其他脚本中时填写
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        script_name: Option<String>,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetWorkerSettingsRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: String,
+    #[serde(alias = "scriptName")]
+    pub script_name: String,
+}
+
+// 脚本当前设置；bindings 保留原始格式以包含所有绑定类型（secret 不返回内容）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WorkerSettings {
+    #[serde(default)]
+    pub bindings: Vec<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compatibility_date: Option<String>,
+    #[serde(default)]
+    pub compatibility_flags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage_model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub placement: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logpush: Option<bool>,
+}
+
+// 版本与部署上的注释（message、tag，triggered_by 由 Cloudflare 填写）
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct WorkerAnnotations {
+    #[serde(rename = "workers/message", default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(rename = "workers/tag", default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(rename = "workers/triggered_by", default, skip_serializing_if = "Option::is_none")]
+    pub triggered_by: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WorkerVersion {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub number: Option<u64>,
+    // created_on、author_email、source 等
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+    #[serde(default)]
+    pub annotations: WorkerAnnotations,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeploymentVersion {
+    #[serde(alias = "versionId")]
+    pub version_id: String,
+    pub percentage: f64,
+    // 部署历史中补充的版本号，不发送给 Cloudflare
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub number: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WorkerDeployment {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_on: Option<String>,
+    // api、wrangler、dash 等
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author_email: Option<String>,
+    #[serde(default)]
+    pub strategy: String,
+    #[serde(default)]
+    pub versions: Vec<DeploymentVersion>,
+    #[serde(default)]
+    pub annotations: WorkerAnnotations,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListWorkerVersionsRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: String,
+    #[serde(alias = "scriptName")]
+    pub script_name: String,
+}
+
+// 与 /workers/upload 相同的上传参数，另加版本注释
+#[derive(Debug, Deserialize)]
+pub struct UploadWorkerVersionRequest {
+    #[serde(flatten)]
+    pub upload: UploadWorkerRequest,
+    pub message: Option<String>,
+    pub tag: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetWorkerDeploymentsRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: String,
+    #[serde(alias = "scriptName")]
+    pub script_name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkerDeploymentHistory {
+    pub script_name: String,
+    // 当前生效的部署
+    pub active: Option<WorkerDeployment>,
+    // 从新到旧
+    pub deployments: Vec<WorkerDeployment>,
+}
+
+// 按百分比部署版本，例如 [{v1, 90}, {v2, 10}]
+#[derive(Debug, Deserialize)]
+pub struct CreateWorkerDeploymentRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: String,
+    #[serde(alias = "scriptName")]
+    pub script_name: String,
+    pub versions: Vec<DeploymentVersion>,
+    pub message: Option<String>,
+    // 覆盖通过 API 以外方式做出的变更
+    #[serde(default)]
+    pub force: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RollbackWorkerRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: String,
+    #[serde(alias = "scriptName")]
+    pub script_name: String,
+    // 省略时回滚到上一次部署中流量占比最高的版本
+    #[serde(alias = "versionId")]
+    pub version_id: Option<String>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WorkerTail {
+    pub id: String,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TailWorkerRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: String,
+    #[serde(alias = "scriptName")]
+    pub script_name: String,
+    #[serde(default)]
+    pub filters: WorkerTailFilters,
+}
+
+// 日志过滤条件，与 wrangler tail 的参数一致
+#[derive(Debug, Deserialize, Default)]
+pub struct WorkerTailFilters {
+    // ok / error / canceled
+    #[serde(default)]
+    pub status: Vec<String>,
+    #[serde(default)]
+    pub method: Vec<String>,
+    // 0 到 1 之间的采样率
+    #[serde(alias = "samplingRate")]
+    pub sampling_rate: Option<f64>,
+    #[serde(default, alias = "clientIp")]
+    pub client_ip: Vec<String>,
+    // 在 console 日志与异常信息中搜索的文本
+    pub search: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WorkerSchedule {
+    pub cron: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_on: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_on: Option<String>,
+    // 接下来的触发时间（UTC），由后端计算
+    #[serde(default, skip_deserializing)]
+    pub next_runs: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkerSchedules {
+    pub script_name: String,
+    pub schedules: Vec<WorkerSchedule>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetWorkerSchedulesRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: String,
+    #[serde(alias = "scriptName")]
+    pub script_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateWorkerSchedulesRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: String,
+    #[serde(alias = "scriptName")]
+    pub script_name: String,
+    // 完整的 cron 表达式列表，替换脚本上现有的全部触发器
+    #[serde(default)]
+    pub schedules: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PreviewCronRequest {
+    pub cron: String,
+    pub count: Option<usize>,
+    // RFC 3339 起始时间，默认为当前时间
+    pub from: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CronPreview {
+    pub cron: String,
+    pub next_runs: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetWorkerRoutesRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WorkerRoute {
+    pub id: String,
+    pub pattern: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub script: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateWorkerRouteRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    pub pattern: String,
+    #[serde(alias = "scriptName")]
+    pub script_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteWorkerRouteRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    #[serde(alias = "routeId")]
+    pub route_id: String,
+}
+
+// Zone 设置
+#[derive(Debug, Deserialize)]
+pub struct GetZoneSettingsRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ZoneSetting {
+    pub id: String,
+    pub value: serde_json::Value,
+    // 只读或受套餐限制的设置为 false
+    #[serde(default = "default_true")]
+    pub editable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(alias = "modifiedOn")]
+    pub modified_on: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateZoneSettingsRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    pub settings: Vec<UpdateSetting>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UpdateSetting {
+    pub id: String,
+    pub value: serde_json::Value,
+}
+
+// 多 Zone 设置对比
+#[derive(Debug, Deserialize)]
+pub struct CompareZoneSettingsRequest {
+    // 为空时对比账户下所有 Zone
+    #[serde(default, alias = "zoneIds")]
+    pub zone_ids: Vec<String>,
+    // 为空时对比所有设置项
+    #[serde(default, alias = "settingIds")]
+    pub setting_ids: Vec<String>,
+    pub concurrency: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MatrixZone {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SettingComparison {
+    pub id: String,
+    pub majority: serde_json::Value,
+    pub majority_count: usize,
+    pub uniform: bool,
+    // zone_id -> 设置值（Zone 不支持该设置时缺失）
+    pub values: BTreeMap<String, serde_json::Value>,
+    pub outliers: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ZoneSettingsMatrix {
+    pub zones: Vec<MatrixZone>,
+    pub settings: Vec<SettingComparison>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AlignZoneSettingRequest {
+    #[serde(alias = "settingId")]
+    pub setting_id: String,
+    // 未提供时对齐到多数值
+    pub value: Option<serde_json::Value>,
+    #[serde(default, alias = "zoneIds")]
+    pub zone_ids: Vec<String>,
+    pub concurrency: Option<usize>,
+    #[serde(default, alias = "dryRun")]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AlignZoneResult {
+    pub zone_id: String,
+    pub status: String, // updated, unchanged, skipped, failed, would_update
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AlignZoneSettingResponse {
+    pub setting_id: String,
+    pub value: serde_json::Value,
+    pub results: Vec<AlignZoneResult>,
+}
+
+// 自动优化
+#[derive(Debug, Deserialize)]
+pub struct OptimizeZoneRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    pub mode: OptimizeMode,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OptimizeMode {
+    Security,
+    Performance,
+}
+
+// Analytics 相关
+#[derive(Debug, Deserialize)]
+pub struct GetAnalyticsRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    #[serde(alias = "timeRange")]
+    pub time_range: String, // "24h", "7d", "30d"
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnalyticsData {
+    pub stats: AnalyticsStats,
+    pub timeseries: Vec<TimeseriesPoint>,
+    #[serde(rename = "statusCodes")]
+    pub status_codes: Vec<StatusCodeStat>,
+    pub countries: Vec<CountryStat>,
+    pub content: Vec<ContentStat>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnalyticsStats {
+    #[serde(rename = "totalRequests")]
+    pub total_requests: u64,
+    #[serde(rename = "cacheHitRate")]
+    pub cache_hit_rate: f64,
+    pub bandwidth: f64,
+    pub threats: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TimeseriesPoint {
+    pub timestamp: String,
+    pub requests: u64,
+    pub cached: u64,
+    pub uncached: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatusCodeStat {
+    pub code: String,
+    pub description: String,
+    pub count: u64,
+    pub percentage: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CountryStat {
+    pub rank: u32,
+    pub country: String,
+    pub requests: u64,
+    pub percentage: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ContentStat {
+    pub rank: u32,
+    pub url: String,
+    pub requests: u64,
+    pub bandwidth: String,
+}
+
+// 缓存清除相关
+#[derive(Debug, Deserialize)]
+pub struct PurgeCacheRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    #[serde(alias = "purgeEverything")]
+    pub purge_everything: Option<bool>,
+    pub files: Option<Vec<String>>,
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PurgeCacheResponse {
+    pub id: String,
+}
+
+// 页面规则相关
+#[derive(Debug, Deserialize)]
+pub struct GetPageRulesRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PageRule {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub targets: Vec<PageRuleTarget>,
+    pub actions: Vec<PageRuleAction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PageRuleTarget {
+    pub target: String,  // "url"
+    pub constraint: PageRuleConstraint,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PageRuleConstraint {
+    pub operator: String,  // "matches"
+    pub value: String,  // URL pattern
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PageRuleAction {
+    pub id: String,
+    pub value: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePageRuleRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    pub rule: PageRule,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdatePageRuleRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    #[serde(alias = "ruleId")]
+    pub rule_id: String,
+    pub rule: PageRule,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeletePageRuleRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    #[serde(alias = "ruleId")]
+    pub rule_id: String,
+}
+
+// SSL 证书相关
+#[derive(Debug, Deserialize)]
+pub struct GetSslCertificatesRequest {
     #[serde(alias = "zoneId")]
     pub zone_id: String,
 }
@@ -525,26 +1735,151 @@ pub struct WafRuleGroup {
 }
 
 #[derive(Debug, Deserialize)]
-pub struct UpdateWafRuleRequest {
+pub struct UpdateWafRuleRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    #[serde(alias = "packageId")]
+    pub package_id: String,
+    #[serde(alias = "ruleId")]
+    pub rule_id: String,
+    pub mode: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateWafPackageRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    #[serde(alias = "packageId")]
+    pub package_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sensitivity: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action_mode: Option<String>,
+}
+
+// WAF 托管规则集（http_request_firewall_managed 阶段）
+#[derive(Debug, Deserialize)]
+pub struct ManagedRulesRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CategoryOverride {
+    pub category: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ManagedRuleOverride {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score_threshold: Option<u32>,
+}
+
+// 部署（或更新）托管规则集
+#[derive(Debug, Deserialize)]
+pub struct DeployManagedRulesetRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    // cloudflare / owasp 或托管规则集 ID
+    pub ruleset: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    // 生效范围，默认 true（全部请求）
+    pub expression: Option<String>,
+    // Cloudflare 托管规则集：整体动作覆盖；OWASP：异常分数超限时的动作
+    pub action: Option<String>,
+    // OWASP 偏执级别 1-4
+    #[serde(alias = "paranoiaLevel")]
+    pub paranoia_level: Option<u8>,
+    // OWASP 灵敏度 low / medium / high（对应异常分数阈值 60 / 40 / 25）
+    pub sensitivity: Option<String>,
+    #[serde(default, alias = "categoryOverrides")]
+    pub category_overrides: Vec<CategoryOverride>,
+    #[serde(default, alias = "ruleOverrides")]
+    pub rule_overrides: Vec<ManagedRuleOverride>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RemoveManagedRulesetRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    pub ruleset: String,
+}
+
+// 例外（skip 规则），始终放在执行托管规则集的规则之前
+#[derive(Debug, Deserialize)]
+pub struct CreateManagedExceptionRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    pub expression: String,
+    pub description: Option<String>,
+    // 跳过的托管规则集（cloudflare / owasp 或 ID）；与 rules 均为空时跳过全部托管规则
+    #[serde(default)]
+    pub rulesets: Vec<String>,
+    // 按托管规则集跳过指定规则：规则集 -> 规则 ID 列表
+    #[serde(default)]
+    pub rules: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteManagedRuleRequest {
     #[serde(alias = "zoneId")]
     pub zone_id: String,
-    #[serde(alias = "packageId")]
-    pub package_id: String,
     #[serde(alias = "ruleId")]
     pub rule_id: String,
-    pub mode: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ManagedDeployment {
+    pub rule_id: Option<String>,
+    pub ruleset_id: String,
+    pub name: String,
+    pub enabled: bool,
+    pub expression: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub overrides: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ManagedRulesStatus {
+    pub ruleset_id: Option<String>,
+    pub deployments: Vec<ManagedDeployment>,
+    pub exceptions: Vec<RulesetRule>,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct UpdateWafPackageRequest {
+pub struct ManagedCatalogRequest {
     #[serde(alias = "zoneId")]
     pub zone_id: String,
-    #[serde(alias = "packageId")]
-    pub package_id: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub sensitivity: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub action_mode: Option<String>,
+    pub ruleset: String,
+}
+
+// 托管规则集内容概览，用于选择覆盖的分类与规则
+#[derive(Debug, Serialize)]
+pub struct ManagedRulesetCatalog {
+    pub ruleset_id: String,
+    pub name: String,
+    pub version: Option<String>,
+    pub categories: Vec<String>,
+    pub rules: Vec<ManagedRuleInfo>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ManagedRuleInfo {
+    pub id: String,
+    pub description: String,
+    pub action: String,
+    pub enabled: bool,
+    pub categories: Vec<String>,
 }
 
 // Rate Limiting
@@ -623,6 +1958,84 @@ pub struct DeleteRateLimitRequest {
     pub rate_limit_id: String,
 }
 
+// 速率限制规则（http_ratelimit 阶段），对应 RulesetRule.ratelimit
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RateLimitParameters {
+    // 计数维度：ip.src、http.request.headers["x-api-key"]、cf.bot_management.ja3_hash 等，cf.colo.id 会自动补充
+    pub characteristics: Vec<String>,
+    pub period: u32,
+    pub requests_per_period: u32,
+    #[serde(default)]
+    pub mitigation_timeout: u32,
+    // 仅对匹配该表达式的请求计数，默认与规则表达式相同
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub counting_expression: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_to_origin: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateRateLimitRuleRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    pub rule: RulesetRule,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateRateLimitRuleRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    #[serde(alias = "ruleId")]
+    pub rule_id: String,
+    pub rule: RulesetRule,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteRateLimitRuleRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    #[serde(alias = "ruleId")]
+    pub rule_id: String,
+}
+
+// 速率限制模拟：按历史流量估算不同阈值下会被拦截的客户端与请求
+#[derive(Debug, Deserialize)]
+pub struct SimulateRateLimitRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    // 候选规则的匹配条件（与旧版速率限制相同的 URL 模式与方法）
+    pub match_request: MatchRequest,
+    // 统计周期（秒）
+    pub period: u32,
+    // 待评估的阈值；为空时按流量分布自动选取
+    #[serde(default)]
+    pub thresholds: Vec<u32>,
+    // 回溯的小时数，默认 24
+    pub hours: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RateLimitSimulationRow {
+    pub threshold: u32,
+    pub clients_blocked: usize,
+    pub requests_blocked: u64,
+    pub clients_blocked_percent: f64,
+    pub requests_blocked_percent: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RateLimitSimulation {
+    pub since: String,
+    pub until: String,
+    pub period: u32,
+    pub total_requests: u64,
+    pub total_clients: usize,
+    // 单个客户端在一个周期内的最大请求数
+    pub peak_requests_per_period: u64,
+    pub rows: Vec<RateLimitSimulationRow>,
+    pub notes: Vec<String>,
+}
+
 // Workers KV 相关
 #[derive(Debug, Deserialize)]
 pub struct ListKVNamespacesRequest {
@@ -716,6 +2129,413 @@ pub struct ExecuteD1QueryRequest {
     pub query: String,
 }
 
+// 备份与恢复
+#[derive(Debug, Deserialize)]
+pub struct BackupExportRequest {
+    #[serde(default, alias = "zoneIds")]
+    pub zone_ids: Vec<String>,
+    // 提供时导出账户资源（Workers、KV、D1），未指定 Zone 时同时导出该账户下所有 Zone
+    #[serde(alias = "accountId")]
+    pub account_id: Option<String>,
+    #[serde(default = "default_true", alias = "includeKvValues")]
+    pub include_kv_values: bool,
+    #[serde(default = "default_true", alias = "includeD1Data")]
+    pub include_d1_data: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupArchive {
+    pub format_version: u32,
+    pub created_at: String,
+    #[serde(default)]
+    pub zones: Vec<ZoneBackup>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account: Option<AccountBackup>,
+    // 导出时读取失败的资源
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ZoneBackup {
+    pub zone_id: String,
+    pub zone_name: String,
+    #[serde(default)]
+    pub settings: Vec<ZoneSetting>,
+    #[serde(default)]
+    pub dns_records: Vec<DnsRecord>,
+    #[serde(default)]
+    pub page_rules: Vec<PageRule>,
+    #[serde(default)]
+    pub firewall_rules: Vec<FirewallRule>,
+    #[serde(default)]
+    pub waf_packages: Vec<WafPackage>,
+    #[serde(default)]
+    pub rate_limits: Vec<RateLimit>,
+    #[serde(default)]
+    pub worker_routes: Vec<WorkerRoute>,
+    // 仅元数据，私钥无法导出
+    #[serde(default)]
+    pub custom_certificates: Vec<CustomCertificate>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountBackup {
+    pub account_id: String,
+    #[serde(default)]
+    pub worker_scripts: Vec<WorkerScriptBackup>,
+    #[serde(default)]
+    pub kv_namespaces: Vec<KvNamespaceBackup>,
+    #[serde(default)]
+    pub d1_databases: Vec<D1DatabaseBackup>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WorkerScriptBackup {
+    pub name: String,
+    // 单文件脚本的源码
+    #[serde(default)]
+    pub content: String,
+    // 多模块脚本的主模块与全部模块
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub main_module: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modules: Vec<WorkerModule>,
+    // Service Worker 格式脚本的字段名；为空时 content 按 ES 模块恢复
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_part: Option<String>,
+    // 绑定（secret 不含内容）、兼容性日期与标志、用量模型；旧版归档中没有
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<WorkerSettings>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KvNamespaceBackup {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub entries: Vec<KvEntryBackup>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KvEntryBackup {
+    pub key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiration: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct D1DatabaseBackup {
+    pub uuid: String,
+    pub name: String,
+    // sqlite_master 中的建表、索引、视图、触发器语句（按依赖顺序）
+    #[serde(default)]
+    pub schema: Vec<D1SchemaObject>,
+    #[serde(default)]
+    pub tables: Vec<D1TableBackup>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct D1SchemaObject {
+    #[serde(rename = "type")]
+    pub object_type: String,
+    pub name: String,
+    pub sql: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct D1TableBackup {
+    pub name: String,
+    #[serde(default)]
+    pub rows: Vec<serde_json::Map<String, serde_json::Value>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BackupRestoreRequest {
+    pub archive: BackupArchive,
+    // 归档只包含一个 Zone 时可直接指定目标 Zone
+    #[serde(alias = "targetZoneId")]
+    pub target_zone_id: Option<String>,
+    // 源 Zone ID -> 目标 Zone ID，未映射的 Zone 恢复到原 Zone
+    #[serde(default, alias = "zoneMap")]
+    pub zone_map: std::collections::HashMap<String, String>,
+    // 账户资源恢复目标，默认使用归档中的账户
+    #[serde(alias = "targetAccountId")]
+    pub target_account_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RestoreItem {
+    pub resource: String,
+    pub detail: String,
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct RestoreReport {
+    pub applied: Vec<RestoreItem>,
+    pub skipped: Vec<RestoreItem>,
+}
+
+// Terraform 导出
+#[derive(Debug, Deserialize)]
+pub struct TerraformExportRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    // 提供时同时导出该账户下被 Zone 路由引用的 Worker 脚本
+    #[serde(alias = "accountId")]
+    pub account_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TerraformExport {
+    pub zone_id: String,
+    pub zone_name: String,
+    // 文件路径 -> 文件内容（main.tf、imports.tf、workers/*.js）
+    pub files: BTreeMap<String, String>,
+    pub resource_count: usize,
+    // 无法完整转换、需要人工检查的资源
+    pub warnings: Vec<String>,
+}
+
+// 批量 Zone 操作
+#[derive(Debug, Deserialize)]
+pub struct CreateBulkJobRequest {
+    // 为空时作用于当前凭证下的所有 Zone
+    #[serde(default)]
+    pub targets: Vec<BulkTarget>,
+    pub operation: BulkOperation,
+    pub concurrency: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BulkTarget {
+    // 其他账户的 API Token，未提供时使用请求凭证
+    #[serde(alias = "apiToken")]
+    pub api_token: Option<String>,
+    // 为空时作用于该 Token 下的所有 Zone
+    #[serde(default, alias = "zoneIds")]
+    pub zone_ids: Vec<String>,
+    pub label: Option<String>,
+}
+
+// 记录名称与内容中的 {zone} 会被替换为 Zone 名称
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BulkOperation {
+    UpdateSetting { id: String, value: serde_json::Value },
+    PurgeEverything,
+    CreateDnsRecord { record: DnsRecord },
+    CreateFirewallRule { rule: FirewallRule },
+    // 按描述匹配已有规则并启用/暂停
+    SetFirewallRulePaused { description: String, paused: bool },
+    CreateAccessRule { rule: AccessRule },
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetBulkJobRequest {
+    #[serde(alias = "jobId")]
+    pub job_id: String,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BulkJobStatus {
+    Running,
+    Completed,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BulkZoneResult {
+    pub zone_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zone_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub success: bool,
+    pub message: String,
+    pub finished_at: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BulkJob {
+    pub id: String,
+    pub description: String,
+    pub status: BulkJobStatus,
+    pub created_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+    pub total: usize,
+    pub completed: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<BulkZoneResult>,
+}
+
+// 安全审计评分
+#[derive(Debug, Deserialize)]
+pub struct AuditZoneRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    #[serde(default)]
+    pub format: AuditFormat,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditZonesRequest {
+    // 为空时审计账户下所有 Zone
+    #[serde(default, alias = "zoneIds")]
+    pub zone_ids: Vec<String>,
+    pub concurrency: Option<usize>,
+    #[serde(default)]
+    pub format: AuditFormat,
+}
+
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditFormat {
+    #[default]
+    Json,
+    Sarif,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Critical,
+    High,
+    Medium,
+    Low,
+    Info,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct AuditFinding {
+    pub rule_id: String,
+    pub severity: Severity,
+    pub title: String,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource: Option<String>,
+    pub fix: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ZoneAuditReport {
+    pub zone_id: String,
+    pub zone_name: String,
+    pub score: u32,
+    pub grade: String,
+    pub audited_at: String,
+    pub findings: Vec<AuditFinding>,
+    // 读取失败的数据源，相关规则被跳过
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+// 配置漂移检测
+#[derive(Debug, Deserialize)]
+pub struct SetDriftBaselineRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+    // 用于查询审计日志，未提供时从 Zone 信息中获取
+    #[serde(alias = "accountId")]
+    pub account_id: Option<String>,
+    #[serde(alias = "webhookUrl")]
+    pub webhook_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DriftZoneRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetDriftReportsRequest {
+    #[serde(alias = "zoneId")]
+    pub zone_id: Option<String>,
+    #[serde(default, alias = "driftedOnly")]
+    pub drifted_only: bool,
+    pub limit: Option<usize>,
+}
+
+// Zone 配置快照，每个分区以资源 ID 为键；读取失败的分区为 None，不参与比较
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ZoneConfigSnapshot {
+    pub captured_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssl_mode: Option<String>,
+    pub settings: Option<BTreeMap<String, serde_json::Value>>,
+    pub dns_records: Option<BTreeMap<String, serde_json::Value>>,
+    pub firewall_rules: Option<BTreeMap<String, serde_json::Value>>,
+    pub page_rules: Option<BTreeMap<String, serde_json::Value>>,
+    pub rate_limits: Option<BTreeMap<String, serde_json::Value>>,
+    pub worker_routes: Option<BTreeMap<String, serde_json::Value>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DriftBaselineInfo {
+    pub zone_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zone_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+    pub created_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_checked_at: Option<String>,
+    pub snapshot: ZoneConfigSnapshot,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DriftKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DriftChange {
+    pub section: String,
+    pub resource_id: String,
+    pub kind: DriftKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified_on: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changed_by: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DriftReport {
+    pub id: String,
+    pub zone_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zone_name: Option<String>,
+    pub checked_at: String,
+    pub drifted: bool,
+    pub changes: Vec<DriftChange>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
 // API 响应
 #[derive(Debug, Serialize)]
 pub struct ApiResponse<T> {
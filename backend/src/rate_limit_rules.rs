@@ -0,0 +1,337 @@
+use crate::cloudflare::CloudflareClient;
+use crate::custom_rules;
+use crate::expression;
+use crate::models::*;
+use serde_json::json;
+
+// 速率限制规则所在的阶段
+pub const PHASE: &str = "http_ratelimit";
+
+const ALLOWED_ACTIONS: &[&str] = &["block", "challenge", "js_challenge", "managed_challenge", "log"];
+
+// 质询类动作不支持缓解时长
+const CHALLENGE_ACTIONS: &[&str] = &["challenge", "js_challenge", "managed_challenge"];
+
+const PERIODS: &[u32] = &[10, 60, 120, 300, 600, 3600];
+const MITIGATION_TIMEOUTS: &[u32] = &[0, 10, 60, 120, 300, 600, 3600, 86400];
+
+// 可直接使用的计数维度
+const CHARACTERISTICS: &[&str] = &[
+    "cf.colo.id",
+    "ip.src",
+    "ip.src.asnum",
+    "ip.src.country",
+    "http.host",
+    "http.request.uri.path",
+    "cf.unique_visitor_id",
+    "cf.bot_management.ja3_hash",
+    "cf.bot_management.ja4",
+];
+
+// 需要键名的计数维度前缀
+const KEYED_CHARACTERISTICS: &[&str] = &["http.request.headers", "http.request.cookies", "http.request.uri.args"];
+
+fn to_list(ruleset: Option<Ruleset>) -> PhaseRuleList {
+    match ruleset {
+        Some(ruleset) => PhaseRuleList { ruleset_id: Some(ruleset.id), rules: ruleset.rules },
+        None => PhaseRuleList { ruleset_id: None, rules: Vec::new() },
+    }
+}
+
+fn keyed(prefix: &str, key: &str) -> Result<String, String> {
+    if key.is_empty() || key.contains('"') || key.contains('\\') {
+        return Err(format!("Invalid characteristic key '{}'", key));
+    }
+    // 请求头名称在 Rulesets 中统一为小写
+    let key = if prefix == "http.request.headers" { key.to_ascii_lowercase() } else { key.to_string() };
+    Ok(format!("{}[\"{}\"]", prefix, key))
+}
+
+// 规范化计数维度，支持 ip / asn / country / host / path / ja3 / ja4 以及 header:名称、cookie:名称、query:名称 简写
+pub fn characteristic(input: &str) -> Result<String, String> {
+    let input = input.trim();
+    let full = match input {
+        "ip" => "ip.src",
+        "asn" => "ip.src.asnum",
+        "country" => "ip.src.country",
+        "host" => "http.host",
+        "path" => "http.request.uri.path",
+        "colo" => "cf.colo.id",
+        "visitor" => "cf.unique_visitor_id",
+        "ja3" => "cf.bot_management.ja3_hash",
+        "ja4" => "cf.bot_management.ja4",
+        other => other,
+    };
+    if CHARACTERISTICS.contains(&full) {
+        return Ok(full.to_string());
+    }
+
+    if let Some((kind, key)) = input.split_once(':') {
+        let prefix = match kind {
+            "header" => "http.request.headers",
+            "cookie" => "http.request.cookies",
+            "query" => "http.request.uri.args",
+            _ => return Err(format!("Unsupported characteristic '{}'", input)),
+        };
+        return keyed(prefix, key.trim());
+    }
+
+    for prefix in KEYED_CHARACTERISTICS {
+        if let Some(key) = input.strip_prefix(prefix).and_then(|k| k.strip_prefix("[\"")).and_then(|k| k.strip_suffix("\"]")) {
+            return keyed(prefix, key);
+        }
+    }
+
+    Err(format!("Unsupported characteristic '{}'", input))
+}
+
+fn validate_expression(expression: &str) -> Result<(), String> {
+    if expression.trim() == "true" {
+        return Ok(());
+    }
+    expression::validate(expression)
+}
+
+// 校验规则并规范化 ratelimit 参数
+pub fn validate(rule: &RulesetRule) -> Result<RulesetRule, String> {
+    validate_expression(&rule.expression)?;
+    if !ALLOWED_ACTIONS.contains(&rule.action.as_str()) {
+        return Err(format!(
+            "Unsupported action '{}' for rate limiting rules (expected one of: {})",
+            rule.action,
+            ALLOWED_ACTIONS.join(", ")
+        ));
+    }
+
+    let ratelimit = rule.ratelimit.clone().ok_or("Rate limiting rules require ratelimit parameters")?;
+    let mut params: RateLimitParameters = serde_json::from_value(ratelimit)
+        .map_err(|e| format!("Invalid ratelimit parameters: {}", e))?;
+
+    let mut characteristics = Vec::new();
+    for input in &params.characteristics {
+        let normalized = characteristic(input)?;
+        if !characteristics.contains(&normalized) {
+            characteristics.push(normalized);
+        }
+    }
+    if !characteristics.iter().any(|c| c == "cf.colo.id") {
+        characteristics.insert(0, "cf.colo.id".to_string());
+    }
+    if characteristics.len() < 2 {
+        return Err("At least one characteristic besides cf.colo.id is required".to_string());
+    }
+    params.characteristics = characteristics;
+
+    if !PERIODS.contains(&params.period) {
+        return Err(format!("Period must be one of {:?} seconds", PERIODS));
+    }
+    if params.requests_per_period == 0 {
+        return Err("requests_per_period must be at least 1".to_string());
+    }
+    if !MITIGATION_TIMEOUTS.contains(&params.mitigation_timeout) {
+        return Err(format!("Mitigation timeout must be one of {:?} seconds", MITIGATION_TIMEOUTS));
+    }
+    if CHALLENGE_ACTIONS.contains(&rule.action.as_str()) && params.mitigation_timeout != 0 {
+        return Err(format!("Mitigation timeout must be 0 for the {} action", rule.action));
+    }
+
+    if let Some(counting) = &params.counting_expression {
+        if counting.trim().is_empty() {
+            params.counting_expression = None;
+        } else {
+            validate_expression(counting).map_err(|e| format!("Counting expression: {}", e))?;
+        }
+    }
+
+    let mut rule = rule.clone();
+    rule.ratelimit = Some(json!(params));
+    Ok(rule)
+}
+
+pub async fn list_rules(client: &CloudflareClient, zone_id: &str) -> Result<PhaseRuleList, String> {
+    Ok(to_list(client.get_phase_entrypoint(zone_id, PHASE).await?))
+}
+
+// 创建规则；入口规则集不存在时一并创建
+pub async fn create_rule(client: &CloudflareClient, zone_id: &str, rule: &RulesetRule) -> Result<PhaseRuleList, String> {
+    let mut rule = validate(rule)?;
+    rule.id = None;
+
+    let ruleset = match client.get_phase_entrypoint(zone_id, PHASE).await? {
+        Some(ruleset) => client.create_ruleset_rule(zone_id, &ruleset.id, &rule).await?,
+        None => {
+            rule.position = None;
+            client.update_phase_entrypoint(zone_id, PHASE, &[rule]).await?
+        }
+    };
+
+    Ok(to_list(Some(ruleset)))
+}
+
+pub async fn update_rule(
+    client: &CloudflareClient,
+    zone_id: &str,
+    rule_id: &str,
+    rule: &RulesetRule,
+) -> Result<PhaseRuleList, String> {
+    let mut rule = validate(rule)?;
+    rule.id = None;
+
+    let ruleset = client.get_phase_entrypoint(zone_id, PHASE).await?
+        .ok_or_else(|| "No rate limiting rules are configured for this zone".to_string())?;
+    let ruleset = client.update_ruleset_rule(zone_id, &ruleset.id, rule_id, &rule).await?;
+    Ok(to_list(Some(ruleset)))
+}
+
+pub async fn delete_rule(client: &CloudflareClient, zone_id: &str, rule_id: &str) -> Result<PhaseRuleList, String> {
+    let ruleset = client.get_phase_entrypoint(zone_id, PHASE).await?
+        .ok_or_else(|| "No rate limiting rules are configured for this zone".to_string())?;
+    let ruleset = client.delete_ruleset_rule(zone_id, &ruleset.id, rule_id).await?;
+    Ok(to_list(Some(ruleset)))
+}
+
+// 取不小于 value 的最小允许值，超出时取最大值
+fn round_up(value: u32, allowed: &[u32]) -> u32 {
+    allowed.iter().copied().find(|a| *a >= value).unwrap_or(allowed[allowed.len() - 1])
+}
+
+// 旧版 URL 模式（如 *example.com/api/*）转换为主机与路径条件
+pub(crate) fn url_clauses(url: &str) -> Vec<String> {
+    let url = url.trim();
+    let url = url.strip_prefix("https://").or_else(|| url.strip_prefix("http://")).unwrap_or(url);
+    let (host, path) = match url.find('/') {
+        Some(index) => (&url[..index], &url[index..]),
+        None => (url, ""),
+    };
+
+    let mut clauses = Vec::new();
+    if !host.is_empty() && host != "*" {
+        let op = if host.contains('*') { "wildcard" } else { "eq" };
+        clauses.push(format!("http.host {} {}", op, expression::quote(&host.to_ascii_lowercase())));
+    }
+    if !path.is_empty() && path != "/*" && path != "*" {
+        let stars = path.matches('*').count();
+        if stars == 0 {
+            clauses.push(format!("http.request.uri.path eq {}", expression::quote(path)));
+        } else if stars == 1 && path.ends_with('*') {
+            clauses.push(format!("starts_with(http.request.uri.path, {})", expression::quote(&path[..path.len() - 1])));
+        } else {
+            clauses.push(format!("http.request.uri.path wildcard {}", expression::quote(path)));
+        }
+    }
+    clauses
+}
+
+// 将旧版速率限制（/rate_limits）转换为 http_ratelimit 阶段的规则
+pub fn convert_legacy(rate_limit: &RateLimit) -> Result<MigratedRule, String> {
+    let mut notes = Vec::new();
+    let mut clauses = url_clauses(&rate_limit.match_request.url);
+
+    if let Some(methods) = &rate_limit.match_request.methods {
+        if !methods.is_empty() && !methods.iter().any(|m| m == "_ALL_") {
+            let set: Vec<String> = methods.iter().map(|m| expression::quote(&m.to_ascii_uppercase())).collect();
+            clauses.push(format!("http.request.method in {{{}}}", set.join(" ")));
+        }
+    }
+    if let Some(schemes) = &rate_limit.match_request.schemes {
+        if schemes.len() == 1 {
+            match schemes[0].to_ascii_uppercase().as_str() {
+                "HTTPS" => clauses.push("ssl".to_string()),
+                "HTTP" => clauses.push("not ssl".to_string()),
+                _ => {}
+            }
+        }
+    }
+
+    let expression = if clauses.is_empty() { "true".to_string() } else { clauses.join(" and ") };
+
+    let (action, action_parameters) = match rate_limit.action.mode.as_str() {
+        "ban" => ("block".to_string(), None),
+        "simulate" => ("log".to_string(), None),
+        "challenge" | "js_challenge" | "managed_challenge" => (rate_limit.action.mode.clone(), None),
+        other => return Err(format!("Action '{}' has no rate limiting rule equivalent", other)),
+    };
+
+    let action_parameters = match (&rate_limit.action.response, action.as_str()) {
+        (Some(response), "block") => Some(json!({
+            "response": {
+                "status_code": 429,
+                "content_type": response.content_type,
+                "content": response.body
+            }
+        })),
+        (Some(_), _) => {
+            notes.push("Custom response body only applies to block; it was dropped".to_string());
+            action_parameters
+        }
+        (None, _) => action_parameters,
+    };
+
+    let period = round_up(rate_limit.period, PERIODS);
+    let mut requests_per_period = rate_limit.threshold.max(1);
+    if period != rate_limit.period {
+        // 按新的统计周期等比例换算阈值
+        requests_per_period = ((rate_limit.threshold as u64 * period as u64).div_ceil(rate_limit.period.max(1) as u64)).max(1) as u32;
+        notes.push(format!(
+            "Period {}s is not supported; using {}s with {} requests (was {})",
+            rate_limit.period, period, requests_per_period, rate_limit.threshold
+        ));
+    }
+
+    let mitigation_timeout = if CHALLENGE_ACTIONS.contains(&action.as_str()) {
+        0
+    } else {
+        let timeout = rate_limit.action.timeout.unwrap_or(period);
+        let rounded = round_up(timeout, MITIGATION_TIMEOUTS);
+        if rounded != timeout {
+            notes.push(format!("Timeout {}s rounded to {}s", timeout, rounded));
+        }
+        rounded
+    };
+
+    if action == "log" {
+        notes.push("Simulate mode converted to log".to_string());
+    }
+    if rate_limit.disabled {
+        notes.push("Legacy rate limit was disabled; converted rule is disabled".to_string());
+    }
+
+    let rule = RulesetRule {
+        id: None,
+        expression,
+        action,
+        description: Some(rate_limit.description.clone()).filter(|d| !d.is_empty()),
+        enabled: !rate_limit.disabled,
+        action_parameters,
+        ratelimit: Some(json!(RateLimitParameters {
+            characteristics: vec!["cf.colo.id".to_string(), "ip.src".to_string()],
+            period,
+            requests_per_period,
+            mitigation_timeout,
+            counting_expression: None,
+            requests_to_origin: None,
+        })),
+        logging: None,
+        rule_ref: Some(rate_limit.id.clone()),
+        position: None,
+    };
+
+    // 校验失败的转换结果不能应用，交由调用方记入错误
+    let rule = validate(&rule)?;
+
+    Ok(MigratedRule { legacy_id: Some(rate_limit.id.clone()), rule, notes })
+}
+
+// 迁移 Zone 的全部旧版速率限制；已按 ref 迁移过的规则不会重复添加
+pub async fn migrate(client: &CloudflareClient, request: &MigrateLegacyRulesRequest) -> Result<RuleMigrationReport, String> {
+    let zone_id = request.zone_id.as_str();
+    custom_rules::migrate_legacy(
+        client,
+        request,
+        PHASE,
+        "rate limit",
+        client.get_rate_limits(zone_id),
+        |item| convert_legacy(item).map_err(|e| format!("{}: {}", item.id, e)),
+        move |legacy_id| async move { client.delete_rate_limit(zone_id, &legacy_id).await },
+    ).await
+}
@@ -0,0 +1,249 @@
+use crate::cloudflare::CloudflareClient;
+use crate::models::*;
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+// GraphQL 单次查询返回的最大分组数
+const MAX_GROUPS: u64 = 10_000;
+
+// 按时间分段查询，每段的（IP × 时间粒度）分组数需小于 MAX_GROUPS；
+// 分段仍被截断时对半拆分，最多发起 MAX_QUERIES 次查询
+const SLICE_MINUTES: i64 = 60;
+const MAX_QUERIES: usize = 200;
+
+const DEFAULT_HOURS: u32 = 24;
+const MAX_HOURS: u32 = 24;
+
+// 自动选取阈值时使用的分位数
+const PERCENTILES: &[f64] = &[0.5, 0.9, 0.95, 0.99, 0.999];
+
+// 可用的时间粒度维度及其秒数，从粗到细
+const BUCKETS: &[(&str, u32)] = &[
+    ("datetimeHour", 3600),
+    ("datetimeFifteenMinutes", 900),
+    ("datetimeFiveMinutes", 300),
+    ("datetimeMinute", 60),
+];
+
+// 选择不大于周期且能整除周期的最大粒度；周期小于一分钟时使用分钟粒度
+fn bucket_for(period: u32) -> (&'static str, u32) {
+    BUCKETS.iter()
+        .copied()
+        .find(|(_, seconds)| *seconds <= period && period.is_multiple_of(*seconds))
+        .unwrap_or(("datetimeMinute", 60))
+}
+
+fn like_pattern(pattern: &str) -> String {
+    pattern.replace('%', "\\%").replace('*', "%")
+}
+
+// 将旧版 URL 模式转换为 GraphQL 过滤条件
+fn build_filter(request: &SimulateRateLimitRequest, since: &DateTime<Utc>, until: &DateTime<Utc>) -> Value {
+    let mut filter = Map::new();
+    filter.insert("datetime_geq".to_string(), json!(since.to_rfc3339_opts(SecondsFormat::Secs, true)));
+    filter.insert("datetime_lt".to_string(), json!(until.to_rfc3339_opts(SecondsFormat::Secs, true)));
+    filter.insert("requestSource".to_string(), json!("eyeball"));
+
+    let url = request.match_request.url.trim();
+    let url = url.strip_prefix("https://").or_else(|| url.strip_prefix("http://")).unwrap_or(url);
+    let url = url.split('?').next().unwrap_or_default();
+    let (host, path) = match url.find('/') {
+        Some(index) => (&url[..index], &url[index..]),
+        None => (url, ""),
+    };
+
+    if !host.is_empty() && host != "*" {
+        let host = host.to_ascii_lowercase();
+        if host.contains('*') {
+            filter.insert("clientRequestHTTPHost_like".to_string(), json!(like_pattern(&host)));
+        } else {
+            filter.insert("clientRequestHTTPHost".to_string(), json!(host));
+        }
+    }
+    if !path.is_empty() && path != "/*" {
+        if path.contains('*') {
+            filter.insert("clientRequestPath_like".to_string(), json!(like_pattern(path)));
+        } else {
+            filter.insert("clientRequestPath".to_string(), json!(path));
+        }
+    }
+
+    if let Some(methods) = &request.match_request.methods {
+        if !methods.is_empty() && !methods.iter().any(|m| m == "_ALL_") {
+            let methods: Vec<String> = methods.iter().map(|m| m.to_ascii_uppercase()).collect();
+            filter.insert("clientRequestHTTPMethodName_in".to_string(), json!(methods));
+        }
+    }
+
+    Value::Object(filter)
+}
+
+fn percentile(sorted: &[u64], p: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let index = ((sorted.len() - 1) as f64 * p).round() as usize;
+    sorted[index.min(sorted.len() - 1)]
+}
+
+fn percent(part: f64, total: f64) -> f64 {
+    if total > 0.0 {
+        (part / total * 10_000.0).round() / 100.0
+    } else {
+        0.0
+    }
+}
+
+// 分段拉取各客户端 IP 按时间粒度的请求数，按周期聚合后估算各阈值的拦截量
+pub async fn simulate(client: &CloudflareClient, request: &SimulateRateLimitRequest) -> Result<RateLimitSimulation, String> {
+    if request.period == 0 {
+        return Err("Period must be greater than 0".to_string());
+    }
+
+    let hours = request.hours.unwrap_or(DEFAULT_HOURS).clamp(1, MAX_HOURS);
+    let until = Utc::now();
+    let since = until - Duration::hours(hours as i64);
+    let (dimension, bucket_seconds) = bucket_for(request.period);
+
+    let query = format!(
+        r#"
+query RateLimitSimulation($zoneTag: string, $filter: ZoneHttpRequestsAdaptiveGroupsFilter_InputObject, $limit: uint64!) {{
+    viewer {{
+        zones(filter: {{ zoneTag: $zoneTag }}) {{
+            series: httpRequestsAdaptiveGroups(filter: $filter, limit: $limit, orderBy: [count_DESC]) {{
+                count
+                dimensions {{ clientIP {} }}
+            }}
+        }}
+    }}
+}}
+"#,
+        dimension
+    );
+
+    let mut slices = VecDeque::new();
+    let mut start = since;
+    while start < until {
+        let end = (start + Duration::minutes(SLICE_MINUTES)).min(until);
+        slices.push_back((start, end));
+        start = end;
+    }
+
+    let mut groups = Vec::new();
+    let mut queries = 0;
+    while let Some((start, end)) = slices.pop_front() {
+        queries += 1;
+        if queries > MAX_QUERIES {
+            return Err(format!(
+                "Traffic is too large to simulate within {} queries; use a shorter window or a narrower URL pattern",
+                MAX_QUERIES
+            ));
+        }
+
+        let variables = json!({
+            "zoneTag": request.zone_id,
+            "filter": build_filter(request, &start, &end),
+            "limit": MAX_GROUPS,
+        });
+        let zone = client.query_zone_analytics(&query, variables).await?;
+        let series = zone["series"].as_array().cloned().unwrap_or_default();
+
+        // 结果被截断时拆分时间段重新查询；单个粒度内仍被截断则无法得到准确结果
+        if series.len() as u64 >= MAX_GROUPS {
+            if (end - start).num_seconds() <= bucket_seconds as i64 {
+                return Err(format!(
+                    "More than {} clients sent matching requests between {} and {}; use a narrower URL pattern",
+                    MAX_GROUPS,
+                    start.to_rfc3339_opts(SecondsFormat::Secs, true),
+                    end.to_rfc3339_opts(SecondsFormat::Secs, true)
+                ));
+            }
+            let middle = start + (end - start) / 2;
+            slices.push_front((middle, end));
+            slices.push_front((start, middle));
+            continue;
+        }
+        groups.extend(series);
+    }
+
+    let mut notes = vec!["Counts are estimated from sampled analytics data".to_string()];
+
+    // 周期小于粒度时按均匀分布折算，周期大于粒度时合并相邻粒度
+    let scale = if request.period < bucket_seconds {
+        notes.push(format!(
+            "Period {}s is shorter than the finest analytics interval ({}s); counts assume evenly spread requests",
+            request.period, bucket_seconds
+        ));
+        request.period as f64 / bucket_seconds as f64
+    } else {
+        1.0
+    };
+
+    let mut windows: HashMap<(String, i64), u64> = HashMap::new();
+    let mut total_requests = 0;
+    for group in &groups {
+        let count = group["count"].as_u64().unwrap_or(0);
+        let ip = group["dimensions"]["clientIP"].as_str().unwrap_or_default().to_string();
+        let timestamp = group["dimensions"][dimension].as_str()
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| t.timestamp())
+            .unwrap_or_default();
+
+        total_requests += count;
+        let window = timestamp.div_euclid(request.period.max(bucket_seconds) as i64);
+        *windows.entry((ip, window)).or_default() += count;
+    }
+
+    let per_period: Vec<(String, u64)> = windows.into_iter()
+        .map(|((ip, _), count)| (ip, (count as f64 * scale).ceil() as u64))
+        .collect();
+
+    let total_clients = per_period.iter().map(|(ip, _)| ip.as_str()).collect::<HashSet<_>>().len();
+    let mut sorted: Vec<u64> = per_period.iter().map(|(_, count)| *count).collect();
+    sorted.sort_unstable();
+    let peak = sorted.last().copied().unwrap_or(0);
+
+    let mut thresholds: Vec<u32> = if request.thresholds.is_empty() {
+        PERCENTILES.iter()
+            .map(|p| percentile(&sorted, *p).max(1) as u32)
+            .collect()
+    } else {
+        request.thresholds.iter().map(|t| (*t).max(1)).collect()
+    };
+    thresholds.sort_unstable();
+    thresholds.dedup();
+
+    let rows = thresholds.into_iter()
+        .map(|threshold| {
+            let mut clients = HashSet::new();
+            let mut blocked = 0u64;
+            for (ip, count) in &per_period {
+                if *count > threshold as u64 {
+                    clients.insert(ip.as_str());
+                    blocked += count - threshold as u64;
+                }
+            }
+            // 折算后的拦截请求数换算回实际请求量
+            let blocked = (blocked as f64 / scale).round() as u64;
+            RateLimitSimulationRow {
+                threshold,
+                clients_blocked: clients.len(),
+                requests_blocked: blocked,
+                clients_blocked_percent: percent(clients.len() as f64, total_clients as f64),
+                requests_blocked_percent: percent(blocked as f64, total_requests as f64),
+            }
+        })
+        .collect();
+
+    Ok(RateLimitSimulation {
+        since: since.to_rfc3339_opts(SecondsFormat::Secs, true),
+        until: until.to_rfc3339_opts(SecondsFormat::Secs, true),
+        period: request.period,
+        total_requests,
+        total_clients,
+        peak_requests_per_period: peak,
+        rows,
+        notes,
+    })
+}
@@ -0,0 +1,438 @@
+use crate::bulk::{self, BulkJobStore};
+use crate::cloudflare::{concurrency_limit, CloudflareClient};
+use crate::custom_rules;
+use crate::expression;
+use crate::models::*;
+use actix_web::web;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// 模板可用的动作（自定义规则与旧版防火墙规则通用）
+const ALLOWED_ACTIONS: &[&str] = &["block", "challenge", "js_challenge", "managed_challenge", "log"];
+
+// 常见扫描器与恶意爬虫的 UA 片段（小写）
+const BAD_BOT_PATTERNS: &[&str] = &[
+    "sqlmap", "nikto", "nmap", "masscan", "zgrab", "dirbuster", "gobuster", "wpscan", "nuclei", "httrack",
+];
+
+const DEFAULT_API_METHODS: &[&str] = &["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"];
+
+struct Param {
+    name: &'static str,
+    kind: &'static str,
+    required: bool,
+    description: &'static str,
+}
+
+struct Template {
+    id: &'static str,
+    // 模板表达式或默认值变化时递增
+    version: u32,
+    name: &'static str,
+    description: &'static str,
+    category: &'static str,
+    action: &'static str,
+    params: &'static [Param],
+}
+
+const TEMPLATES: &[Template] = &[
+    Template {
+        id: "wordpress-hardening",
+        version: 1,
+        name: "WordPress hardening",
+        description: "Block xmlrpc.php, wp-config probes and PHP execution in uploads; optionally restrict wp-admin and wp-login.php to an IP list",
+        category: "cms",
+        action: "block",
+        params: &[
+            Param { name: "admin_ips", kind: "ip_list", required: false, description: "IPs or CIDRs allowed to reach wp-admin and wp-login.php" },
+        ],
+    },
+    Template {
+        id: "admin-paths-ip-allowlist",
+        version: 1,
+        name: "Admin paths outside IP list",
+        description: "Block requests to admin paths unless they come from an allowed IP list",
+        category: "access",
+        action: "block",
+        params: &[
+            Param { name: "paths", kind: "path_list", required: false, description: "Path prefixes to protect (default /admin)" },
+            Param { name: "allowed_ips", kind: "ip_list", required: true, description: "IPs or CIDRs allowed to reach the admin paths" },
+        ],
+    },
+    Template {
+        id: "geo-block",
+        version: 1,
+        name: "Geo-block",
+        description: "Block the listed countries, or everything except the listed countries",
+        category: "access",
+        action: "block",
+        params: &[
+            Param { name: "countries", kind: "country_list", required: true, description: "ISO 3166-1 alpha-2 country codes" },
+            Param { name: "allow_only", kind: "bool", required: false, description: "Treat the list as the only allowed countries (default false)" },
+        ],
+    },
+    Template {
+        id: "bad-bot-user-agents",
+        version: 1,
+        name: "Bad bot user agents",
+        description: "Block common scanner and scraper user agents and empty user agents, sparing verified bots",
+        category: "bots",
+        action: "block",
+        params: &[
+            Param { name: "extra_patterns", kind: "string_list", required: false, description: "Additional user agent substrings (case-insensitive)" },
+            Param { name: "block_empty", kind: "bool", required: false, description: "Also match requests without a user agent (default true)" },
+            Param { name: "allow_verified_bots", kind: "bool", required: false, description: "Never match verified bots (default true)" },
+        ],
+    },
+    Template {
+        id: "api-method-enforcement",
+        version: 1,
+        name: "API method enforcement",
+        description: "Block API requests that use HTTP methods outside an allowed list",
+        category: "api",
+        action: "block",
+        params: &[
+            Param { name: "path_prefix", kind: "path", required: false, description: "API path prefix (default /api/)" },
+            Param { name: "methods", kind: "method_list", required: false, description: "Allowed methods (default GET, POST, PUT, PATCH, DELETE, OPTIONS)" },
+        ],
+    },
+    Template {
+        id: "block-ip-list",
+        version: 1,
+        name: "Block IP list",
+        description: "Block the listed IP addresses and CIDR ranges",
+        category: "access",
+        action: "block",
+        params: &[
+            Param { name: "ips", kind: "ip_list", required: true, description: "IPs or CIDRs to block" },
+        ],
+    },
+];
+
+fn find(id: &str) -> Result<&'static Template, String> {
+    TEMPLATES.iter()
+        .find(|t| t.id == id)
+        .ok_or_else(|| format!("Unknown template '{}'", id))
+}
+
+pub fn catalogue() -> Vec<RuleTemplateInfo> {
+    TEMPLATES.iter()
+        .map(|t| RuleTemplateInfo {
+            id: t.id.to_string(),
+            version: t.version,
+            name: t.name.to_string(),
+            description: t.description.to_string(),
+            category: t.category.to_string(),
+            default_action: t.action.to_string(),
+            parameters: t.params.iter()
+                .map(|p| TemplateParameterInfo {
+                    name: p.name.to_string(),
+                    kind: p.kind.to_string(),
+                    required: p.required,
+                    description: p.description.to_string(),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+// 模板参数读取与校验
+struct Params<'a> {
+    template: &'static Template,
+    values: &'a HashMap<String, Value>,
+}
+
+impl Params<'_> {
+    fn raw_list(&self, name: &str) -> Vec<String> {
+        match self.values.get(name) {
+            Some(Value::Array(items)) => items.iter()
+                .filter_map(|v| match v {
+                    Value::String(s) => Some(s.trim().to_string()),
+                    Value::Number(n) => Some(n.to_string()),
+                    _ => None,
+                })
+                .filter(|s| !s.is_empty())
+                .collect(),
+            Some(Value::String(s)) => s.split(|c: char| c == ',' || c.is_whitespace())
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn list(&self, name: &str) -> Result<Vec<String>, String> {
+        let values = self.raw_list(name);
+        let required = self.template.params.iter().any(|p| p.name == name && p.required);
+        if required && values.is_empty() {
+            return Err(format!("Parameter '{}' is required", name));
+        }
+
+        let kind = self.template.params.iter().find(|p| p.name == name).map(|p| p.kind).unwrap_or("string_list");
+        values.into_iter()
+            .map(|v| match kind {
+                "ip_list" => expression::parse_ip(&v)
+                    .map(|_| v.clone())
+                    .ok_or_else(|| format!("{}: '{}' is not an IP address or CIDR", name, v)),
+                "country_list" if v.len() == 2 && v.chars().all(|c| c.is_ascii_alphabetic()) => Ok(v.to_ascii_uppercase()),
+                "country_list" => Err(format!("{}: '{}' is not a two-letter country code", name, v)),
+                "method_list" if v.chars().all(|c| c.is_ascii_alphabetic()) => Ok(v.to_ascii_uppercase()),
+                "method_list" => Err(format!("{}: '{}' is not an HTTP method", name, v)),
+                "path_list" if v.starts_with('/') => Ok(v),
+                "path_list" => Err(format!("{}: '{}' must start with /", name, v)),
+                _ => Ok(v),
+            })
+            .collect()
+    }
+
+    fn flag(&self, name: &str, default: bool) -> Result<bool, String> {
+        match self.values.get(name) {
+            None | Some(Value::Null) => Ok(default),
+            Some(Value::Bool(b)) => Ok(*b),
+            Some(other) => Err(format!("Parameter '{}' must be a boolean, got {}", name, other)),
+        }
+    }
+
+    fn text(&self, name: &str, default: &str) -> Result<String, String> {
+        match self.values.get(name) {
+            None | Some(Value::Null) => Ok(default.to_string()),
+            Some(Value::String(s)) if !s.trim().is_empty() => Ok(s.trim().to_string()),
+            Some(other) => Err(format!("Parameter '{}' must be a non-empty string, got {}", name, other)),
+        }
+    }
+}
+
+fn quoted_set(values: &[String]) -> String {
+    let items: Vec<String> = values.iter().map(|v| expression::quote(v)).collect();
+    format!("{{{}}}", items.join(" "))
+}
+
+fn ip_set(values: &[String]) -> String {
+    format!("{{{}}}", values.join(" "))
+}
+
+fn render_expression(template: &'static Template, params: &Params) -> Result<String, String> {
+    let expression = match template.id {
+        "wordpress-hardening" => {
+            let mut clauses = vec![
+                "http.request.uri.path eq \"/xmlrpc.php\"".to_string(),
+                "http.request.uri.path contains \"wp-config\"".to_string(),
+                "(starts_with(http.request.uri.path, \"/wp-content/uploads/\") and http.request.uri.path.extension eq \"php\")".to_string(),
+            ];
+            let admin_ips = params.list("admin_ips")?;
+            if !admin_ips.is_empty() {
+                clauses.push(format!(
+                    "((starts_with(http.request.uri.path, \"/wp-admin\") or http.request.uri.path eq \"/wp-login.php\") and http.request.uri.path ne \"/wp-admin/admin-ajax.php\" and not ip.src in {})",
+                    ip_set(&admin_ips)
+                ));
+            }
+            clauses.join(" or ")
+        }
+        "admin-paths-ip-allowlist" => {
+            let mut paths = params.list("paths")?;
+            if paths.is_empty() {
+                paths.push("/admin".to_string());
+            }
+            let allowed = params.list("allowed_ips")?;
+            let prefixes: Vec<String> = paths.iter()
+                .map(|p| format!("starts_with(http.request.uri.path, {})", expression::quote(p)))
+                .collect();
+            format!("({}) and not ip.src in {}", prefixes.join(" or "), ip_set(&allowed))
+        }
+        "geo-block" => {
+            let countries = params.list("countries")?;
+            let set = quoted_set(&countries);
+            if params.flag("allow_only", false)? {
+                format!("not ip.src.country in {}", set)
+            } else {
+                format!("ip.src.country in {}", set)
+            }
+        }
+        "bad-bot-user-agents" => {
+            let mut patterns: Vec<String> = BAD_BOT_PATTERNS.iter().map(|p| p.to_string()).collect();
+            for extra in params.list("extra_patterns")? {
+                let extra = extra.to_lowercase();
+                if !patterns.contains(&extra) {
+                    patterns.push(extra);
+                }
+            }
+            let mut clauses: Vec<String> = patterns.iter()
+                .map(|p| format!("lower(http.user_agent) contains {}", expression::quote(p)))
+                .collect();
+            if params.flag("block_empty", true)? {
+                clauses.push("http.user_agent eq \"\"".to_string());
+            }
+            let matched = format!("({})", clauses.join(" or "));
+            if params.flag("allow_verified_bots", true)? {
+                format!("{} and not cf.client.bot", matched)
+            } else {
+                matched
+            }
+        }
+        "api-method-enforcement" => {
+            let prefix = params.text("path_prefix", "/api/")?;
+            if !prefix.starts_with('/') {
+                return Err("path_prefix must start with /".to_string());
+            }
+            let mut methods = params.list("methods")?;
+            if methods.is_empty() {
+                methods = DEFAULT_API_METHODS.iter().map(|m| m.to_string()).collect();
+            }
+            format!(
+                "starts_with(http.request.uri.path, {}) and not http.request.method in {}",
+                expression::quote(&prefix),
+                quoted_set(&methods)
+            )
+        }
+        "block-ip-list" => format!("ip.src in {}", ip_set(&params.list("ips")?)),
+        other => return Err(format!("Template '{}' has no renderer", other)),
+    };
+
+    expression::validate(&expression)?;
+    Ok(expression)
+}
+
+// 渲染模板为经过校验的表达式与动作
+pub fn render(request: &RenderTemplateRequest) -> Result<RenderedTemplate, String> {
+    let template = find(&request.template_id)?;
+
+    if let Some(unknown) = request.parameters.keys().find(|k| !template.params.iter().any(|p| p.name == k.as_str())) {
+        return Err(format!("Template '{}' has no parameter '{}'", template.id, unknown));
+    }
+
+    let action = request.action.clone().unwrap_or_else(|| template.action.to_string());
+    if !ALLOWED_ACTIONS.contains(&action.as_str()) {
+        return Err(format!("Unsupported action '{}' (expected one of: {})", action, ALLOWED_ACTIONS.join(", ")));
+    }
+
+    let params = Params { template, values: &request.parameters };
+    let expression = render_expression(template, &params)?;
+
+    Ok(RenderedTemplate {
+        template_id: template.id.to_string(),
+        version: template.version,
+        expression,
+        action,
+        description: format!("{} [template:{}@v{}]", template.name, template.id, template.version),
+    })
+}
+
+async fn apply_rendered(client: &CloudflareClient, zone_id: &str, rendered: &RenderedTemplate, legacy: bool) -> Result<Option<String>, String> {
+    if legacy {
+        let rule = FirewallRule {
+            id: None,
+            filter: FirewallFilter {
+                id: None,
+                expression: rendered.expression.clone(),
+                description: Some(rendered.description.clone()),
+                paused: None,
+            },
+            action: rendered.action.clone(),
+            description: Some(rendered.description.clone()),
+            paused: false,
+            priority: None,
+        };
+        return client.create_firewall_rule(zone_id, &rule).await.map(|r| r.id);
+    }
+
+    let rule = RulesetRule {
+        id: None,
+        expression: rendered.expression.clone(),
+        action: rendered.action.clone(),
+        description: Some(rendered.description.clone()),
+        enabled: true,
+        action_parameters: None,
+        ratelimit: None,
+        logging: None,
+        rule_ref: None,
+        position: None,
+    };
+    let list = custom_rules::create_rule(client, zone_id, &rule).await?;
+    // 新规则追加在入口规则集末尾
+    Ok(list.rules.last().and_then(|r| r.id.clone()))
+}
+
+pub async fn apply(client: &CloudflareClient, request: &ApplyTemplateRequest) -> Result<AppliedTemplate, String> {
+    let rendered = render(&request.template)?;
+    let rule_id = apply_rendered(client, &request.zone_id, &rendered, request.legacy).await?;
+    log::info!("Applied template {}@v{} to zone {}", rendered.template_id, rendered.version, request.zone_id);
+    Ok(AppliedTemplate { rendered, rule_id })
+}
+
+// 将模板应用到多个 Zone（后台批量任务）
+pub async fn apply_bulk(
+    store: &web::Data<BulkJobStore>,
+    credentials: &CloudflareCredentials,
+    request: &ApplyTemplateBulkRequest,
+) -> Result<BulkJob, String> {
+    if !credentials.is_valid() {
+        return Err("Invalid credentials: API Token is required".to_string());
+    }
+
+    let rendered = Arc::new(render(&request.template)?);
+    let legacy = request.legacy;
+    let (targets, failed) = bulk::resolve_targets(credentials, &request.targets).await;
+    let description = format!("Apply template {}@v{}", rendered.template_id, rendered.version);
+
+    Ok(BulkJobStore::spawn_job(
+        store,
+        credentials,
+        description,
+        targets,
+        failed,
+        concurrency_limit(request.concurrency),
+        move |client, zone_id, _zone_name| {
+            let rendered = rendered.clone();
+            async move {
+                apply_rendered(&client, &zone_id, &rendered, legacy).await
+                    .map(|id| format!("Created rule {}", id.unwrap_or_default()))
+            }
+        },
+    ))
+}
+
+// 从描述中解析模板来源标记
+fn template_origin(description: &str) -> Option<(String, u32)> {
+    let marker = Regex::new(r"\[template:([a-z0-9-]+)@v(\d+)\]").ok()?;
+    let captures = marker.captures(description)?;
+    Some((captures[1].to_string(), captures[2].parse().ok()?))
+}
+
+fn usage(rule_id: Option<String>, source: &str, description: &str) -> Option<TemplateUsage> {
+    let (template_id, version) = template_origin(description)?;
+    let current_version = TEMPLATES.iter().find(|t| t.id == template_id).map(|t| t.version);
+    Some(TemplateUsage {
+        rule_id,
+        source: source.to_string(),
+        description: description.to_string(),
+        outdated: current_version.is_some_and(|current| current > version),
+        template_id,
+        version,
+        current_version,
+    })
+}
+
+// 列出 Zone 中来自模板的规则及其版本是否过期
+pub async fn zone_usage(client: &CloudflareClient, zone_id: &str) -> Result<Vec<TemplateUsage>, String> {
+    let mut usages = Vec::new();
+
+    for rule in custom_rules::list_rules(client, zone_id).await?.rules {
+        if let Some(u) = usage(rule.id.clone(), "custom_rules", rule.description.as_deref().unwrap_or_default()) {
+            usages.push(u);
+        }
+    }
+
+    // 旧版防火墙规则接口可能已停用，读取失败时忽略
+    if let Ok(rules) = client.get_firewall_rules(zone_id).await {
+        for rule in rules {
+            if let Some(u) = usage(rule.id.clone(), "firewall_rules", rule.description.as_deref().unwrap_or_default()) {
+                usages.push(u);
+            }
+        }
+    }
+
+    Ok(usages)
+}
@@ -0,0 +1,266 @@
+use crate::cloudflare::CloudflareClient;
+use crate::custom_rules;
+use crate::expression::{self, EvalContext, Type, Val};
+use crate::models::*;
+use std::net::IpAddr;
+
+// 命中后不再继续评估的动作（log 只记录，继续评估后续规则）
+const TERMINATING_ACTIONS: &[&str] = &[
+    "block", "challenge", "js_challenge", "managed_challenge", "skip", "allow", "bypass",
+];
+
+struct Candidate {
+    source: &'static str,
+    rule_id: Option<String>,
+    description: Option<String>,
+    expression: String,
+    action: String,
+    enabled: bool,
+}
+
+// 用示例请求在本地依次评估 Zone 的规则：旧版防火墙规则（按 priority 与动作优先级）→ 自定义规则 → 草稿规则
+pub async fn test_rules(client: &CloudflareClient, request: &TestRulesRequest) -> Result<RuleTestResult, String> {
+    let zone = client.get_zone(&request.zone_id).await?;
+    let ctx = build_context(&request.request, &zone.name, &request.lists)?;
+
+    let mut candidates = Vec::new();
+
+    if request.include_legacy {
+        // 新 Zone 已无法使用旧版接口，读取失败时忽略
+        let mut legacy = client.get_firewall_rules(&zone.id).await.unwrap_or_default();
+        custom_rules::sort_legacy(&mut legacy);
+        for rule in legacy {
+            candidates.push(Candidate {
+                source: "legacy",
+                rule_id: rule.id,
+                description: rule.description,
+                expression: rule.filter.expression,
+                action: rule.action,
+                enabled: !rule.paused,
+            });
+        }
+    }
+
+    let custom = custom_rules::list_rules(client, &zone.id).await?;
+    for rule in custom.rules {
+        candidates.push(Candidate {
+            source: "custom",
+            rule_id: rule.id,
+            description: rule.description,
+            expression: rule.expression,
+            action: rule.action,
+            enabled: rule.enabled,
+        });
+    }
+    for rule in &request.draft_rules {
+        candidates.push(Candidate {
+            source: "draft",
+            rule_id: None,
+            description: rule.description.clone(),
+            expression: rule.expression.clone(),
+            action: rule.action.clone(),
+            enabled: rule.enabled,
+        });
+    }
+
+    let mut evaluations = Vec::new();
+    let mut matched_index = None;
+
+    for (position, candidate) in candidates.into_iter().enumerate() {
+        let outcome = expression::parse(&candidate.expression)
+            .map_err(|e| e.to_string())
+            .and_then(|expr| expression::evaluate(&expr, &ctx));
+
+        let (matched, error) = match outcome {
+            Ok(matched) => (matched, None),
+            Err(e) => (false, Some(e)),
+        };
+
+        if matched && candidate.enabled && matched_index.is_none()
+            && TERMINATING_ACTIONS.contains(&candidate.action.as_str())
+        {
+            matched_index = Some(evaluations.len());
+        }
+
+        evaluations.push(RuleEvaluation {
+            source: candidate.source.to_string(),
+            position,
+            rule_id: candidate.rule_id,
+            description: candidate.description,
+            action: candidate.action,
+            enabled: candidate.enabled,
+            matched,
+            error,
+        });
+    }
+
+    let matched_rule = matched_index.map(|i| {
+        let e = &evaluations[i];
+        RuleEvaluation {
+            source: e.source.clone(),
+            position: e.position,
+            rule_id: e.rule_id.clone(),
+            description: e.description.clone(),
+            action: e.action.clone(),
+            enabled: e.enabled,
+            matched: e.matched,
+            error: None,
+        }
+    });
+    let action = matched_rule.as_ref().map(|r| r.action.clone()).unwrap_or_else(|| "none".to_string());
+
+    Ok(RuleTestResult {
+        matched_rule,
+        action,
+        evaluations,
+        defaulted_fields: ctx.defaulted_fields(),
+    })
+}
+
+fn bytes(s: impl Into<String>) -> Val {
+    Val::Bytes(s.into())
+}
+
+fn bytes_array(items: &[String]) -> Val {
+    Val::Array(items.iter().map(|s| bytes(s.as_str())).collect())
+}
+
+// 键相同的值合并为数组（Cloudflare 的 Map<Array<String>> 字段）
+fn multi_map(pairs: &[(String, String)]) -> Val {
+    let mut entries: Vec<(String, Val)> = Vec::new();
+    for (key, value) in pairs {
+        match entries.iter_mut().find(|(k, _)| k == key) {
+            Some((_, Val::Array(values))) => values.push(bytes(value.as_str())),
+            _ => entries.push((key.clone(), Val::Array(vec![bytes(value.as_str())]))),
+        }
+    }
+    Val::Map(entries)
+}
+
+fn build_context(
+    sample: &SampleRequest,
+    zone_name: &str,
+    lists: &std::collections::HashMap<String, Vec<String>>,
+) -> Result<EvalContext, String> {
+    let mut headers: Vec<(String, String)> = sample.headers.iter()
+        .map(|(k, v)| (k.to_lowercase(), v.clone()))
+        .collect();
+    // 单独给出的 User-Agent 同时体现在请求头中
+    if let Some(user_agent) = &sample.user_agent {
+        headers.retain(|(k, _)| k != "user-agent");
+        headers.push(("user-agent".to_string(), user_agent.clone()));
+    }
+    let header = |name: &str| headers.iter().find(|(k, _)| k == name).map(|(_, v)| v.clone());
+
+    let url = if sample.uri.starts_with("http://") || sample.uri.starts_with("https://") {
+        reqwest::Url::parse(&sample.uri)
+    } else {
+        let host = header("host").unwrap_or_else(|| zone_name.to_string());
+        let path = if sample.uri.starts_with('/') { sample.uri.clone() } else { format!("/{}", sample.uri) };
+        reqwest::Url::parse(&format!("https://{}{}", host, path))
+    }
+    .map_err(|e| format!("Invalid request URI: {}", e))?;
+
+    let ip: IpAddr = sample.ip.trim().parse().map_err(|_| format!("Invalid IP address: {}", sample.ip))?;
+
+    let host = url.host_str().unwrap_or_default().to_string();
+    let path = url.path().to_string();
+    let query = url.query().unwrap_or_default().to_string();
+    let uri = if query.is_empty() { path.clone() } else { format!("{}?{}", path, query) };
+    let full_uri = format!("{}://{}{}", url.scheme(), host, uri);
+    let extension = path.rsplit('/').next()
+        .and_then(|segment| segment.rsplit_once('.').map(|(_, ext)| ext.to_lowercase()))
+        .unwrap_or_default();
+
+    let args: Vec<(String, String)> = url.query_pairs().map(|(k, v)| (k.into_owned(), v.into_owned())).collect();
+    let cookie_header = header("cookie").unwrap_or_default();
+    let cookies: Vec<(String, String)> = cookie_header.split(';')
+        .filter_map(|pair| pair.trim().split_once('='))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect();
+    let user_agent = header("user-agent").unwrap_or_default();
+
+    let mut ctx = EvalContext::new(lists.clone());
+    let fields = &mut ctx.fields;
+    let mut set = |name: &str, value: Val| {
+        fields.insert(name.to_string(), value);
+    };
+
+    set("http.host", bytes(host.as_str()));
+    set("http.request.method", bytes(sample.method.to_uppercase()));
+    set("http.request.uri", bytes(uri.as_str()));
+    set("http.request.uri.path", bytes(path.as_str()));
+    set("http.request.uri.path.extension", bytes(extension));
+    set("http.request.uri.query", bytes(query.as_str()));
+    set("http.request.full_uri", bytes(full_uri.as_str()));
+    set("raw.http.request.uri", bytes(uri.as_str()));
+    set("raw.http.request.uri.path", bytes(path.as_str()));
+    set("raw.http.request.uri.query", bytes(query.as_str()));
+    set("raw.http.request.full_uri", bytes(full_uri.as_str()));
+    set("http.request.version", bytes("HTTP/1.1"));
+    set("http.user_agent", bytes(user_agent));
+    set("http.cookie", bytes(cookie_header.as_str()));
+    set("http.referer", bytes(header("referer").unwrap_or_default()));
+    set("http.x_forwarded_for", bytes(header("x-forwarded-for").unwrap_or_default()));
+    set("http.request.headers", multi_map(&headers));
+    set("http.request.headers.names", bytes_array(&headers.iter().map(|(k, _)| k.clone()).collect::<Vec<_>>()));
+    set("http.request.headers.values", bytes_array(&headers.iter().map(|(_, v)| v.clone()).collect::<Vec<_>>()));
+    set("http.request.headers.truncated", Val::Bool(false));
+    set("http.request.cookies", multi_map(&cookies));
+    set("http.request.uri.args", multi_map(&args));
+    set("http.request.uri.args.names", bytes_array(&args.iter().map(|(k, _)| k.clone()).collect::<Vec<_>>()));
+    set("http.request.uri.args.values", bytes_array(&args.iter().map(|(_, v)| v.clone()).collect::<Vec<_>>()));
+    set("ssl", Val::Bool(url.scheme() == "https"));
+    set("ip.src", Val::Ip(ip));
+    set("cf.zone.name", bytes(zone_name));
+    if let Some(country) = &sample.country {
+        set("ip.src.country", bytes(country.to_uppercase()));
+        set("ip.geoip.country", bytes(country.to_uppercase()));
+    }
+    if let Some(asn) = sample.asn {
+        set("ip.src.asnum", Val::Int(asn));
+        set("ip.geoip.asnum", Val::Int(asn));
+    }
+
+    for (name, value) in &sample.fields {
+        // 未知字段按 JSON 值本身推断类型
+        let field_type = expression::field_type(name).unwrap_or(Type::Unknown);
+        set(name, json_to_val(name, &field_type, value)?);
+    }
+
+    Ok(ctx)
+}
+
+fn json_to_val(name: &str, field_type: &Type, value: &serde_json::Value) -> Result<Val, String> {
+    let invalid = || format!("Value for '{}' must be {}", name, field_type);
+    match field_type {
+        Type::Bytes => value.as_str().map(bytes).ok_or_else(invalid),
+        Type::Int => value.as_i64().map(Val::Int).ok_or_else(invalid),
+        Type::Bool => value.as_bool().map(Val::Bool).ok_or_else(invalid),
+        Type::Ip => value.as_str().and_then(|s| s.parse().ok()).map(Val::Ip).ok_or_else(invalid),
+        Type::Array(inner) => value.as_array()
+            .ok_or_else(invalid)?
+            .iter()
+            .map(|item| json_to_val(name, inner, item))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Val::Array),
+        Type::Map(inner) => value.as_object()
+            .ok_or_else(invalid)?
+            .iter()
+            .map(|(k, v)| {
+                // Map 的值为数组时允许直接传单个值
+                let v = if matches!(**inner, Type::Array(_)) && !v.is_array() { serde_json::json!([v]) } else { v.clone() };
+                json_to_val(name, inner, &v).map(|val| (k.clone(), val))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Val::Map),
+        Type::Unknown => match value {
+            serde_json::Value::String(s) => Ok(s.parse().map(Val::Ip).unwrap_or_else(|_| bytes(s))),
+            serde_json::Value::Number(n) => n.as_i64().map(Val::Int).ok_or_else(invalid),
+            serde_json::Value::Bool(b) => Ok(Val::Bool(*b)),
+            serde_json::Value::Array(_) => json_to_val(name, &Type::Array(Box::new(Type::Unknown)), value),
+            serde_json::Value::Object(_) => json_to_val(name, &Type::Map(Box::new(Type::Unknown)), value),
+            serde_json::Value::Null => Ok(Val::Missing),
+        },
+    }
+}
@@ -0,0 +1,194 @@
+use crate::cloudflare::{concurrency_limit, CloudflareClient};
+use crate::models::*;
+use futures::stream::{self, StreamExt};
+use std::collections::{BTreeMap, HashMap};
+
+// 对比多个 Zone 的设置，给出每个设置项的多数值与离群 Zone
+pub async fn compare_zone_settings(
+    client: &CloudflareClient,
+    request: &CompareZoneSettingsRequest,
+) -> Result<ZoneSettingsMatrix, String> {
+    let (zone_ids, names) = resolve_zones(client, &request.zone_ids).await?;
+
+    let fetched: Vec<(String, Result<Vec<ZoneSetting>, String>)> = stream::iter(zone_ids)
+        .map(|zone_id| async move {
+            let result = client.get_zone_settings(&zone_id).await;
+            (zone_id, result)
+        })
+        .buffered(concurrency_limit(request.concurrency))
+        .collect()
+        .await;
+
+    let mut zones = Vec::new();
+    let mut by_setting: BTreeMap<String, BTreeMap<String, serde_json::Value>> = BTreeMap::new();
+
+    for (zone_id, result) in fetched {
+        let mut error = None;
+        match result {
+            Ok(settings) => {
+                for setting in settings {
+                    if request.setting_ids.is_empty() || request.setting_ids.contains(&setting.id) {
+                        by_setting.entry(setting.id).or_default().insert(zone_id.clone(), setting.value);
+                    }
+                }
+            }
+            Err(e) => {
+                log::warn!("Failed to fetch settings for zone {}: {}", zone_id, e);
+                error = Some(e);
+            }
+        }
+
+        zones.push(MatrixZone {
+            name: names.get(&zone_id).cloned(),
+            id: zone_id,
+            error,
+        });
+    }
+
+    let settings = by_setting.into_iter()
+        .map(|(id, values)| {
+            let (majority, majority_count) = majority_value(values.values());
+            let outliers: Vec<String> = values.iter()
+                .filter(|(_, v)| **v != majority)
+                .map(|(zone_id, _)| zone_id.clone())
+                .collect();
+
+            SettingComparison {
+                id,
+                uniform: outliers.is_empty(),
+                majority,
+                majority_count,
+                values,
+                outliers,
+            }
+        })
+        .collect();
+
+    Ok(ZoneSettingsMatrix { zones, settings })
+}
+
+// 将离群 Zone 的设置对齐到指定值（默认多数值）
+pub async fn align_zone_setting(
+    client: &CloudflareClient,
+    request: &AlignZoneSettingRequest,
+) -> Result<AlignZoneSettingResponse, String> {
+    let (zone_ids, _) = resolve_zones(client, &request.zone_ids).await?;
+    let concurrency = concurrency_limit(request.concurrency);
+
+    let current: Vec<(String, Result<Option<serde_json::Value>, String>)> = stream::iter(zone_ids)
+        .map(|zone_id| async move {
+            let result = client.get_zone_settings(&zone_id).await.map(|settings| {
+                settings.into_iter()
+                    .find(|s| s.id == request.setting_id)
+                    .map(|s| s.value)
+            });
+            (zone_id, result)
+        })
+        .buffered(concurrency)
+        .collect()
+        .await;
+
+    let target = match &request.value {
+        Some(value) => value.clone(),
+        None => {
+            let values: Vec<&serde_json::Value> = current.iter()
+                .filter_map(|(_, r)| r.as_ref().ok().and_then(|v| v.as_ref()))
+                .collect();
+            if values.is_empty() {
+                return Err(format!("Setting {} is not available on any selected zone", request.setting_id));
+            }
+            majority_value(values.into_iter()).0
+        }
+    };
+
+    let results: Vec<AlignZoneResult> = stream::iter(current)
+        .map(|(zone_id, result)| {
+            let target = &target;
+            async move {
+                let previous = match result {
+                    Ok(Some(value)) => value,
+                    Ok(None) => {
+                        return AlignZoneResult {
+                            zone_id,
+                            status: "skipped".to_string(),
+                            previous: None,
+                            error: Some(format!("Setting {} is not available on this zone", request.setting_id)),
+                        };
+                    }
+                    Err(e) => {
+                        return AlignZoneResult { zone_id, status: "failed".to_string(), previous: None, error: Some(e) };
+                    }
+                };
+
+                if previous == *target {
+                    return AlignZoneResult { zone_id, status: "unchanged".to_string(), previous: Some(previous), error: None };
+                }
+
+                if request.dry_run {
+                    return AlignZoneResult { zone_id, status: "would_update".to_string(), previous: Some(previous), error: None };
+                }
+
+                let update = [UpdateSetting { id: request.setting_id.clone(), value: target.clone() }];
+                match client.update_zone_settings(&zone_id, &update).await {
+                    Ok(_) => AlignZoneResult { zone_id, status: "updated".to_string(), previous: Some(previous), error: None },
+                    Err(e) => AlignZoneResult { zone_id, status: "failed".to_string(), previous: Some(previous), error: Some(e) },
+                }
+            }
+        })
+        .buffered(concurrency)
+        .collect()
+        .await;
+
+    let updated = results.iter().filter(|r| r.status == "updated").count();
+    log::info!("Aligned setting {} on {} zone(s)", request.setting_id, updated);
+
+    Ok(AlignZoneSettingResponse {
+        setting_id: request.setting_id.clone(),
+        value: target,
+        results,
+    })
+}
+
+// 未指定 Zone 时使用账户下所有 Zone，同时返回 Zone 名称
+async fn resolve_zones(
+    client: &CloudflareClient,
+    zone_ids: &[String],
+) -> Result<(Vec<String>, HashMap<String, String>), String> {
+    match client.get_zones().await {
+        Ok(zones) => {
+            let names: HashMap<String, String> = zones.iter()
+                .map(|z| (z.id.clone(), z.name.clone()))
+                .collect();
+            let ids = if zone_ids.is_empty() {
+                zones.into_iter().map(|z| z.id).collect()
+            } else {
+                zone_ids.to_vec()
+            };
+            Ok((ids, names))
+        }
+        // 指定了 Zone 时，名称获取失败不影响对比
+        Err(_) if !zone_ids.is_empty() => Ok((zone_ids.to_vec(), HashMap::new())),
+        Err(e) => Err(e),
+    }
+}
+
+// 出现次数最多的值；票数相同时取最先出现的值
+fn majority_value<'a>(values: impl Iterator<Item = &'a serde_json::Value>) -> (serde_json::Value, usize) {
+    let mut counts: Vec<(&serde_json::Value, usize)> = Vec::new();
+    for value in values {
+        match counts.iter_mut().find(|(v, _)| *v == value) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((value, 1)),
+        }
+    }
+
+    let mut best: Option<(&serde_json::Value, usize)> = None;
+    for (value, count) in counts {
+        if best.map(|(_, c)| count > c).unwrap_or(true) {
+            best = Some((value, count));
+        }
+    }
+
+    best.map(|(v, c)| (v.clone(), c))
+        .unwrap_or((serde_json::Value::Null, 0))
+}
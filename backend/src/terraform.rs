@@ -0,0 +1,545 @@
+use crate::cloudflare::CloudflareClient;
+use crate::models::*;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+
+// 生成的配置所针对的 Terraform Cloudflare Provider 版本
+const PROVIDER_VERSION: &str = "~> 4.40";
+
+// cloudflare_zone_settings_override 支持的设置项：API 中的 ID -> Provider 属性名
+const SUPPORTED_SETTINGS: &[(&str, &str)] = &[
+    ("0rtt", "zero_rtt"),
+    ("always_online", "always_online"),
+    ("always_use_https", "always_use_https"),
+    ("automatic_https_rewrites", "automatic_https_rewrites"),
+    ("brotli", "brotli"),
+    ("browser_cache_ttl", "browser_cache_ttl"),
+    ("browser_check", "browser_check"),
+    ("cache_level", "cache_level"),
+    ("challenge_ttl", "challenge_ttl"),
+    ("ciphers", "ciphers"),
+    ("cname_flattening", "cname_flattening"),
+    ("development_mode", "development_mode"),
+    ("early_hints", "early_hints"),
+    ("email_obfuscation", "email_obfuscation"),
+    ("h2_prioritization", "h2_prioritization"),
+    ("hotlink_protection", "hotlink_protection"),
+    ("http2", "http2"),
+    ("http3", "http3"),
+    ("image_resizing", "image_resizing"),
+    ("ip_geolocation", "ip_geolocation"),
+    ("ipv6", "ipv6"),
+    ("max_upload", "max_upload"),
+    ("min_tls_version", "min_tls_version"),
+    ("minify", "minify"),
+    ("mirage", "mirage"),
+    ("mobile_redirect", "mobile_redirect"),
+    ("opportunistic_encryption", "opportunistic_encryption"),
+    ("opportunistic_onion", "opportunistic_onion"),
+    ("polish", "polish"),
+    ("prefetch_preload", "prefetch_preload"),
+    ("privacy_pass", "privacy_pass"),
+    ("proxy_read_timeout", "proxy_read_timeout"),
+    ("pseudo_ipv4", "pseudo_ipv4"),
+    ("response_buffering", "response_buffering"),
+    ("rocket_loader", "rocket_loader"),
+    ("security_level", "security_level"),
+    ("server_side_exclude", "server_side_exclude"),
+    ("sort_query_string_for_cache", "sort_query_string_for_cache"),
+    ("ssl", "ssl"),
+    ("tls_1_3", "tls_1_3"),
+    ("tls_client_auth", "tls_client_auth"),
+    ("true_client_ip_header", "true_client_ip_header"),
+    ("waf", "waf"),
+    ("webp", "webp"),
+    ("websockets", "websockets"),
+];
+
+// content 无法完整表达、需要 data 块的记录类型
+const STRUCTURED_RECORD_TYPES: &[&str] = &[
+    "CAA", "CERT", "DNSKEY", "DS", "LOC", "NAPTR", "SMIMEA", "SRV", "SSHFP", "TLSA", "URI",
+];
+
+// 规则中不属于配置的只读字段
+const RULE_READONLY_FIELDS: &[&str] = &["id", "version", "last_updated", "categories"];
+
+// 导出 Zone 的 Terraform 配置及对应的 import 块
+pub async fn export_zone(client: &CloudflareClient, request: &TerraformExportRequest) -> Result<TerraformExport, String> {
+    let zone = client.get_zone(&request.zone_id).await?;
+    let account_id = request.account_id.clone()
+        .or_else(|| zone.account.as_ref().map(|a| a.id.clone()));
+
+    let (settings, dns, page_rules, firewall, rate_limits, routes, rulesets) = tokio::join!(
+        client.get_zone_settings(&zone.id),
+        client.get_dns_records(&zone.id),
+        client.get_page_rules(&zone.id),
+        client.get_firewall_rules(&zone.id),
+        client.get_rate_limits(&zone.id),
+        client.get_worker_routes(&zone.id),
+        client.list_rulesets(&zone.id),
+    );
+
+    let mut export = Exporter::default();
+
+    match settings {
+        Ok(settings) => export.zone_settings(&zone.name, &zone.id, &settings),
+        Err(e) => export.warn(format!("Zone settings not exported: {}", e)),
+    }
+    match dns {
+        Ok(records) => records.iter().for_each(|r| export.dns_record(&zone.id, r)),
+        Err(e) => export.warn(format!("DNS records not exported: {}", e)),
+    }
+    match page_rules {
+        Ok(rules) => rules.iter().for_each(|r| export.page_rule(&zone.id, r)),
+        Err(e) => export.warn(format!("Page rules not exported: {}", e)),
+    }
+    match firewall {
+        Ok(rules) => rules.iter().for_each(|r| export.firewall_rule(&zone.id, r)),
+        Err(e) => export.warn(format!("Firewall rules not exported: {}", e)),
+    }
+    match rate_limits {
+        Ok(limits) => limits.iter().for_each(|l| export.rate_limit(&zone.id, l)),
+        Err(e) => export.warn(format!("Rate limits not exported: {}", e)),
+    }
+    match rulesets {
+        Ok(rulesets) => {
+            // 托管规则集由 Cloudflare 维护，只导出 Zone 自己的规则集
+            for summary in rulesets.iter().filter(|r| r["kind"].as_str() == Some("zone")) {
+                let ruleset_id = summary["id"].as_str().unwrap_or_default();
+                match client.get_ruleset(&zone.id, ruleset_id).await {
+                    Ok(ruleset) => export.ruleset(&zone.id, &ruleset),
+                    Err(e) => export.warn(format!("Ruleset {} not exported: {}", ruleset_id, e)),
+                }
+            }
+        }
+        Err(e) => export.warn(format!("Rulesets not exported: {}", e)),
+    }
+
+    match routes {
+        Ok(routes) => {
+            // 先导出路由引用的脚本，路由才能引用脚本资源
+            let scripts: Vec<&str> = routes.iter()
+                .filter_map(|r| r.script.as_deref())
+                .collect::<std::collections::BTreeSet<_>>()
+                .into_iter()
+                .collect();
+            for script in scripts {
+                match &account_id {
+                    Some(account_id) => match client.get_worker(account_id, script).await {
+                        Ok(content) => match content.single_source() {
+                            Some(source) => export.worker_script(account_id, script, source, content.main_module.is_some()),
+                            // 多模块 Worker 无法映射为单个 content 文件
+                            None => export.warn(format!("Worker script {} uses multiple modules and was not exported", script)),
+                        },
+                        Err(e) => export.warn(format!("Worker script {} not exported: {}", script, e)),
+                    },
+                    None => export.warn(format!("Worker script {} not exported: account ID unknown", script)),
+                }
+            }
+            routes.iter().for_each(|r| export.worker_route(&zone.id, r));
+        }
+        Err(e) => export.warn(format!("Worker routes not exported: {}", e)),
+    }
+
+    log::info!(
+        "Exported {} Terraform resources for zone {} ({} warnings)",
+        export.imports.len(), zone.name, export.warnings.len()
+    );
+
+    Ok(export.finish(zone.id, zone.name, account_id))
+}
+
+#[derive(Default)]
+struct Exporter {
+    resources: String,
+    imports: Vec<(String, String)>,
+    names: HashSet<String>,
+    // 脚本名 -> 资源地址
+    scripts: BTreeMap<String, String>,
+    extra_files: BTreeMap<String, String>,
+    warnings: Vec<String>,
+}
+
+impl Exporter {
+    fn warn(&mut self, message: String) {
+        self.warnings.push(message);
+    }
+
+    // 生成唯一的资源名称
+    fn name(&mut self, resource_type: &str, base: &str) -> String {
+        let mut name = String::new();
+        for c in base.to_lowercase().chars() {
+            if c.is_ascii_alphanumeric() {
+                name.push(c);
+            } else if !name.ends_with('_') {
+                name.push('_');
+            }
+        }
+        let mut name = name.trim_matches('_').to_string();
+        if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+            name = format!("r_{}", name);
+        }
+
+        let mut candidate = name.clone();
+        let mut suffix = 2;
+        while !self.names.insert(format!("{}.{}", resource_type, candidate)) {
+            candidate = format!("{}_{}", name, suffix);
+            suffix += 1;
+        }
+        candidate
+    }
+
+    fn open(&mut self, resource_type: &str, name: &str, import_id: String) -> String {
+        let address = format!("{}.{}", resource_type, name);
+        self.resources.push_str(&format!("resource \"{}\" \"{}\" {{\n", resource_type, name));
+        self.imports.push((address.clone(), import_id));
+        address
+    }
+
+    fn close(&mut self) {
+        self.resources.push_str("}\n\n");
+    }
+
+    fn zone_settings(&mut self, zone_name: &str, zone_id: &str, settings: &[ZoneSetting]) {
+        let name = self.name("cloudflare_zone_settings_override", zone_name);
+        self.open("cloudflare_zone_settings_override", &name, zone_id.to_string());
+        raw(&mut self.resources, 1, "zone_id", "local.zone_id");
+        self.resources.push('\n');
+        self.resources.push_str("  settings {\n");
+        let mut skipped = Vec::new();
+        for setting in settings.iter().filter(|s| s.editable) {
+            match SUPPORTED_SETTINGS.iter().find(|(id, _)| *id == setting.id) {
+                Some((_, attribute)) => write_field(&mut self.resources, 2, attribute, &setting.value),
+                None => skipped.push(setting.id.as_str()),
+            }
+        }
+        self.resources.push_str("  }\n");
+        self.close();
+
+        if !skipped.is_empty() {
+            self.warn(format!("Zone settings not supported by the provider were skipped: {}", skipped.join(", ")));
+        }
+    }
+
+    fn dns_record(&mut self, zone_id: &str, record: &DnsRecord) {
+        let Some(record_id) = &record.id else { return };
+        let name = self.name("cloudflare_record", &format!("{}_{}", record.name, record.record_type));
+        self.open("cloudflare_record", &name, format!("{}/{}", zone_id, record_id));
+
+        let out = &mut self.resources;
+        raw(out, 1, "zone_id", "local.zone_id");
+        attr(out, 1, "name", &Value::from(record.name.as_str()));
+        attr(out, 1, "type", &Value::from(record.record_type.as_str()));
+        attr(out, 1, "content", &Value::from(record.content.as_str()));
+        attr(out, 1, "ttl", &Value::from(record.ttl));
+        attr(out, 1, "proxied", &Value::from(record.proxied));
+        if let Some(priority) = record.priority {
+            attr(out, 1, "priority", &Value::from(priority));
+        }
+        self.close();
+
+        if STRUCTURED_RECORD_TYPES.contains(&record.record_type.as_str()) {
+            self.warn(format!(
+                "cloudflare_record.{}: {} records may need a data block instead of content",
+                name, record.record_type
+            ));
+        }
+    }
+
+    fn page_rule(&mut self, zone_id: &str, rule: &PageRule) {
+        let Some(rule_id) = &rule.id else { return };
+        let target = rule.targets.first().map(|t| t.constraint.value.clone()).unwrap_or_default();
+        let name = self.name("cloudflare_page_rule", &target);
+        self.open("cloudflare_page_rule", &name, format!("{}/{}", zone_id, rule_id));
+
+        let out = &mut self.resources;
+        raw(out, 1, "zone_id", "local.zone_id");
+        attr(out, 1, "target", &Value::from(target));
+        if let Some(priority) = rule.priority {
+            attr(out, 1, "priority", &Value::from(priority));
+        }
+        if let Some(status) = &rule.status {
+            attr(out, 1, "status", &Value::from(status.as_str()));
+        }
+        out.push('\n');
+        out.push_str("  actions {\n");
+        for action in &rule.actions {
+            // 无值动作（如 always_use_https、disable_security）在 Provider 中是布尔开关
+            let value = if action.value.is_null() { Value::Bool(true) } else { action.value.clone() };
+            write_field(out, 2, &action.id, &value);
+        }
+        out.push_str("  }\n");
+        self.close();
+    }
+
+    fn firewall_rule(&mut self, zone_id: &str, rule: &FirewallRule) {
+        let (Some(rule_id), Some(filter_id)) = (&rule.id, &rule.filter.id) else { return };
+        let base = rule.description.clone().unwrap_or_else(|| rule_id.clone());
+
+        let filter_name = self.name("cloudflare_filter", &base);
+        self.open("cloudflare_filter", &filter_name, format!("{}/{}", zone_id, filter_id));
+        let out = &mut self.resources;
+        raw(out, 1, "zone_id", "local.zone_id");
+        if let Some(description) = &rule.filter.description {
+            attr(out, 1, "description", &Value::from(description.as_str()));
+        }
+        attr(out, 1, "expression", &Value::from(rule.filter.expression.as_str()));
+        self.close();
+
+        let rule_name = self.name("cloudflare_firewall_rule", &base);
+        self.open("cloudflare_firewall_rule", &rule_name, format!("{}/{}", zone_id, rule_id));
+        let out = &mut self.resources;
+        raw(out, 1, "zone_id", "local.zone_id");
+        raw(out, 1, "filter_id", &format!("cloudflare_filter.{}.id", filter_name));
+        attr(out, 1, "action", &Value::from(rule.action.as_str()));
+        if let Some(description) = &rule.description {
+            attr(out, 1, "description", &Value::from(description.as_str()));
+        }
+        attr(out, 1, "paused", &Value::from(rule.paused));
+        self.close();
+    }
+
+    fn rate_limit(&mut self, zone_id: &str, limit: &RateLimit) {
+        let name = self.name("cloudflare_rate_limit", &limit.description);
+        self.open("cloudflare_rate_limit", &name, format!("{}/{}", zone_id, limit.id));
+
+        let out = &mut self.resources;
+        raw(out, 1, "zone_id", "local.zone_id");
+        attr(out, 1, "description", &Value::from(limit.description.as_str()));
+        attr(out, 1, "threshold", &Value::from(limit.threshold));
+        attr(out, 1, "period", &Value::from(limit.period));
+        attr(out, 1, "disabled", &Value::from(limit.disabled));
+
+        let mut request = serde_json::Map::new();
+        request.insert("url_pattern".to_string(), Value::from(limit.match_request.url.as_str()));
+        if let Some(methods) = &limit.match_request.methods {
+            request.insert("methods".to_string(), Value::from(methods.clone()));
+        }
+        if let Some(schemes) = &limit.match_request.schemes {
+            request.insert("schemes".to_string(), Value::from(schemes.clone()));
+        }
+        let mut matcher = serde_json::Map::new();
+        matcher.insert("request".to_string(), Value::Object(request));
+        write_field(out, 1, "match", &Value::Object(matcher));
+
+        let action = serde_json::to_value(&limit.action).unwrap_or(Value::Null);
+        write_field(out, 1, "action", &action);
+        self.close();
+    }
+
+    fn ruleset(&mut self, zone_id: &str, ruleset: &Value) {
+        let ruleset_id = ruleset["id"].as_str().unwrap_or_default();
+        let phase = ruleset["phase"].as_str().unwrap_or_default();
+        let name = self.name("cloudflare_ruleset", phase);
+        self.open("cloudflare_ruleset", &name, format!("zone/{}/{}", zone_id, ruleset_id));
+
+        let out = &mut self.resources;
+        raw(out, 1, "zone_id", "local.zone_id");
+        for field in ["name", "description", "kind", "phase"] {
+            if let Some(value) = ruleset[field].as_str() {
+                attr(out, 1, field, &Value::from(value));
+            }
+        }
+
+        let mut generic = false;
+        for rule in ruleset["rules"].as_array().into_iter().flatten() {
+            let Some(rule) = rule.as_object() else { continue };
+            let mut rule = rule.clone();
+            for field in RULE_READONLY_FIELDS {
+                rule.remove(*field);
+            }
+            generic |= rule.contains_key("action_parameters");
+            out.push('\n');
+            write_field(out, 1, "rules", &Value::Object(rule));
+        }
+        self.close();
+
+        if generic {
+            self.warn(format!(
+                "cloudflare_ruleset.{}: action_parameters were converted generically; run terraform plan to confirm",
+                name
+            ));
+        }
+    }
+
+    fn worker_script(&mut self, account_id: &str, script: &str, content: &str, module: bool) {
+        let name = self.name("cloudflare_workers_script", script);
+        let address = self.open("cloudflare_workers_script", &name, format!("{}/{}", account_id, script));
+        let path = format!("workers/{}.js", script);
+
+        let out = &mut self.resources;
+        raw(out, 1, "account_id", "local.account_id");
+        attr(out, 1, "name", &Value::from(script));
+        raw(out, 1, "content", &format!("file(\"${{path.module}}/{}\")", escape(&path)));
+        if module {
+            attr(out, 1, "module", &Value::Bool(true));
+        }
+        self.close();
+
+        self.extra_files.insert(path, content.to_string());
+        self.scripts.insert(script.to_string(), address);
+        self.warn(format!("cloudflare_workers_script.{}: bindings and secrets are not exported", name));
+    }
+
+    fn worker_route(&mut self, zone_id: &str, route: &WorkerRoute) {
+        let name = self.name("cloudflare_workers_route", &route.pattern);
+        self.open("cloudflare_workers_route", &name, format!("{}/{}", zone_id, route.id));
+
+        let script = route.script.as_ref().map(|s| (s.clone(), self.scripts.get(s).cloned()));
+        let out = &mut self.resources;
+        raw(out, 1, "zone_id", "local.zone_id");
+        attr(out, 1, "pattern", &Value::from(route.pattern.as_str()));
+        match script {
+            Some((_, Some(address))) => raw(out, 1, "script_name", &format!("{}.name", address)),
+            Some((script, None)) => attr(out, 1, "script_name", &Value::from(script)),
+            None => {}
+        }
+        self.close();
+    }
+
+    fn finish(self, zone_id: String, zone_name: String, account_id: Option<String>) -> TerraformExport {
+        let mut main = String::new();
+        main.push_str("terraform {\n  required_providers {\n    cloudflare = {\n");
+        main.push_str("      source  = \"cloudflare/cloudflare\"\n");
+        main.push_str(&format!("      version = \"{}\"\n", PROVIDER_VERSION));
+        main.push_str("    }\n  }\n}\n\n");
+        main.push_str("# API Token 从环境变量 CLOUDFLARE_API_TOKEN 读取\nprovider \"cloudflare\" {}\n\n");
+        main.push_str("locals {\n");
+        main.push_str(&format!("  zone_id    = \"{}\"\n", escape(&zone_id)));
+        if let Some(account_id) = &account_id {
+            main.push_str(&format!("  account_id = \"{}\"\n", escape(account_id)));
+        }
+        main.push_str("}\n\n");
+        main.push_str(&self.resources);
+
+        let mut imports = String::new();
+        for (address, id) in &self.imports {
+            imports.push_str(&format!("import {{\n  to = {}\n  id = \"{}\"\n}}\n\n", address, escape(id)));
+        }
+
+        let mut files = self.extra_files;
+        files.insert("main.tf".to_string(), main.trim_end().to_string() + "\n");
+        files.insert("imports.tf".to_string(), imports.trim_end().to_string() + "\n");
+
+        TerraformExport {
+            zone_id,
+            zone_name,
+            files,
+            resource_count: self.imports.len(),
+            warnings: self.warnings,
+        }
+    }
+}
+
+// ==================== HCL 输出 ====================
+
+fn indent(level: usize) -> String {
+    "  ".repeat(level)
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t")
+        .replace("${", "$${")
+        .replace("%{", "%%{")
+}
+
+fn is_identifier(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+fn raw(out: &mut String, level: usize, key: &str, expression: &str) {
+    out.push_str(&format!("{}{} = {}\n", indent(level), key, expression));
+}
+
+fn attr(out: &mut String, level: usize, key: &str, value: &Value) {
+    if value.is_null() {
+        return;
+    }
+    raw(out, level, key, &expression(value, level));
+}
+
+fn expression(value: &Value, level: usize) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => format!("\"{}\"", escape(s)),
+        Value::Array(items) if items.iter().all(|i| !i.is_object() && !i.is_array()) => {
+            format!("[{}]", items.iter().map(|i| expression(i, level)).collect::<Vec<_>>().join(", "))
+        }
+        Value::Array(items) => {
+            let inner: Vec<String> = items.iter()
+                .map(|i| format!("{}{},\n", indent(level + 1), expression(i, level + 1)))
+                .collect();
+            format!("[\n{}{}]", inner.concat(), indent(level))
+        }
+        Value::Object(map) => {
+            let inner: Vec<String> = map.iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| {
+                    let key = if is_identifier(k) { k.clone() } else { format!("\"{}\"", escape(k)) };
+                    format!("{}{} = {}\n", indent(level + 1), key, expression(v, level + 1))
+                })
+                .collect();
+            format!("{{\n{}{}}}", inner.concat(), indent(level))
+        }
+    }
+}
+
+// 按 Provider 的模式映射 JSON：对象为嵌套块，对象数组为重复块，其余（包括键名不合法的映射）为属性
+fn write_field(out: &mut String, level: usize, key: &str, value: &Value) {
+    match value {
+        Value::Object(map) if map.keys().all(|k| is_identifier(k)) => {
+            out.push_str(&format!("{}{} {{\n", indent(level), key));
+            for (k, v) in map {
+                write_field(out, level + 1, k, v);
+            }
+            out.push_str(&format!("{}}}\n", indent(level)));
+        }
+        Value::Array(items) if !items.is_empty() && items.iter().all(|i| i.is_object()) => {
+            for item in items {
+                write_field(out, level, key, item);
+            }
+        }
+        _ => attr(out, level, key, value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setting(id: &str, value: Value, editable: bool) -> ZoneSetting {
+        ZoneSetting { id: id.to_string(), value, editable, modified_on: None }
+    }
+
+    #[test]
+    fn zone_settings_use_provider_attribute_names() {
+        let mut export = Exporter::default();
+        export.zone_settings("example.com", "zone", &[
+            setting("0rtt", Value::from("off"), true),
+            setting("visitor_ip", Value::from("on"), true),
+            setting("http2", Value::from("on"), false),
+            setting("minify", serde_json::json!({ "css": "on", "html": "off", "js": "on" }), true),
+        ]);
+
+        let out = &export.resources;
+        assert!(out.contains("    zero_rtt = \"off\"\n"), "{}", out);
+        assert!(out.contains("    minify {\n      css = \"on\"\n"), "{}", out);
+        assert!(!out.contains("0rtt"), "{}", out);
+        assert!(!out.contains("visitor_ip"), "{}", out);
+        assert!(!out.contains("http2"), "{}", out);
+        assert_eq!(export.warnings, vec!["Zone settings not supported by the provider were skipped: visitor_ip"]);
+
+        // 设置块内每个属性名都必须是合法的 HCL 标识符
+        for line in out.lines().filter(|l| l.contains(" = ") || l.ends_with(" {")) {
+            let key = line.trim().split([' ', '=']).next().unwrap();
+            assert!(is_identifier(key), "invalid identifier in {:?}", line);
+        }
+    }
+}
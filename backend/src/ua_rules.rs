@@ -0,0 +1,89 @@
+use crate::cloudflare::CloudflareClient;
+use crate::custom_rules;
+use crate::expression;
+use crate::models::*;
+
+// User Agent 拦截规则支持的模式
+const MODES: &[&str] = &["block", "challenge", "js_challenge", "managed_challenge"];
+
+pub fn validate(rule: &UserAgentRule) -> Result<UserAgentRule, String> {
+    if !MODES.contains(&rule.mode.as_str()) {
+        return Err(format!(
+            "Unsupported mode '{}' (expected one of: {})",
+            rule.mode,
+            MODES.join(", ")
+        ));
+    }
+    if rule.configuration.target != "ua" {
+        return Err(format!("Unsupported target '{}' (expected ua)", rule.configuration.target));
+    }
+    if rule.configuration.value.trim().is_empty() {
+        return Err("User agent is required".to_string());
+    }
+    Ok(rule.clone())
+}
+
+pub async fn list_rules(client: &CloudflareClient, zone_id: &str) -> Result<Vec<UserAgentRule>, String> {
+    client.list_ua_rules(zone_id).await
+}
+
+pub async fn create_rule(client: &CloudflareClient, zone_id: &str, rule: &UserAgentRule) -> Result<UserAgentRule, String> {
+    let rule = validate(rule)?;
+    client.save_ua_rule(zone_id, None, &rule).await
+}
+
+pub async fn update_rule(client: &CloudflareClient, zone_id: &str, rule_id: &str, rule: &UserAgentRule) -> Result<UserAgentRule, String> {
+    let rule = validate(rule)?;
+    client.save_ua_rule(zone_id, Some(rule_id), &rule).await
+}
+
+pub async fn delete_rule(client: &CloudflareClient, zone_id: &str, rule_id: &str) -> Result<String, String> {
+    client.delete_ua_rule(zone_id, rule_id).await
+}
+
+// 将 User Agent 拦截规则转换为自定义规则（完整匹配 User-Agent）
+pub fn convert_legacy(rule: &UserAgentRule) -> Result<MigratedRule, String> {
+    let rule = validate(rule)?;
+    let mut notes = Vec::new();
+
+    let expression = format!("http.user_agent eq {}", expression::quote(&rule.configuration.value));
+    expression::validate(&expression)?;
+
+    if rule.paused {
+        notes.push("User agent rule was paused; converted rule is disabled".to_string());
+    }
+
+    Ok(MigratedRule {
+        legacy_id: rule.id.clone(),
+        rule: RulesetRule {
+            id: None,
+            expression,
+            action: rule.mode.clone(),
+            description: Some(match rule.description.as_deref().filter(|d| !d.is_empty()) {
+                Some(description) => description.to_string(),
+                None => format!("User agent {}: {}", rule.mode, rule.configuration.value),
+            }),
+            enabled: !rule.paused,
+            action_parameters: None,
+            ratelimit: None,
+            logging: None,
+            rule_ref: rule.id.clone(),
+            position: None,
+        },
+        notes,
+    })
+}
+
+// 迁移 Zone 的全部 User Agent 拦截规则到自定义规则
+pub async fn migrate(client: &CloudflareClient, request: &MigrateLegacyRulesRequest) -> Result<RuleMigrationReport, String> {
+    let zone_id = request.zone_id.as_str();
+    custom_rules::migrate_legacy(
+        client,
+        request,
+        custom_rules::PHASE,
+        "user agent rule",
+        client.list_ua_rules(zone_id),
+        |item| convert_legacy(item).map_err(|e| format!("{}: {}", item.id.as_deref().unwrap_or("?"), e)),
+        move |legacy_id| async move { client.delete_ua_rule(zone_id, &legacy_id).await },
+    ).await
+}
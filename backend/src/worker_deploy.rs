@@ -0,0 +1,233 @@
+use crate::cloudflare::CloudflareClient;
+use crate::models::*;
+use crate::worker_scripts;
+
+// 仅用于 Worker 路由的主机名使用的占位地址（不会真正回源）
+const PLACEHOLDER_AAAA: &str = "100::";
+
+// 生成 ES Module 代理 Worker；所有注入值都以 JSON 字面量写入，避免拼接出非法或可注入的脚本
+pub fn render_script(request: &DeployWorkerRequest) -> Result<String, String> {
+    let target = reqwest::Url::parse(request.target_url.trim())
+        .map_err(|e| format!("Invalid target URL '{}': {}", request.target_url, e))?;
+    if !matches!(target.scheme(), "http" | "https") || target.host_str().is_none() {
+        return Err(format!("Target URL '{}' must be an http(s) URL with a host", request.target_url));
+    }
+
+    let cdn_node = request.cdn_node.trim();
+    if !cdn_node.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.') {
+        return Err(format!("CDN node '{}' must be a hostname", cdn_node));
+    }
+
+    let literal = |value: &str| serde_json::to_string(value).unwrap_or_else(|_| "\"\"".to_string());
+
+    Ok(format!(
+        r#"const TARGET_URL = {target_url};
+const CDN_NODE = {cdn_node};
+const CACHE_TTL = {cache_ttl};
+
+export default {{
+    async fetch(request, env, ctx) {{
+        const target = new URL(TARGET_URL);
+        const url = new URL(request.url);
+        url.protocol = target.protocol;
+        url.hostname = target.hostname;
+        url.port = target.port;
+
+        const cacheable = CACHE_TTL > 0 && request.method === 'GET';
+        const cache = caches.default;
+        if (cacheable) {{
+            const cached = await cache.match(request);
+            if (cached) {{
+                return cached;
+            }}
+        }}
+
+        const init = CDN_NODE ? {{ cf: {{ resolveOverride: CDN_NODE }} }} : {{}};
+        let response = await fetch(new Request(url.toString(), request), init);
+
+        if (cacheable && response.ok) {{
+            response = new Response(response.body, response);
+            response.headers.set('Cache-Control', `public, max-age=${{CACHE_TTL}}`);
+            ctx.waitUntil(cache.put(request, response.clone()));
+        }}
+
+        return response;
+    }},
+}};
+"#,
+        target_url = literal(target.as_str()),
+        cdn_node = literal(cdn_node),
+        cache_ttl = request.cache_ttl
+    ))
+}
+
+fn step(report: &mut WorkerDeployReport, name: &str, status: &str, message: String) {
+    if status == "failed" {
+        report.success = false;
+    }
+    report.steps.push(WorkerDeployStep { step: name.to_string(), status: status.to_string(), message });
+}
+
+// 上传 Worker，并为访问域名创建路由与代理的 DNS 记录；上传失败时不再继续
+pub async fn deploy(client: &CloudflareClient, request: &DeployWorkerRequest) -> Result<WorkerDeployReport, String> {
+    let script_name = request.script_name.trim();
+    if script_name.is_empty() || !script_name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("Invalid script name '{}'", request.script_name));
+    }
+
+    let domain = request.access_domain.trim();
+    let domain = domain.strip_prefix("https://").or_else(|| domain.strip_prefix("http://")).unwrap_or(domain);
+    let domain = domain.trim_end_matches('/').to_ascii_lowercase();
+    if domain.is_empty() || domain.contains('/') {
+        return Err(format!("Invalid access domain '{}'", request.access_domain));
+    }
+
+    let script = render_script(request)?;
+
+    let zone = client.get_zone(&request.zone_id).await?;
+    if domain != zone.name && !domain.ends_with(&format!(".{}", zone.name)) {
+        return Err(format!("{} is not part of zone {}", domain, zone.name));
+    }
+    let account_id = zone.account
+        .map(|a| a.id)
+        .ok_or_else(|| format!("Zone {} has no account", zone.name))?;
+
+    log::info!("Deploying Worker {} for {} on zone {}", script_name, domain, zone.name);
+
+    let mut report = WorkerDeployReport {
+        script_name: script_name.to_string(),
+        account_id: account_id.clone(),
+        route_pattern: format!("{}/*", domain),
+        success: true,
+        steps: Vec::new(),
+    };
+
+    match client.upload_worker(&account_id, script_name, &worker_scripts::single_module(&script), &worker_scripts::default_metadata()).await {
+        Ok(_) => step(&mut report, "upload", "ok", format!("Uploaded {}", script_name)),
+        Err(e) => {
+            step(&mut report, "upload", "failed", e);
+            return Ok(report);
+        }
+    }
+
+    let pattern = report.route_pattern.clone();
+    match client.get_worker_routes(&request.zone_id).await {
+        Ok(routes) => match routes.iter().find(|r| r.pattern == pattern) {
+            Some(route) if route.script.as_deref() == Some(script_name) => {
+                step(&mut report, "route", "skipped", format!("{} already routes to {}", pattern, script_name));
+            }
+            Some(route) => step(
+                &mut report,
+                "route",
+                "failed",
+                format!("{} is already routed to {}", pattern, route.script.as_deref().unwrap_or("no script")),
+            ),
+            None => match client.create_worker_route(&request.zone_id, &pattern, script_name).await {
+                Ok(route) => step(&mut report, "route", "ok", format!("Created route {} ({})", pattern, route.id)),
+                Err(e) => step(&mut report, "route", "failed", e),
+            },
+        },
+        Err(e) => step(&mut report, "route", "failed", e),
+    }
+
+    match client.get_dns_records_by_name(&request.zone_id, &domain).await {
+        Ok(records) => {
+            let existing = records.iter()
+                .find(|r| r.name.eq_ignore_ascii_case(&domain) && matches!(r.record_type.as_str(), "A" | "AAAA" | "CNAME"));
+            match existing {
+                Some(record) if record.proxied => step(
+                    &mut report,
+                    "dns",
+                    "skipped",
+                    format!("Proxied {} record for {} already exists", record.record_type, domain),
+                ),
+                Some(record) => step(
+                    &mut report,
+                    "dns",
+                    "failed",
+                    format!("{} record for {} exists but is not proxied; the route will not receive traffic", record.record_type, domain),
+                ),
+                None => {
+                    let record = DnsRecord {
+                        id: None,
+                        zone_id: Some(request.zone_id.clone()),
+                        record_type: "AAAA".to_string(),
+                        name: domain.clone(),
+                        content: PLACEHOLDER_AAAA.to_string(),
+                        ttl: 1,
+                        proxied: true,
+                        priority: None,
+                    };
+                    match client.create_dns_record(&record).await {
+                        Ok(_) => step(&mut report, "dns", "ok", format!("Created proxied AAAA record {} -> {}", domain, PLACEHOLDER_AAAA)),
+                        Err(e) => step(&mut report, "dns", "failed", e),
+                    }
+                }
+            }
+        }
+        Err(e) => step(&mut report, "dns", "failed", e),
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(target_url: &str, cdn_node: &str) -> DeployWorkerRequest {
+        DeployWorkerRequest {
+            zone_id: "zone".to_string(),
+            script_name: "proxy".to_string(),
+            target_url: target_url.to_string(),
+            access_domain: "www.example.com".to_string(),
+            cache_ttl: 300,
+            cdn_node: cdn_node.to_string(),
+        }
+    }
+
+    // 返回 TARGET_URL 的 JSON 字面量解析结果，以及去掉该行后的脚本
+    fn split_target(script: &str) -> (String, String) {
+        let (first, rest) = script.split_once('\n').unwrap();
+        let literal = first.strip_prefix("const TARGET_URL = ").unwrap().strip_suffix(';').unwrap();
+        (serde_json::from_str(literal).unwrap(), rest.to_string())
+    }
+
+    #[test]
+    fn target_url_stays_inside_string_literal() {
+        let (_, expected_rest) = split_target(&render_script(&request("https://origin.example.com/", "")).unwrap());
+
+        for url in [
+            "https://origin.example.com/a\"b",
+            "https://origin.example.com/?q=`x`",
+            "https://origin.example.com/?q=${alert(1)}",
+            "https://origin.example.com/</script><script>alert(1)</script>",
+            "https://origin.example.com/a\nb';alert(1);//",
+        ] {
+            let script = render_script(&request(url, "")).unwrap();
+            let (target, rest) = split_target(&script);
+            assert_eq!(target, reqwest::Url::parse(url).unwrap().as_str());
+            assert_eq!(rest, expected_rest, "script body changed for {:?}", url);
+        }
+    }
+
+    #[test]
+    fn cdn_node_is_a_string_literal() {
+        let script = render_script(&request("https://origin.example.com", "cdn-1.example.com")).unwrap();
+        assert!(script.contains("\nconst CDN_NODE = \"cdn-1.example.com\";\n"));
+    }
+
+    #[test]
+    fn rejects_invalid_cdn_node() {
+        for node in ["a\"b", "x;alert(1)", "`x`", "a b", "a\nb"] {
+            assert!(render_script(&request("https://origin.example.com", node)).is_err(), "{:?}", node);
+        }
+    }
+
+    #[test]
+    fn rejects_non_http_target() {
+        for url in ["javascript:alert(1)", "ftp://origin.example.com", "file:///etc/passwd", "data:text/html,x", "not a url"] {
+            assert!(render_script(&request(url, "")).is_err(), "{:?}", url);
+        }
+    }
+}
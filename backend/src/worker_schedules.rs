@@ -0,0 +1,366 @@
+use crate::cloudflare::CloudflareClient;
+use crate::models::*;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Timelike, Utc, Weekday};
+use std::collections::HashSet;
+
+// 预览触发时间的默认与最大条数
+const DEFAULT_PREVIEW_COUNT: usize = 5;
+const MAX_PREVIEW_COUNT: usize = 100;
+
+// 查找下一次触发时间时最多向后搜索的天数（如 2 月 30 日这类永不触发的表达式）
+const MAX_SEARCH_DAYS: i64 = 366 * 5;
+
+const MONTH_NAMES: &[&str] = &["JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC"];
+// Cloudflare 的星期取值为 1-7，1 表示周日
+const WEEKDAY_NAMES: &[&str] = &["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+// Cloudflare Cron Triggers 方言：分 时 日 月 周，全部按 UTC 计算。
+// 日支持 L（月末）、LW（月末最后一个工作日）、nW（离 n 日最近的工作日）；
+// 周支持 nL（当月最后一个星期 n）与 n#k（当月第 k 个星期 n）
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    last_day: bool,
+    last_weekday: bool,
+    nearest_weekdays: Vec<u32>,
+    months: u16,
+    weekdays: u8,
+    last_of_weekday: Vec<u32>,
+    nth_weekday: Vec<(u32, u32)>,
+    // 字段为 * 时不参与日与周的“或”匹配
+    any_day: bool,
+    any_weekday: bool,
+}
+
+fn parse_value(token: &str, min: u32, max: u32, names: &[&str], field: &str) -> Result<u32, String> {
+    let upper = token.to_ascii_uppercase();
+    if let Some(index) = names.iter().position(|n| *n == upper) {
+        return Ok(index as u32 + min);
+    }
+    let value: u32 = token.parse().map_err(|_| format!("Invalid {} value '{}'", field, token))?;
+    if value < min || value > max {
+        return Err(format!("Value {} for {} is out of range {}-{}", value, field, min, max));
+    }
+    Ok(value)
+}
+
+// 解析数字列表、范围与步长（*、a、a-b、*/s、a/s、a-b/s），返回位集合
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str], label: &str) -> Result<u64, String> {
+    let mut bits = 0u64;
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().map_err(|_| format!("Invalid {} step '{}'", label, step))?;
+                if step == 0 || step > max {
+                    return Err(format!("Step {} for {} is out of range 1-{}", step, label, max));
+                }
+                (range, step)
+            }
+            None => (item, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            let start = parse_value(start, min, max, names, label)?;
+            let end = parse_value(end, min, max, names, label)?;
+            if start > end {
+                return Err(format!("Invalid {} range '{}'", label, range));
+            }
+            (start, end)
+        } else {
+            let start = parse_value(range, min, max, names, label)?;
+            // a/s 表示从 a 开始到最大值，每 s 个取一个
+            (start, if item.contains('/') { max } else { start })
+        };
+
+        let mut value = start;
+        while value <= end {
+            bits |= 1 << value;
+            value += step;
+        }
+    }
+    Ok(bits)
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    NaiveDate::from_ymd_opt(next_year, next_month, 1)
+        .and_then(|d| d.pred_opt())
+        .map(|d| d.day())
+        .unwrap_or(31)
+}
+
+fn is_weekend(date: NaiveDate) -> bool {
+    matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+}
+
+// 离指定日期最近的工作日，不跨越月份
+fn nearest_weekday(year: i32, month: u32, day: u32) -> Option<u32> {
+    let last = days_in_month(year, month);
+    if day > last {
+        return None;
+    }
+    let date = NaiveDate::from_ymd_opt(year, month, day)?;
+    Some(match date.weekday() {
+        Weekday::Sat if day == 1 => 3,
+        Weekday::Sat => day - 1,
+        Weekday::Sun if day == last => day - 2,
+        Weekday::Sun => day + 1,
+        _ => day,
+    })
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!(
+                "Cron expression '{}' must have 5 fields (minute hour day-of-month month day-of-week), got {}",
+                expression,
+                fields.len()
+            ));
+        }
+
+        let mut schedule = CronSchedule {
+            minutes: parse_field(fields[0], 0, 59, &[], "minute")?,
+            hours: parse_field(fields[1], 0, 23, &[], "hour")? as u32,
+            days: 0,
+            last_day: false,
+            last_weekday: false,
+            nearest_weekdays: Vec::new(),
+            months: parse_field(fields[3], 1, 12, MONTH_NAMES, "month")? as u16,
+            weekdays: 0,
+            last_of_weekday: Vec::new(),
+            nth_weekday: Vec::new(),
+            any_day: fields[2] == "*",
+            any_weekday: fields[4] == "*",
+        };
+
+        for item in fields[2].split(',') {
+            let upper = item.to_ascii_uppercase();
+            if upper == "L" {
+                schedule.last_day = true;
+            } else if upper == "LW" {
+                schedule.last_weekday = true;
+            } else if let Some(day) = upper.strip_suffix('W') {
+                schedule.nearest_weekdays.push(parse_value(day, 1, 31, &[], "day-of-month")?);
+            } else {
+                schedule.days |= parse_field(item, 1, 31, &[], "day-of-month")? as u32;
+            }
+        }
+
+        for item in fields[4].split(',') {
+            let upper = item.to_ascii_uppercase();
+            if let Some((weekday, nth)) = upper.split_once('#') {
+                let weekday = parse_value(weekday, 1, 7, WEEKDAY_NAMES, "day-of-week")?;
+                let nth: u32 = nth.parse().ok().filter(|n| (1..=5).contains(n))
+                    .ok_or_else(|| format!("Invalid day-of-week occurrence '{}' (expected 1-5)", item))?;
+                schedule.nth_weekday.push((weekday, nth));
+            } else if let Some(weekday) = upper.strip_suffix('L').filter(|w| !w.is_empty()) {
+                schedule.last_of_weekday.push(parse_value(weekday, 1, 7, WEEKDAY_NAMES, "day-of-week")?);
+            } else {
+                schedule.weekdays |= parse_field(item, 1, 7, WEEKDAY_NAMES, "day-of-week")? as u8;
+            }
+        }
+
+        Ok(schedule)
+    }
+
+    fn day_of_month_matches(&self, date: NaiveDate) -> bool {
+        let last = days_in_month(date.year(), date.month());
+        if self.days & (1 << date.day()) != 0 || (self.last_day && date.day() == last) {
+            return true;
+        }
+        if self.last_weekday {
+            let last_weekday = (1..=last).rev()
+                .find(|d| NaiveDate::from_ymd_opt(date.year(), date.month(), *d).is_some_and(|d| !is_weekend(d)));
+            if last_weekday == Some(date.day()) {
+                return true;
+            }
+        }
+        self.nearest_weekdays.iter()
+            .any(|day| nearest_weekday(date.year(), date.month(), *day) == Some(date.day()))
+    }
+
+    fn day_of_week_matches(&self, date: NaiveDate) -> bool {
+        let weekday = date.weekday().number_from_sunday();
+        if self.weekdays & (1 << weekday) != 0 {
+            return true;
+        }
+        if self.last_of_weekday.contains(&weekday) && date.day() + 7 > days_in_month(date.year(), date.month()) {
+            return true;
+        }
+        self.nth_weekday.iter().any(|(w, nth)| *w == weekday && (date.day() - 1) / 7 + 1 == *nth)
+    }
+
+    // 与标准 cron 一致：日与周都被限制时，任一匹配即可
+    fn date_matches(&self, date: NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => self.day_of_week_matches(date),
+            (false, true) => self.day_of_month_matches(date),
+            (false, false) => self.day_of_month_matches(date) || self.day_of_week_matches(date),
+        }
+    }
+
+    // after 之后（不含）的下一次触发时间
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let mut date = start.date_naive();
+        let mut first_day = true;
+
+        for _ in 0..MAX_SEARCH_DAYS {
+            if self.date_matches(date) {
+                let (from_hour, from_minute) = if first_day { (start.hour(), start.minute()) } else { (0, 0) };
+                for hour in from_hour..24 {
+                    if self.hours & (1 << hour) == 0 {
+                        continue;
+                    }
+                    let first_minute = if hour == from_hour { from_minute } else { 0 };
+                    if let Some(minute) = (first_minute..60).find(|m| self.minutes & (1 << m) != 0) {
+                        return date.and_hms_opt(hour, minute, 0).map(|t| t.and_utc());
+                    }
+                }
+            }
+            date = date.succ_opt()?;
+            first_day = false;
+        }
+
+        None
+    }
+
+    pub fn upcoming(&self, after: DateTime<Utc>, count: usize) -> Vec<DateTime<Utc>> {
+        let mut runs = Vec::with_capacity(count);
+        let mut cursor = after;
+        while runs.len() < count {
+            match self.next_after(cursor) {
+                Some(next) => {
+                    runs.push(next);
+                    cursor = next;
+                }
+                None => break,
+            }
+        }
+        runs
+    }
+}
+
+fn next_runs(cron: &str, after: DateTime<Utc>, count: usize) -> Vec<String> {
+    CronSchedule::parse(cron)
+        .map(|schedule| schedule.upcoming(after, count).iter().map(|t| t.to_rfc3339()).collect())
+        .unwrap_or_default()
+}
+
+// 获取脚本的 Cron Triggers，并附上每个触发器接下来的触发时间
+pub async fn get(client: &CloudflareClient, account_id: &str, script_name: &str) -> Result<WorkerSchedules, String> {
+    let mut schedules = client.get_worker_schedules(account_id, script_name).await?;
+    let now = Utc::now();
+    for schedule in &mut schedules {
+        schedule.next_runs = next_runs(&schedule.cron, now, DEFAULT_PREVIEW_COUNT);
+    }
+    Ok(WorkerSchedules { script_name: script_name.to_string(), schedules })
+}
+
+// 校验全部表达式后整体替换；空列表表示移除所有触发器
+pub async fn update(client: &CloudflareClient, request: &UpdateWorkerSchedulesRequest) -> Result<WorkerSchedules, String> {
+    let mut seen = HashSet::new();
+    let mut crons = Vec::new();
+    for cron in &request.schedules {
+        let cron = cron.split_whitespace().collect::<Vec<_>>().join(" ");
+        CronSchedule::parse(&cron)?;
+        if !seen.insert(cron.clone()) {
+            return Err(format!("Duplicate cron trigger '{}'", cron));
+        }
+        crons.push(cron);
+    }
+
+    log::info!("Setting {} cron triggers on Worker {}", crons.len(), request.script_name);
+
+    let mut schedules = client.update_worker_schedules(&request.account_id, &request.script_name, &crons).await?;
+    let now = Utc::now();
+    for schedule in &mut schedules {
+        schedule.next_runs = next_runs(&schedule.cron, now, DEFAULT_PREVIEW_COUNT);
+    }
+    Ok(WorkerSchedules { script_name: request.script_name.clone(), schedules })
+}
+
+pub fn preview(request: &PreviewCronRequest) -> Result<CronPreview, String> {
+    let schedule = CronSchedule::parse(&request.cron)?;
+    let after = match request.from.as_deref() {
+        Some(from) => DateTime::parse_from_rfc3339(from)
+            .map_err(|e| format!("Invalid start time '{}': {}", from, e))?
+            .with_timezone(&Utc),
+        None => Utc::now(),
+    };
+    let count = request.count.unwrap_or(DEFAULT_PREVIEW_COUNT).clamp(1, MAX_PREVIEW_COUNT);
+
+    let next_runs: Vec<String> = schedule.upcoming(after, count).iter().map(|t| t.to_rfc3339()).collect();
+    if next_runs.is_empty() {
+        return Err(format!("Cron expression '{}' never fires", request.cron));
+    }
+
+    Ok(CronPreview { cron: request.cron.clone(), next_runs })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc)
+    }
+
+    fn next(cron: &str, after: &str) -> Option<String> {
+        CronSchedule::parse(cron).unwrap().next_after(at(after)).map(|t| t.to_rfc3339())
+    }
+
+    #[test]
+    fn day_of_week_one_is_sunday() {
+        // 2026-01-01 是周四，下一个周日是 1 月 4 日
+        assert_eq!(next("0 12 * * 1", "2026-01-01T00:00:00Z").as_deref(), Some("2026-01-04T12:00:00+00:00"));
+        assert_eq!(next("0 12 * * SUN", "2026-01-01T00:00:00Z").as_deref(), Some("2026-01-04T12:00:00+00:00"));
+        assert_eq!(next("0 12 * * 7", "2026-01-01T00:00:00Z").as_deref(), Some("2026-01-03T12:00:00+00:00"));
+    }
+
+    #[test]
+    fn nearest_weekday() {
+        // 8 月 15 日是周六，取周五 14 日
+        assert_eq!(next("0 0 15W * *", "2026-08-01T00:00:00Z").as_deref(), Some("2026-08-14T00:00:00+00:00"));
+        // 2 月 15 日是周日，取周一 16 日
+        assert_eq!(next("0 0 15W * *", "2026-02-01T00:00:00Z").as_deref(), Some("2026-02-16T00:00:00+00:00"));
+        // 8 月 1 日是周六，不跨月，取周一 3 日
+        assert_eq!(next("0 0 1W * *", "2026-07-31T12:00:00Z").as_deref(), Some("2026-08-03T00:00:00+00:00"));
+    }
+
+    #[test]
+    fn last_weekday_of_month() {
+        // 1 月 31 日是周六
+        assert_eq!(next("0 0 LW * *", "2026-01-01T00:00:00Z").as_deref(), Some("2026-01-30T00:00:00+00:00"));
+        assert_eq!(next("0 0 L * *", "2026-02-01T00:00:00Z").as_deref(), Some("2026-02-28T00:00:00+00:00"));
+    }
+
+    #[test]
+    fn nth_and_last_weekday() {
+        assert_eq!(next("0 0 * * 2#1", "2026-01-01T00:00:00Z").as_deref(), Some("2026-01-05T00:00:00+00:00"));
+        assert_eq!(next("0 0 * * MON#3", "2026-01-01T00:00:00Z").as_deref(), Some("2026-01-19T00:00:00+00:00"));
+        assert_eq!(next("0 18 * * 6L", "2026-01-01T00:00:00Z").as_deref(), Some("2026-01-30T18:00:00+00:00"));
+    }
+
+    #[test]
+    fn impossible_date_never_fires() {
+        assert_eq!(next("0 0 30 2 *", "2026-01-01T00:00:00Z"), None);
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        assert!(CronSchedule::parse("60 * * * *").err().unwrap().contains("out of range"));
+        assert!(CronSchedule::parse("* * * *").is_err());
+        assert!(CronSchedule::parse("0 0 * * 2#6").is_err());
+        assert!(CronSchedule::parse("0 0 * * 0").is_err());
+    }
+}
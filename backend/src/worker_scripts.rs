@@ -0,0 +1,632 @@
+use crate::cloudflare::CloudflareClient;
+use crate::models::*;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::{NaiveDate, Utc};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::io::{Cursor, Read, Write};
+
+pub const MAIN_MODULE: &str = "worker.js";
+const DEFAULT_COMPATIBILITY_DATE: &str = "2024-01-01";
+
+const USAGE_MODELS: &[&str] = &["standard", "bundled", "unbound"];
+
+// 上传接口接受的模块类型
+const MODULE_TYPES: &[&str] = &[
+    "application/javascript+module",
+    "application/javascript",
+    "text/javascript",
+    "text/x-python",
+    "application/wasm",
+    "text/plain",
+    "application/octet-stream",
+    "application/source-map",
+];
+
+// 可作为主模块的类型
+const MAIN_MODULE_TYPES: &[&str] = &["application/javascript+module", "text/x-python"];
+
+// 归档解压后的总大小上限，防止压缩炸弹
+const MAX_ARCHIVE_BYTES: u64 = 64 * 1024 * 1024;
+
+// 待上传或已下载的模块文件
+pub struct ModuleFile {
+    pub name: String,
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+// 从 Cloudflare 下载的脚本内容；Service Worker 格式的脚本没有主模块
+pub struct WorkerScriptContent {
+    pub main_module: Option<String>,
+    pub modules: Vec<ModuleFile>,
+}
+
+impl WorkerScriptContent {
+    // 单文件脚本的源码；多模块或非文本内容返回 None
+    pub fn single_source(&self) -> Option<&str> {
+        match self.modules.as_slice() {
+            [module] if is_text(&module.content_type) => std::str::from_utf8(&module.data).ok(),
+            _ => None,
+        }
+    }
+}
+
+pub struct MultipartPart {
+    pub name: String,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    pub data: Vec<u8>,
+}
+
+// 不带绑定的默认元数据
+pub fn default_metadata() -> Value {
+    json!({
+        "main_module": MAIN_MODULE,
+        "compatibility_date": DEFAULT_COMPATIBILITY_DATE
+    })
+}
+
+// 单个 ES 模块脚本，与 default_metadata 配合使用
+pub fn single_module(script: &str) -> Vec<ModuleFile> {
+    vec![ModuleFile {
+        name: MAIN_MODULE.to_string(),
+        content_type: "application/javascript+module".to_string(),
+        data: script.as_bytes().to_vec(),
+    }]
+}
+
+// Service Worker 格式的单文件脚本：以 body_part 指定脚本字段，而不是 main_module
+pub fn service_worker(body_part: &str, script: &str) -> (Vec<ModuleFile>, Value) {
+    let modules = vec![ModuleFile {
+        name: body_part.to_string(),
+        content_type: "application/javascript".to_string(),
+        data: script.as_bytes().to_vec(),
+    }];
+    let metadata = json!({
+        "body_part": body_part,
+        "compatibility_date": DEFAULT_COMPATIBILITY_DATE
+    });
+    (modules, metadata)
+}
+
+// 按扩展名推断模块类型
+fn content_type_for(name: &str) -> &'static str {
+    let extension = name.rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase()).unwrap_or_default();
+    match extension.as_str() {
+        "js" | "mjs" => "application/javascript+module",
+        "cjs" => "application/javascript",
+        "py" => "text/x-python",
+        "wasm" => "application/wasm",
+        "map" => "application/source-map",
+        "txt" | "html" | "htm" | "css" | "csv" | "json" | "md" | "sql" | "svg" | "xml" => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+fn is_text(content_type: &str) -> bool {
+    content_type.starts_with("text/") || content_type.contains("javascript") || content_type == "application/source-map"
+}
+
+// 规范化模块路径：去掉 ./ 前缀，拒绝绝对路径与 ..
+fn module_name(path: &str) -> Result<String, String> {
+    let name = path.trim().trim_start_matches("./");
+    if name.is_empty() || name.starts_with('/') || name.contains('\\') || name.split('/').any(|s| s == ".." || s.is_empty()) {
+        return Err(format!("Invalid module name '{}'", path));
+    }
+    Ok(name.to_string())
+}
+
+fn module_type(name: &str, declared: Option<&str>) -> Result<String, String> {
+    match declared.map(|t| t.split(';').next().unwrap_or_default().trim()).filter(|t| !t.is_empty()) {
+        Some(declared) if MODULE_TYPES.contains(&declared) => Ok(declared.to_string()),
+        Some(declared) => Err(format!(
+            "Unsupported content type '{}' for module {} (expected one of: {})",
+            declared,
+            name,
+            MODULE_TYPES.join(", ")
+        )),
+        None => Ok(content_type_for(name).to_string()),
+    }
+}
+
+fn decode_module(module: &WorkerModule) -> Result<ModuleFile, String> {
+    let name = module_name(&module.name)?;
+    let data = match module.encoding.as_deref() {
+        None | Some("") | Some("utf8") | Some("utf-8") => module.content.as_bytes().to_vec(),
+        Some("base64") => BASE64.decode(module.content.trim())
+            .map_err(|e| format!("Module {} is not valid base64: {}", name, e))?,
+        Some(other) => return Err(format!("Unsupported encoding '{}' for module {}", other, name)),
+    };
+    Ok(ModuleFile { content_type: module_type(&name, module.content_type.as_deref())?, name, data })
+}
+
+// 文本模块原样返回，其余（或非 UTF-8 内容）使用 base64
+fn encode_module(file: ModuleFile) -> WorkerModule {
+    let ModuleFile { name, content_type, mut data } = file;
+    if is_text(&content_type) {
+        match String::from_utf8(data) {
+            Ok(content) => return WorkerModule { name, content_type: Some(content_type), content, encoding: None },
+            Err(e) => data = e.into_bytes(),
+        }
+    }
+    WorkerModule {
+        name,
+        content_type: Some(content_type),
+        content: BASE64.encode(data),
+        encoding: Some("base64".to_string()),
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    if from > haystack.len() {
+        return None;
+    }
+    haystack[from..].windows(needle.len()).position(|w| w == needle).map(|i| i + from)
+}
+
+// 查找分隔符；其后必须是换行或结束标记 "--"，避免把以边界开头的数据误认为分隔符
+fn find_delimiter(body: &[u8], delimiter: &[u8], mut from: usize) -> Option<usize> {
+    loop {
+        let index = find(body, delimiter, from)?;
+        let rest = &body[index + delimiter.len()..];
+        if rest.starts_with(b"\r\n") || rest.starts_with(b"--") {
+            return Some(index);
+        }
+        from = index + 1;
+    }
+}
+
+// 解析 Content-Type / Content-Disposition 的参数（支持带引号的值）
+fn header_params(value: &str) -> Vec<(String, String)> {
+    let mut params = Vec::new();
+    let mut rest = value;
+    while let Some(index) = rest.find(';') {
+        rest = rest[index + 1..].trim_start();
+        let Some((key, after)) = rest.split_once('=') else { break };
+        let key = key.trim().to_ascii_lowercase();
+        let (value, remaining) = if let Some(quoted) = after.strip_prefix('"') {
+            let mut value = String::new();
+            let mut chars = quoted.char_indices();
+            let mut end = quoted.len();
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '\\' => {
+                        if let Some((_, escaped)) = chars.next() {
+                            value.push(escaped);
+                        }
+                    }
+                    '"' => {
+                        end = i + 1;
+                        break;
+                    }
+                    c => value.push(c),
+                }
+            }
+            (value, &quoted[end..])
+        } else {
+            let end = after.find(';').unwrap_or(after.len());
+            (after[..end].trim().to_string(), &after[end..])
+        };
+        params.push((key, value));
+        rest = remaining;
+    }
+    params
+}
+
+// 解析 multipart/form-data 内容（Cloudflare 返回的模块脚本与前端上传的模块包）
+pub fn parse_multipart(content_type: &str, body: &[u8]) -> Result<Vec<MultipartPart>, String> {
+    let boundary = header_params(content_type).into_iter()
+        .find(|(key, _)| key == "boundary")
+        .map(|(_, value)| value)
+        .filter(|b| !b.is_empty())
+        .ok_or_else(|| "Multipart content type has no boundary".to_string())?;
+    let delimiter = format!("--{}", boundary).into_bytes();
+    let separator = [b"\r\n".as_slice(), &delimiter].concat();
+
+    let mut parts = Vec::new();
+    let mut pos = find_delimiter(body, &delimiter, 0).ok_or_else(|| "Multipart body has no boundary".to_string())? + delimiter.len();
+    loop {
+        if body[pos..].starts_with(b"--") {
+            break;
+        }
+        if !body[pos..].starts_with(b"\r\n") {
+            return Err("Malformed multipart boundary".to_string());
+        }
+        pos += 2;
+
+        let (headers, data_start) = if body[pos..].starts_with(b"\r\n") {
+            ("", pos + 2)
+        } else {
+            let end = find(body, b"\r\n\r\n", pos).ok_or_else(|| "Unterminated multipart headers".to_string())?;
+            (std::str::from_utf8(&body[pos..end]).map_err(|_| "Multipart headers are not UTF-8".to_string())?, end + 4)
+        };
+        let data_end = find_delimiter(body, &separator, data_start).ok_or_else(|| "Unterminated multipart part".to_string())?;
+
+        let mut part = MultipartPart { name: String::new(), filename: None, content_type: None, data: body[data_start..data_end].to_vec() };
+        for line in headers.split("\r\n") {
+            let Some((key, value)) = line.split_once(':') else { continue };
+            match key.trim().to_ascii_lowercase().as_str() {
+                "content-disposition" => {
+                    for (param, value) in header_params(value) {
+                        match param.as_str() {
+                            "name" => part.name = value,
+                            "filename" => part.filename = Some(value),
+                            _ => {}
+                        }
+                    }
+                }
+                "content-type" => part.content_type = Some(value.trim().to_string()),
+                _ => {}
+            }
+        }
+        parts.push(part);
+        pos = data_end + separator.len();
+    }
+
+    Ok(parts)
+}
+
+fn archive_entry(path: &str, reader: impl Read, total: &mut u64, files: &mut Vec<ModuleFile>) -> Result<(), String> {
+    if path.starts_with("__MACOSX/") {
+        return Ok(());
+    }
+    let name = module_name(path)?;
+    let mut data = Vec::new();
+    reader.take(MAX_ARCHIVE_BYTES - *total + 1)
+        .read_to_end(&mut data)
+        .map_err(|e| format!("Failed to read {} from archive: {}", name, e))?;
+    *total += data.len() as u64;
+    if *total > MAX_ARCHIVE_BYTES {
+        return Err(format!("Archive expands to more than {} MB", MAX_ARCHIVE_BYTES / 1024 / 1024));
+    }
+    files.push(ModuleFile { content_type: content_type_for(&name).to_string(), name, data });
+    Ok(())
+}
+
+fn read_tar(reader: impl Read) -> Result<Vec<ModuleFile>, String> {
+    let mut archive = tar::Archive::new(reader);
+    let mut files = Vec::new();
+    let mut total = 0;
+    for entry in archive.entries().map_err(|e| format!("Invalid tar archive: {}", e))? {
+        let entry = entry.map_err(|e| format!("Invalid tar archive: {}", e))?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path().map_err(|e| format!("Invalid tar entry path: {}", e))?.to_string_lossy().to_string();
+        archive_entry(&path, entry, &mut total, &mut files)?;
+    }
+    Ok(files)
+}
+
+// 解压 tar、tar.gz 或 zip 归档，按文件内容的魔数判断格式
+pub fn extract_archive(bytes: &[u8]) -> Result<Vec<ModuleFile>, String> {
+    if bytes.starts_with(b"PK\x03\x04") {
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| format!("Invalid zip archive: {}", e))?;
+        let mut files = Vec::new();
+        let mut total = 0;
+        for index in 0..archive.len() {
+            let file = archive.by_index(index).map_err(|e| format!("Invalid zip archive: {}", e))?;
+            if file.is_dir() {
+                continue;
+            }
+            let path = file.name().to_string();
+            archive_entry(&path, file, &mut total, &mut files)?;
+        }
+        Ok(files)
+    } else if bytes.starts_with(&[0x1f, 0x8b]) {
+        read_tar(flate2::read::GzDecoder::new(bytes))
+    } else {
+        read_tar(bytes)
+    }
+}
+
+// 汇总单模块内容、模块列表、归档与 multipart 上传的文件，并确定主模块
+pub fn collect_modules(request: &UploadWorkerRequest, mut files: Vec<ModuleFile>) -> Result<(String, Vec<ModuleFile>), String> {
+    if !request.script_content.is_empty() {
+        let name = module_name(request.main_module.as_deref().unwrap_or(MAIN_MODULE))?;
+        files.push(ModuleFile {
+            content_type: module_type(&name, None)?,
+            name,
+            data: request.script_content.as_bytes().to_vec(),
+        });
+    }
+    for module in &request.modules {
+        files.push(decode_module(module)?);
+    }
+    if let Some(archive) = request.archive.as_deref().filter(|a| !a.trim().is_empty()) {
+        let bytes = BASE64.decode(archive.trim()).map_err(|e| format!("Archive is not valid base64: {}", e))?;
+        files.extend(extract_archive(&bytes)?);
+    }
+
+    if files.is_empty() {
+        return Err("No modules to upload".to_string());
+    }
+    let mut names = HashSet::new();
+    for file in &files {
+        if !names.insert(file.name.as_str()) {
+            return Err(format!("Duplicate module '{}'", file.name));
+        }
+    }
+
+    let main_module = match &request.main_module {
+        Some(main) => module_name(main)?,
+        None if files.len() == 1 => files[0].name.clone(),
+        None => return Err("main_module is required when uploading several modules".to_string()),
+    };
+    let main = files.iter()
+        .find(|f| f.name == main_module)
+        .ok_or_else(|| format!("Main module '{}' is not part of the upload", main_module))?;
+    if !MAIN_MODULE_TYPES.contains(&main.content_type.as_str()) {
+        return Err(format!("Main module '{}' must be an ES module or Python module, got {}", main_module, main.content_type));
+    }
+
+    Ok((main_module, files))
+}
+
+fn binding_name(binding: &WorkerBinding) -> &str {
+    match binding {
+        WorkerBinding::PlainText { name, .. }
+        | WorkerBinding::SecretText { name, .. }
+        | WorkerBinding::KvNamespace { name, .. }
+        | WorkerBinding::D1 { name, .. }
+        | WorkerBinding::R2Bucket { name, .. }
+        | WorkerBinding::Queue { name, .. }
+        | WorkerBinding::Service { name, .. }
+        | WorkerBinding::DurableObjectNamespace { name, .. } => name,
+    }
+}
+
+// 绑定名会成为 env 上的属性，需是合法的 JS 标识符
+fn check_binding_name(name: &str) -> Result<(), String> {
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid binding name '{}': must be a JavaScript identifier", name))
+    }
+}
+
+// 合并 vars、secrets 与显式绑定，校验后生成上传元数据
+pub fn build_metadata(request: &UploadWorkerRequest, main_module: &str) -> Result<Value, String> {
+    let mut bindings: Vec<WorkerBinding> = request.vars.iter()
+        .map(|(name, text)| WorkerBinding::PlainText { name: name.clone(), text: text.clone() })
+        .collect();
+    bindings.extend(request.secrets.iter().map(|(name, text)| WorkerBinding::SecretText { name: name.clone(), text: text.clone() }));
+    bindings.extend(request.bindings.iter().cloned());
+
+    let mut names = HashSet::new();
+    for binding in &bindings {
+        let name = binding_name(binding);
+        check_binding_name(name)?;
+        if !names.insert(name) {
+            return Err(format!("Duplicate binding name '{}'", name));
+        }
+    }
+
+    let compatibility_date = request.compatibility_date.as_deref()
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .unwrap_or(DEFAULT_COMPATIBILITY_DATE);
+    let date = NaiveDate::parse_from_str(compatibility_date, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid compatibility date '{}' (expected YYYY-MM-DD)", compatibility_date))?;
+    if date > Utc::now().date_naive() {
+        return Err(format!("Compatibility date {} is in the future", compatibility_date));
+    }
+
+    for flag in &request.compatibility_flags {
+        if flag.is_empty() || !flag.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_') {
+            return Err(format!("Invalid compatibility flag '{}'", flag));
+        }
+    }
+
+    let mut metadata = json!({
+        "main_module": main_module,
+        "compatibility_date": compatibility_date,
+        "bindings": bindings,
+    });
+    if !request.compatibility_flags.is_empty() {
+        metadata["compatibility_flags"] = json!(request.compatibility_flags);
+    }
+    if let Some(usage_model) = &request.usage_model {
+        if !USAGE_MODELS.contains(&usage_model.as_str()) {
+            return Err(format!(
+                "Unsupported usage model '{}' (expected one of: {})",
+                usage_model,
+                USAGE_MODELS.join(", ")
+            ));
+        }
+        metadata["usage_model"] = json!(usage_model);
+    }
+    match request.placement.as_deref() {
+        None | Some("") | Some("off") => {}
+        Some("smart") => metadata["placement"] = json!({ "mode": "smart" }),
+        Some(other) => return Err(format!("Unsupported placement mode '{}' (expected smart or off)", other)),
+    }
+    if request.keep_secrets {
+        metadata["keep_bindings"] = json!(["secret_text", "secret_key"]);
+    }
+
+    Ok(metadata)
+}
+
+// files 为 multipart 上传中直接携带的模块文件
+pub async fn upload(client: &CloudflareClient, request: &UploadWorkerRequest, files: Vec<ModuleFile>) -> Result<Value, String> {
+    let (main_module, modules) = collect_modules(request, files)?;
+    let metadata = build_metadata(request, &main_module)?;
+    client.upload_worker(&request.account_id, &request.script_name, &modules, &metadata).await
+}
+
+// 解析前端上传的 multipart 模块包：credentials 与 metadata 为 JSON 字段，
+// archive 为归档文件，其余字段名即模块名
+pub fn parse_bundle(content_type: &str, body: &[u8]) -> Result<(CloudflareCredentials, UploadWorkerRequest, Vec<ModuleFile>), String> {
+    let mut credentials = None;
+    let mut request = None;
+    let mut files = Vec::new();
+
+    for part in parse_multipart(content_type, body)? {
+        match part.name.as_str() {
+            "credentials" => {
+                credentials = Some(serde_json::from_slice(&part.data).map_err(|e| format!("Invalid credentials: {}", e))?);
+            }
+            "metadata" => {
+                request = Some(serde_json::from_slice(&part.data).map_err(|e| format!("Invalid metadata: {}", e))?);
+            }
+            "archive" => files.extend(extract_archive(&part.data)?),
+            _ => {
+                let name = module_name(if part.name.is_empty() { part.filename.as_deref().unwrap_or_default() } else { &part.name })?;
+                // 浏览器对未知文件类型统一使用 application/octet-stream，此时按扩展名推断
+                let declared = part.content_type.as_deref().filter(|t| !t.starts_with("application/octet-stream"));
+                files.push(ModuleFile { content_type: module_type(&name, declared)?, name, data: part.data });
+            }
+        }
+    }
+
+    Ok((
+        credentials.ok_or_else(|| "Missing credentials field".to_string())?,
+        request.ok_or_else(|| "Missing metadata field".to_string())?,
+        files,
+    ))
+}
+
+// 解析脚本下载接口的响应：模块脚本为 multipart，每个字段是一个模块；
+// Service Worker 格式的脚本直接返回源码。entrypoint 为响应头 CF-Entrypoint 中的主模块名
+pub fn parse_script(content_type: &str, entrypoint: Option<&str>, body: Vec<u8>) -> Result<WorkerScriptContent, String> {
+    if !content_type.starts_with("multipart/") {
+        let content_type = content_type.split(';').next().map(str::trim).filter(|t| !t.is_empty()).unwrap_or("application/javascript");
+        return Ok(WorkerScriptContent {
+            main_module: None,
+            modules: vec![ModuleFile { name: MAIN_MODULE.to_string(), content_type: content_type.to_string(), data: body }],
+        });
+    }
+
+    let modules: Vec<ModuleFile> = parse_multipart(content_type, &body)?
+        .into_iter()
+        .map(|part| {
+            let name = if part.name.is_empty() { part.filename.unwrap_or_default() } else { part.name };
+            let content_type = part.content_type
+                .map(|t| t.split(';').next().unwrap_or_default().trim().to_string())
+                .filter(|t| !t.is_empty())
+                .unwrap_or_else(|| content_type_for(&name).to_string());
+            ModuleFile { name, content_type, data: part.data }
+        })
+        .collect();
+
+    // 没有 CF-Entrypoint 时按上传顺序取第一个模块
+    let main_module = entrypoint
+        .filter(|e| modules.iter().any(|m| m.name == *e))
+        .map(str::to_string)
+        .or_else(|| modules.first().map(|m| m.name.clone()));
+
+    Ok(WorkerScriptContent { main_module, modules })
+}
+
+pub fn bundle(content: WorkerScriptContent) -> WorkerBundle {
+    WorkerBundle {
+        main_module: content.main_module,
+        modules: content.modules.into_iter().map(encode_module).collect(),
+    }
+}
+
+// 备份等场景中以 WorkerModule 保存的模块还原为上传文件
+pub fn decode_modules(modules: &[WorkerModule]) -> Result<Vec<ModuleFile>, String> {
+    modules.iter().map(decode_module).collect()
+}
+
+// 打包为 zip，文件路径即模块名，可直接作为 archive 重新上传
+pub fn archive(content: &WorkerScriptContent) -> Result<Vec<u8>, String> {
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for module in &content.modules {
+        writer.start_file(module.name.as_str(), options)
+            .map_err(|e| format!("Failed to add {} to archive: {}", module.name, e))?;
+        writer.write_all(&module.data)
+            .map_err(|e| format!("Failed to add {} to archive: {}", module.name, e))?;
+    }
+    let cursor = writer.finish().map_err(|e| format!("Failed to finish archive: {}", e))?;
+    Ok(cursor.into_inner())
+}
+
+// 下载脚本的全部模块
+pub async fn download(client: &CloudflareClient, account_id: &str, script_name: &str) -> Result<WorkerBundle, String> {
+    Ok(bundle(client.get_worker(account_id, script_name).await?))
+}
+
+// 脚本模块与列表接口中的元数据（etag、handlers、兼容性日期等）
+pub async fn get(client: &CloudflareClient, account_id: &str, script_name: &str) -> Result<WorkerDetails, String> {
+    let (content, script) = futures::future::join(
+        client.get_worker(account_id, script_name),
+        client.get_worker_metadata(account_id, script_name),
+    ).await;
+    let WorkerBundle { main_module, modules } = bundle(content?);
+
+    Ok(WorkerDetails { script: script?, main_module, modules })
+}
+
+pub async fn settings(client: &CloudflareClient, account_id: &str, script_name: &str) -> Result<WorkerSettings, String> {
+    client.get_worker_settings(account_id, script_name).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(parts: &[&str]) -> Vec<u8> {
+        parts.concat().into_bytes()
+    }
+
+    #[test]
+    fn parses_parts_with_quoted_boundary() {
+        let content = body(&[
+            "preamble\r\n",
+            "--abc\r\n",
+            "Content-Disposition: form-data; name=\"worker.js\"; filename=\"worker.js\"\r\n",
+            "Content-Type: application/javascript+module\r\n\r\n",
+            "export default {}\r\n",
+            "--abc\r\n",
+            "Content-Disposition: form-data; name=\"empty.txt\"\r\n\r\n",
+            "\r\n",
+            "--abc--\r\n",
+        ]);
+        let parts = parse_multipart("multipart/form-data; boundary=\"abc\"", &content).unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].name, "worker.js");
+        assert_eq!(parts[0].filename.as_deref(), Some("worker.js"));
+        assert_eq!(parts[0].content_type.as_deref(), Some("application/javascript+module"));
+        assert_eq!(parts[0].data, b"export default {}");
+        assert_eq!(parts[1].name, "empty.txt");
+        assert!(parts[1].data.is_empty());
+    }
+
+    #[test]
+    fn boundary_inside_data_is_not_a_delimiter() {
+        let content = body(&[
+            "--abc\r\n",
+            "Content-Disposition: form-data; name=\"a\"\r\n\r\n",
+            "x--abc y\r\n--abcd\r\nz\r\n",
+            "--abc--",
+        ]);
+        let parts = parse_multipart("multipart/form-data; boundary=abc", &content).unwrap();
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].data, b"x--abc y\r\n--abcd\r\nz");
+    }
+
+    #[test]
+    fn keeps_binary_data() {
+        let mut content = b"--b\r\nContent-Disposition: form-data; name=\"m.wasm\"\r\n\r\n".to_vec();
+        content.extend_from_slice(&[0, 159, 146, 150, 13, 10]);
+        content.extend_from_slice(b"\r\n--b--\r\n");
+        let parts = parse_multipart("multipart/form-data; boundary=b", &content).unwrap();
+        assert_eq!(parts[0].data, vec![0, 159, 146, 150, 13, 10]);
+    }
+
+    #[test]
+    fn rejects_missing_or_unterminated_boundaries() {
+        assert!(parse_multipart("multipart/form-data", b"--abc--").is_err());
+        assert!(parse_multipart("multipart/form-data; boundary=abc", b"no delimiter here").is_err());
+        let unterminated = b"--abc\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\ndata";
+        assert!(parse_multipart("multipart/form-data; boundary=abc", unterminated).is_err());
+    }
+}
@@ -0,0 +1,183 @@
+use crate::cloudflare::CloudflareClient;
+use crate::models::*;
+use actix_web::web::Bytes;
+use futures::{SinkExt, Stream, StreamExt};
+use serde_json::{json, Value};
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::Message;
+
+// tail WebSocket 使用的子协议
+const TAIL_PROTOCOL: &str = "trace-v1";
+
+// 定期向浏览器写入注释行，以便及时发现连接已断开
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+// 浏览器读取过慢时最多缓存的事件数
+const EVENT_BUFFER: usize = 256;
+
+const METHODS: &[&str] = &["GET", "HEAD", "POST", "PUT", "DELETE", "CONNECT", "OPTIONS", "TRACE", "PATCH"];
+
+// 将状态映射为 tail 事件的 outcome（与 wrangler tail --status 一致）
+fn outcomes(status: &str) -> Result<&'static [&'static str], String> {
+    match status.trim().to_ascii_lowercase().as_str() {
+        "ok" => Ok(&["ok"]),
+        "error" => Ok(&["exception", "exceededCpu", "exceededMemory", "unknown"]),
+        "canceled" | "cancelled" => Ok(&["canceled"]),
+        other => Err(format!("Unknown status filter '{}' (expected ok, error or canceled)", other)),
+    }
+}
+
+// 校验过滤条件并生成 tail 会话的过滤消息
+pub fn filter_message(filters: &WorkerTailFilters) -> Result<Value, String> {
+    let mut list = Vec::new();
+
+    if let Some(rate) = filters.sampling_rate {
+        if !(rate > 0.0 && rate <= 1.0) {
+            return Err(format!("Sampling rate must be greater than 0 and at most 1 (got {})", rate));
+        }
+        list.push(json!({ "sampling_rate": rate }));
+    }
+
+    if !filters.status.is_empty() {
+        let mut outcome: Vec<&str> = Vec::new();
+        for status in &filters.status {
+            for value in outcomes(status)? {
+                if !outcome.contains(value) {
+                    outcome.push(value);
+                }
+            }
+        }
+        list.push(json!({ "outcome": outcome }));
+    }
+
+    if !filters.method.is_empty() {
+        let mut methods = Vec::new();
+        for method in &filters.method {
+            let method = method.trim().to_ascii_uppercase();
+            if !METHODS.contains(&method.as_str()) {
+                return Err(format!("Unknown HTTP method '{}'", method));
+            }
+            methods.push(method);
+        }
+        list.push(json!({ "method": methods }));
+    }
+
+    if !filters.client_ip.is_empty() {
+        let mut ips = Vec::new();
+        for ip in &filters.client_ip {
+            let ip: IpAddr = ip.trim().parse().map_err(|_| format!("Invalid client IP '{}'", ip))?;
+            ips.push(ip.to_string());
+        }
+        list.push(json!({ "client_ip": ips }));
+    }
+
+    if let Some(search) = filters.search.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        list.push(json!({ "query": search }));
+    }
+
+    Ok(json!({ "filters": list, "debug": false }))
+}
+
+fn sse(event: &str, data: &Value) -> Bytes {
+    Bytes::from(format!("event: {}\ndata: {}\n\n", event, data))
+}
+
+// tail 事件为 JSON（文本或二进制帧），重新序列化以保证是单行的 SSE data
+fn trace_event(payload: &[u8]) -> Bytes {
+    let data = serde_json::from_slice(payload)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(payload).to_string()));
+    sse("event", &data)
+}
+
+// 创建 tail 会话并连接其 WebSocket，返回发给浏览器的 SSE 事件流。
+// 浏览器断开（事件流被丢弃）或 tail 结束后，关闭 WebSocket 并删除会话
+pub async fn start(
+    client: CloudflareClient,
+    request: &TailWorkerRequest,
+) -> Result<impl Stream<Item = Result<Bytes, actix_web::Error>>, String> {
+    let filters = filter_message(&request.filters)?;
+    let account_id = request.account_id.clone();
+    let script_name = request.script_name.clone();
+
+    let tail = client.create_worker_tail(&account_id, &script_name).await?;
+
+    let connected = async {
+        let mut ws_request = tail.url.as_str()
+            .into_client_request()
+            .map_err(|e| format!("Invalid tail URL: {}", e))?;
+        ws_request.headers_mut().insert("Sec-WebSocket-Protocol", HeaderValue::from_static(TAIL_PROTOCOL));
+        let (mut socket, _) = tokio_tungstenite::connect_async(ws_request)
+            .await
+            .map_err(|e| format!("Failed to connect to tail: {}", e))?;
+        socket.send(Message::Text(filters.to_string()))
+            .await
+            .map_err(|e| format!("Failed to send tail filters: {}", e))?;
+        Ok::<_, String>(socket)
+    }.await;
+
+    let mut socket = match connected {
+        Ok(socket) => socket,
+        Err(e) => {
+            if let Err(cleanup) = client.delete_worker_tail(&account_id, &script_name, &tail.id).await {
+                log::warn!("Failed to delete tail {} of {}: {}", tail.id, script_name, cleanup);
+            }
+            return Err(e);
+        }
+    };
+
+    log::info!("Tail {} started for Worker {}", tail.id, script_name);
+
+    let (tx, rx) = mpsc::channel::<Bytes>(EVENT_BUFFER);
+    let ready = sse("ready", &json!({ "id": tail.id, "expires_at": tail.expires_at }));
+
+    actix_web::rt::spawn(async move {
+        let mut keepalive = tokio::time::interval(KEEPALIVE_INTERVAL);
+        let mut events = 0u64;
+
+        if tx.send(ready).await.is_ok() {
+            loop {
+                tokio::select! {
+                    _ = tx.closed() => break,
+                    _ = keepalive.tick() => {
+                        if tx.send(Bytes::from_static(b": keepalive\n\n")).await.is_err() {
+                            break;
+                        }
+                    }
+                    message = socket.next() => {
+                        let chunk = match message {
+                            Some(Ok(Message::Text(text))) => trace_event(text.as_bytes()),
+                            Some(Ok(Message::Binary(data))) => trace_event(&data),
+                            Some(Ok(Message::Close(_))) | None => {
+                                let _ = tx.send(sse("end", &json!({ "reason": "Tail closed by Cloudflare" }))).await;
+                                break;
+                            }
+                            Some(Ok(_)) => continue,
+                            Some(Err(e)) => {
+                                let _ = tx.send(sse("error", &json!({ "message": format!("Tail connection failed: {}", e) }))).await;
+                                break;
+                            }
+                        };
+                        events += 1;
+                        if tx.send(chunk).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        let _ = socket.close(None).await;
+        match client.delete_worker_tail(&account_id, &script_name, &tail.id).await {
+            Ok(()) => log::info!("Tail {} for Worker {} closed after {} events", tail.id, script_name, events),
+            Err(e) => log::warn!("Failed to delete tail {} of {}: {}", tail.id, script_name, e),
+        }
+    });
+
+    Ok(futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (Ok(chunk), rx))
+    }))
+}
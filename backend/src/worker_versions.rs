@@ -0,0 +1,151 @@
+use crate::cloudflare::CloudflareClient;
+use crate::models::*;
+use crate::worker_scripts;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+
+// Cloudflare 的百分比部署最多同时包含两个版本
+const MAX_DEPLOYMENT_VERSIONS: usize = 2;
+
+// 注释长度限制
+const MAX_MESSAGE_LEN: usize = 100;
+const MAX_TAG_LEN: usize = 25;
+
+fn annotations(message: Option<&str>, tag: Option<&str>) -> Result<WorkerAnnotations, String> {
+    let message = message.map(str::trim).filter(|m| !m.is_empty());
+    let tag = tag.map(str::trim).filter(|t| !t.is_empty());
+    if message.is_some_and(|m| m.chars().count() > MAX_MESSAGE_LEN) {
+        return Err(format!("Message must be at most {} characters", MAX_MESSAGE_LEN));
+    }
+    if tag.is_some_and(|t| t.chars().count() > MAX_TAG_LEN) {
+        return Err(format!("Tag must be at most {} characters", MAX_TAG_LEN));
+    }
+    Ok(WorkerAnnotations {
+        message: message.map(str::to_string),
+        tag: tag.map(str::to_string),
+        triggered_by: None,
+    })
+}
+
+// 校验流量分配：一到两个不同版本，百分比之和为 100
+pub fn validate_split(versions: &[DeploymentVersion]) -> Result<(), String> {
+    if versions.is_empty() || versions.len() > MAX_DEPLOYMENT_VERSIONS {
+        return Err(format!("A deployment must include 1 to {} versions", MAX_DEPLOYMENT_VERSIONS));
+    }
+
+    let mut seen = HashSet::new();
+    for version in versions {
+        if version.version_id.trim().is_empty() {
+            return Err("Version ID is required".to_string());
+        }
+        if !seen.insert(version.version_id.as_str()) {
+            return Err(format!("Version {} is listed more than once", version.version_id));
+        }
+        if !(0.0..=100.0).contains(&version.percentage) {
+            return Err(format!("Percentage for version {} must be between 0 and 100", version.version_id));
+        }
+    }
+
+    let total: f64 = versions.iter().map(|v| v.percentage).sum();
+    if (total - 100.0).abs() > 0.01 {
+        return Err(format!("Percentages must add up to 100 (got {})", total));
+    }
+
+    Ok(())
+}
+
+pub async fn list_versions(client: &CloudflareClient, account_id: &str, script_name: &str) -> Result<Vec<WorkerVersion>, String> {
+    let mut versions = client.list_worker_versions(account_id, script_name).await?;
+    versions.sort_by_key(|v| std::cmp::Reverse(v.number));
+    Ok(versions)
+}
+
+// 上传新版本但不部署，之后可通过部署接口逐步放量
+pub async fn upload_version(client: &CloudflareClient, request: &UploadWorkerVersionRequest) -> Result<WorkerVersion, String> {
+    let upload = &request.upload;
+    let (main_module, modules) = worker_scripts::collect_modules(upload, Vec::new())?;
+    let mut metadata = worker_scripts::build_metadata(upload, &main_module)?;
+    metadata["annotations"] = json!(annotations(request.message.as_deref(), request.tag.as_deref())?);
+
+    client.upload_worker_version(&upload.account_id, &upload.script_name, &modules, &metadata).await
+}
+
+fn deployment_body(versions: &[DeploymentVersion], message: Option<&str>) -> Result<Value, String> {
+    let versions: Vec<Value> = versions.iter()
+        .map(|v| json!({ "version_id": v.version_id, "percentage": v.percentage }))
+        .collect();
+    Ok(json!({
+        "strategy": "percentage",
+        "versions": versions,
+        "annotations": annotations(message, None)?,
+    }))
+}
+
+pub async fn deploy(client: &CloudflareClient, request: &CreateWorkerDeploymentRequest) -> Result<WorkerDeployment, String> {
+    validate_split(&request.versions)?;
+    let body = deployment_body(&request.versions, request.message.as_deref())?;
+
+    log::info!(
+        "Deploying Worker {} as {}",
+        request.script_name,
+        request.versions.iter().map(|v| format!("{}@{}%", v.version_id, v.percentage)).collect::<Vec<_>>().join(", ")
+    );
+
+    client.create_worker_deployment(&request.account_id, &request.script_name, &body, request.force).await
+}
+
+// 部署历史（从新到旧），每个版本附上版本号
+pub async fn history(client: &CloudflareClient, account_id: &str, script_name: &str) -> Result<WorkerDeploymentHistory, String> {
+    let (deployments, versions) = futures::future::join(
+        client.list_worker_deployments(account_id, script_name),
+        client.list_worker_versions(account_id, script_name),
+    ).await;
+    let mut deployments = deployments?;
+
+    // 版本号仅用于展示，获取失败不影响历史
+    let numbers: HashMap<String, u64> = versions.unwrap_or_default()
+        .into_iter()
+        .filter_map(|v| v.number.map(|n| (v.id, n)))
+        .collect();
+    for deployment in &mut deployments {
+        for version in &mut deployment.versions {
+            version.number = numbers.get(&version.version_id).copied();
+        }
+    }
+    deployments.sort_by(|a, b| b.created_on.cmp(&a.created_on));
+
+    Ok(WorkerDeploymentHistory {
+        script_name: script_name.to_string(),
+        active: deployments.first().cloned(),
+        deployments,
+    })
+}
+
+// 上一次部署中流量占比最高的版本
+fn previous_version(deployments: &[WorkerDeployment]) -> Result<String, String> {
+    let previous = deployments.get(1).ok_or("No previous deployment to roll back to")?;
+    previous.versions.iter()
+        .max_by(|a, b| a.percentage.total_cmp(&b.percentage))
+        .map(|v| v.version_id.clone())
+        .ok_or_else(|| format!("Deployment {} has no versions", previous.id))
+}
+
+// 回滚：把指定版本（默认上一次部署的版本）以 100% 重新部署
+pub async fn rollback(client: &CloudflareClient, request: &RollbackWorkerRequest) -> Result<WorkerDeployment, String> {
+    let version_id = match request.version_id.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+        Some(version_id) => version_id.to_string(),
+        None => {
+            let history = history(client, &request.account_id, &request.script_name).await?;
+            previous_version(&history.deployments)?
+        }
+    };
+
+    let message = request.message.clone().unwrap_or_else(|| format!("Rollback to {}", version_id));
+    let versions = [DeploymentVersion { version_id: version_id.clone(), percentage: 100.0, number: None }];
+    let body = deployment_body(&versions, Some(&message))?;
+
+    log::info!("Rolling back Worker {} to version {}", request.script_name, version_id);
+
+    // 回滚需要覆盖当前部署，即使它来自 API 以外的来源
+    client.create_worker_deployment(&request.account_id, &request.script_name, &body, true).await
+}
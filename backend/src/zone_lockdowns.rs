@@ -0,0 +1,140 @@
+use crate::access_rules;
+use crate::cloudflare::CloudflareClient;
+use crate::custom_rules;
+use crate::expression;
+use crate::models::*;
+use crate::rate_limit_rules;
+
+// 去掉协议前缀；Zone Lockdown 的 URL 不含协议
+fn normalize_url(url: &str) -> String {
+    let url = url.trim();
+    url.strip_prefix("https://").or_else(|| url.strip_prefix("http://")).unwrap_or(url).to_string()
+}
+
+// 校验 URL 与 IP 配置并规范化（ip6 统一为 ip，范围取网络地址）
+pub fn validate(lockdown: &ZoneLockdown) -> Result<ZoneLockdown, String> {
+    let urls: Vec<String> = lockdown.urls.iter()
+        .map(|u| normalize_url(u))
+        .filter(|u| !u.is_empty())
+        .collect();
+    if urls.is_empty() {
+        return Err("At least one URL is required".to_string());
+    }
+
+    if lockdown.configurations.is_empty() {
+        return Err("At least one IP address or range is required".to_string());
+    }
+    let mut configurations = Vec::with_capacity(lockdown.configurations.len());
+    for config in &lockdown.configurations {
+        let classified = access_rules::classify(&config.value)?;
+        let target = match classified.target.as_str() {
+            "ip" | "ip6" => "ip",
+            "ip_range" => "ip_range",
+            _ => return Err(format!("{}: Zone Lockdown only accepts IP addresses and ranges", config.value)),
+        };
+        configurations.push(AccessRuleConfiguration { target: target.to_string(), value: classified.value });
+    }
+
+    Ok(ZoneLockdown {
+        urls,
+        configurations,
+        ..lockdown.clone()
+    })
+}
+
+pub async fn list_lockdowns(client: &CloudflareClient, zone_id: &str) -> Result<Vec<ZoneLockdown>, String> {
+    client.list_zone_lockdowns(zone_id).await
+}
+
+pub async fn create_lockdown(client: &CloudflareClient, zone_id: &str, lockdown: &ZoneLockdown) -> Result<ZoneLockdown, String> {
+    let lockdown = validate(lockdown)?;
+    client.save_zone_lockdown(zone_id, None, &lockdown).await
+}
+
+pub async fn update_lockdown(
+    client: &CloudflareClient,
+    zone_id: &str,
+    lockdown_id: &str,
+    lockdown: &ZoneLockdown,
+) -> Result<ZoneLockdown, String> {
+    let lockdown = validate(lockdown)?;
+    client.save_zone_lockdown(zone_id, Some(lockdown_id), &lockdown).await
+}
+
+pub async fn delete_lockdown(client: &CloudflareClient, zone_id: &str, lockdown_id: &str) -> Result<String, String> {
+    client.delete_zone_lockdown(zone_id, lockdown_id).await
+}
+
+// 将 Zone Lockdown 转换为自定义规则：匹配任一 URL 且来源 IP 不在列表中时拦截
+pub fn convert_legacy(lockdown: &ZoneLockdown) -> Result<MigratedRule, String> {
+    let lockdown = validate(lockdown)?;
+    let mut notes = Vec::new();
+
+    let mut url_matches = Vec::new();
+    for url in &lockdown.urls {
+        let without_query = match url.split_once('?') {
+            Some((base, _)) => {
+                notes.push(format!("Query string in {} is not matched by the converted rule", url));
+                base
+            }
+            None => url.as_str(),
+        };
+        let clauses = rate_limit_rules::url_clauses(without_query);
+        if clauses.is_empty() {
+            // URL 匹配全部请求
+            url_matches.clear();
+            break;
+        }
+        url_matches.push(format!("({})", clauses.join(" and ")));
+    }
+
+    let ips: Vec<&str> = lockdown.configurations.iter().map(|c| c.value.as_str()).collect();
+    let not_allowed = format!("not ip.src in {{{}}}", ips.join(" "));
+    let expression = if url_matches.is_empty() {
+        not_allowed
+    } else {
+        format!("({}) and {}", url_matches.join(" or "), not_allowed)
+    };
+    expression::validate(&expression)?;
+
+    if lockdown.priority.is_some() {
+        notes.push("Lockdown priority has no equivalent; review the order of the converted rule".to_string());
+    }
+    if lockdown.paused {
+        notes.push("Lockdown was paused; converted rule is disabled".to_string());
+    }
+
+    Ok(MigratedRule {
+        legacy_id: lockdown.id.clone(),
+        rule: RulesetRule {
+            id: None,
+            expression,
+            action: "block".to_string(),
+            description: Some(match lockdown.description.as_deref().filter(|d| !d.is_empty()) {
+                Some(description) => description.to_string(),
+                None => format!("Zone Lockdown: {}", lockdown.urls.join(", ")),
+            }),
+            enabled: !lockdown.paused,
+            action_parameters: None,
+            ratelimit: None,
+            logging: None,
+            rule_ref: lockdown.id.clone(),
+            position: None,
+        },
+        notes,
+    })
+}
+
+// 迁移 Zone 的全部 Zone Lockdown 规则到自定义规则
+pub async fn migrate(client: &CloudflareClient, request: &MigrateLegacyRulesRequest) -> Result<RuleMigrationReport, String> {
+    let zone_id = request.zone_id.as_str();
+    custom_rules::migrate_legacy(
+        client,
+        request,
+        custom_rules::PHASE,
+        "zone lockdown",
+        client.list_zone_lockdowns(zone_id),
+        |item| convert_legacy(item).map_err(|e| format!("{}: {}", item.id.as_deref().unwrap_or("?"), e)),
+        move |legacy_id| async move { client.delete_zone_lockdown(zone_id, &legacy_id).await },
+    ).await
+}
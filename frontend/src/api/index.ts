@@ -166,7 +166,21 @@ export interface DeployWorkerRequest {
   target_url: string
   access_domain: string
   cache_ttl: number
-  cdn_node: string
+  cdn_node?: string
+}
+
+export interface WorkerDeployStep {
+  step: 'upload' | 'route' | 'dns'
+  status: 'ok' | 'skipped' | 'failed'
+  message: string
+}
+
+export interface WorkerDeployReport {
+  script_name: string
+  account_id: string
+  route_pattern: string
+  success: boolean
+  steps: WorkerDeployStep[]
 }
 
 export interface Worker {
@@ -174,6 +188,22 @@ export interface Worker {
   etag?: string
   created_on?: string
   modified_on?: string
+  handlers?: string[]
+  compatibility_date?: string
+  compatibility_flags?: string[]
+  usage_model?: string
+}
+
+export interface WorkerModule {
+  name: string
+  content_type?: string
+  content: string
+  encoding?: 'base64'
+}
+
+export interface WorkerDetails extends Worker {
+  main_module: string | null
+  modules: WorkerModule[]
 }
 
 export interface WorkerRoute {
@@ -520,7 +550,7 @@ export const cloudflareApi = {
   },
 
   // Worker 部署
-  async deployWorker(request: DeployWorkerRequest): Promise<string> {
+  async deployWorker(request: DeployWorkerRequest): Promise<WorkerDeployReport> {
     const res = await api.post('/cloudflare/workers/deploy', request)
     return res.data
   },
@@ -532,7 +562,7 @@ export const cloudflareApi = {
   },
 
   // 获取单个 Worker
-  async getWorker(accountId: string, scriptName: string): Promise<string> {
+  async getWorker(accountId: string, scriptName: string): Promise<WorkerDetails> {
     const res = await api.post('/cloudflare/workers/get', {
       account_id: accountId,
       script_name: scriptName